@@ -1,6 +1,10 @@
 use core::fmt;
 use core::marker::PhantomData;
 
+use crate::crc;
+use crate::emmc::EmmcVersion;
+use crate::sd::SDSpecVersion;
+
 /// Types of SD Card
 #[derive(Debug, Copy, Clone)]
 #[non_exhaustive]
@@ -17,6 +21,387 @@ impl Default for CardCapacity {
     }
 }
 
+/// Specification version of an SD card or eMMC device
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CardVersion {
+    /// SD card, from the SCR
+    Sd(SDSpecVersion),
+    /// eMMC device, from the EXT_CSD
+    Emmc(EmmcVersion),
+}
+
+impl From<SDSpecVersion> for CardVersion {
+    fn from(version: SDSpecVersion) -> Self {
+        CardVersion::Sd(version)
+    }
+}
+
+impl From<EmmcVersion> for CardVersion {
+    fn from(version: EmmcVersion) -> Self {
+        CardVersion::Emmc(version)
+    }
+}
+
+/// How the raw value of a register field is decoded
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Decode {
+    /// An integer, or a flag for single bit fields
+    Int,
+    /// ASCII characters, the first one in the most significant byte
+    Ascii,
+    /// The name of each value, indexed by the raw value. Reserved values
+    /// have an empty name
+    Names(&'static [&'static str]),
+}
+
+/// Name and bit range of a register field
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FieldDesc {
+    /// Field name, as used in the specification
+    pub name: &'static str,
+    /// Most significant bit
+    pub msb: u16,
+    /// Least significant bit
+    pub lsb: u16,
+    /// Decoding of the raw value
+    pub decode: Decode,
+}
+
+impl FieldDesc {
+    pub(crate) const fn new(name: &'static str, msb: u16, lsb: u16) -> Self {
+        Self {
+            name,
+            msb,
+            lsb,
+            decode: Decode::Int,
+        }
+    }
+    pub(crate) const fn ascii(name: &'static str, msb: u16, lsb: u16) -> Self {
+        Self {
+            name,
+            msb,
+            lsb,
+            decode: Decode::Ascii,
+        }
+    }
+    pub(crate) const fn names(
+        name: &'static str,
+        msb: u16,
+        lsb: u16,
+        names: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            msb,
+            lsb,
+            decode: Decode::Names(names),
+        }
+    }
+}
+
+/// A register field and its raw value
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Field {
+    /// Name and bit range
+    pub desc: FieldDesc,
+    /// Raw value of the field
+    pub value: u64,
+}
+
+impl Field {
+    /// The value decoded as described by [`FieldDesc::decode`]. Values
+    /// without a name are returned as integers
+    pub fn decoded(&self) -> Value {
+        match self.desc.decode {
+            Decode::Int if self.desc.msb == self.desc.lsb => Value::Flag(self.value != 0),
+            Decode::Int => Value::Int(self.value),
+            Decode::Ascii => {
+                let len = (self.desc.msb - self.desc.lsb + 1) / 8;
+                let mut ascii = Ascii {
+                    bytes: [0; 8],
+                    len: len.min(8) as u8,
+                };
+                for (i, byte) in ascii.bytes[..usize::from(ascii.len)].iter_mut().enumerate() {
+                    *byte = (self.value >> (8 * (usize::from(ascii.len) - 1 - i))) as u8;
+                }
+                Value::Ascii(ascii)
+            }
+            Decode::Names(names) => match names.get(self.value as usize) {
+                Some(name) if !name.is_empty() => Value::Name(name),
+                _ => Value::Int(self.value),
+            },
+        }
+    }
+}
+
+/// Decoded value of a register [`Field`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Value {
+    /// Single bit field
+    Flag(bool),
+    /// Integer field
+    Int(u64),
+    /// Named value, eg. a state or version
+    Name(&'static str),
+    /// Text field, eg. a product name
+    Ascii(Ascii),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Flag(flag) => write!(f, "{}", flag),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Name(name) => f.write_str(name),
+            Value::Ascii(ascii) => f.write_str(ascii.as_str()),
+        }
+    }
+}
+
+/// Up to 8 ASCII characters of a register field
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Ascii {
+    bytes: [u8; 8],
+    len: u8,
+}
+
+impl Ascii {
+    /// The characters, or `"?"` if they are not valid UTF-8
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..usize::from(self.len)]).unwrap_or("?")
+    }
+}
+
+/// Names of the CURRENT_STATE values of the card status
+pub(crate) static CURRENT_STATE_NAMES: &[&str] = &[
+    "idle", "ready", "ident", "stby", "tran", "data", "rcv", "prg", "dis", "btst", "slp",
+];
+
+/// Registers that can describe their fields
+///
+/// ```
+/// # use sdio_host::sd::{Register, OCR, SD};
+/// let ocr = OCR::<SD>::from(0xC0FF_8000);
+/// for field in ocr.fields() {
+///     // Render field.desc.name, field.desc.msb ..= field.desc.lsb, field.decoded()
+/// }
+/// let ccs = ocr.fields().find(|f| f.desc.name == "CCS").unwrap();
+/// assert_eq!(ccs.value, 1);
+/// ```
+pub trait Register {
+    /// The fields of the register, most significant first
+    fn fields(&self) -> Fields<'_>;
+}
+
+/// Register contents read by [`Fields`]
+#[derive(Debug, Clone)]
+enum Bits<'a> {
+    /// Bit n is in `words[n / 32]`
+    Words([u32; 16]),
+    /// EXT_CSD words, bit n is bit n % 8 of byte n / 8
+    ExtCsd(&'a [u32]),
+}
+
+impl Bits<'_> {
+    fn bit(&self, n: u16) -> u32 {
+        let n = usize::from(n);
+        match self {
+            Bits::Words(words) => words[n / 32] >> (n % 32) & 1,
+            Bits::ExtCsd(words) => {
+                let byte = n / 8;
+                words[byte / 4] >> (24 - 8 * (byte % 4) + n % 8) & 1
+            }
+        }
+    }
+}
+
+/// Iterator over the fields of a [Register]
+#[derive(Debug, Clone)]
+pub struct Fields<'a> {
+    tables: [&'static [FieldDesc]; 3],
+    bits: Bits<'a>,
+    table: usize,
+    index: usize,
+}
+
+impl<'a> Fields<'a> {
+    /// `words` are little endian: bit n is in `words[n / 32]`
+    pub(crate) fn new(table: &'static [FieldDesc], words: &[u32]) -> Self {
+        Self::chain([table, &[], &[]], words)
+    }
+    /// Fields of several tables, in order
+    pub(crate) fn chain(tables: [&'static [FieldDesc]; 3], words: &[u32]) -> Self {
+        let mut bits = [0; 16];
+        bits[..words.len()].copy_from_slice(words);
+        Self {
+            tables,
+            bits: Bits::Words(bits),
+            table: 0,
+            index: 0,
+        }
+    }
+    /// Fields of an EXT_CSD, bit n is bit n % 8 of byte n / 8
+    pub(crate) fn ext_csd(table: &'static [FieldDesc], words: &'a [u32]) -> Self {
+        Self {
+            tables: [table, &[], &[]],
+            bits: Bits::ExtCsd(words),
+            table: 0,
+            index: 0,
+        }
+    }
+}
+
+impl Iterator for Fields<'_> {
+    type Item = Field;
+
+    fn next(&mut self) -> Option<Field> {
+        let desc = loop {
+            let table = self.tables.get(self.table)?;
+            match table.get(self.index) {
+                Some(desc) => break *desc,
+                None => {
+                    self.table += 1;
+                    self.index = 0;
+                }
+            }
+        };
+        self.index += 1;
+        let value = (desc.lsb..=desc.msb)
+            .rev()
+            .fold(0, |value, bit| value << 1 | u64::from(self.bits.bit(bit)));
+        Some(Field { desc, value })
+    }
+}
+
+/// Registers with a sanity check of their contents
+pub trait Plausible {
+    /// The CRC is valid and reserved bits are zero
+    fn is_plausible(&self) -> bool;
+}
+
+/// How dubious register contents and relaxed timings are treated
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Strictness {
+    /// Accept registers as read. Relaxed timings configured by the host
+    /// are used
+    Lenient,
+    /// Reject registers that fail their sanity check and always use the
+    /// waits mandated by the specification. For qualification testing
+    Strict,
+}
+
+impl Default for Strictness {
+    fn default() -> Self {
+        Strictness::Lenient
+    }
+}
+
+/// The register content failed its sanity check
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidRegister;
+
+impl Strictness {
+    /// Create a register from its raw representation, checking it in
+    /// strict mode
+    ///
+    /// ```
+    /// # use sdio_host::sd::{Strictness, CID, SD};
+    /// let garbled = [0, 0, 0, 0x0300_0000];
+    /// assert!(Strictness::Lenient.parse::<CID<SD>, _>(garbled).is_ok());
+    /// assert!(Strictness::Strict.parse::<CID<SD>, _>(garbled).is_err());
+    /// ```
+    pub fn parse<T, W>(self, raw: W) -> Result<T, InvalidRegister>
+    where
+        T: From<W> + Plausible,
+    {
+        let register = T::from(raw);
+        if self == Strictness::Strict && !register.is_plausible() {
+            Err(InvalidRegister)
+        } else {
+            Ok(register)
+        }
+    }
+}
+
+/// Block device geometry, for aligning filesystem structures
+///
+/// Created with `Geometry::sd` or `Geometry::emmc`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Geometry {
+    /// Block size in bytes
+    pub block_size: u32,
+    /// Number of blocks
+    pub block_count: u64,
+    /// Erase unit in blocks
+    pub erase_sector_blocks: u32,
+    /// Allocation unit in blocks. Zero if unknown
+    pub au_size: u32,
+}
+
+impl Geometry {
+    /// Capacity in bytes
+    pub fn capacity_bytes(&self) -> u64 {
+        self.block_count * u64::from(self.block_size)
+    }
+    /// Recommended format parameters for the capacity of the card
+    ///
+    /// The boundary unit is raised to the allocation unit if the card
+    /// reports a larger one.
+    ///
+    /// Ref SD Specifications Part 2 File System Specification, Section 5
+    pub fn format_parameters(&self) -> FormatParameters {
+        const MB: u64 = 1024 * 1024;
+        const GB: u64 = 1024 * MB;
+
+        let capacity = self.capacity_bytes();
+        let (fat_type, sectors_per_cluster, boundary_unit) = match capacity {
+            c if c <= 8 * MB => (FatType::Fat12, 16, 16),
+            c if c <= 64 * MB => (FatType::Fat12, 32, 32),
+            c if c <= 256 * MB => (FatType::Fat16, 32, 64),
+            c if c <= GB => (FatType::Fat16, 32, 128),
+            c if c <= 2 * GB => (FatType::Fat16, 64, 128),
+            c if c < 32 * GB => (FatType::Fat32, 64, 8192),
+            c if c <= 128 * GB => (FatType::ExFat, 256, 32768),
+            c if c <= 512 * GB => (FatType::ExFat, 256, 65536),
+            _ => (FatType::ExFat, 256, 131072),
+        };
+        let boundary_unit = core::cmp::max(boundary_unit, self.au_size);
+
+        FormatParameters {
+            fat_type,
+            sectors_per_cluster,
+            boundary_unit,
+            partition_offset: boundary_unit,
+        }
+    }
+}
+
+/// File system type
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FatType {
+    Fat12,
+    Fat16,
+    Fat32,
+    ExFat,
+}
+
+/// Recommended format parameters, see [Geometry::format_parameters]
+///
+/// All sizes are in 512 byte sectors. The formatter should pad the
+/// reserved sectors or FAT so that the data area also starts on a
+/// boundary unit.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FormatParameters {
+    /// File system type
+    pub fat_type: FatType,
+    /// Cluster size
+    pub sectors_per_cluster: u32,
+    /// Boundary unit the file system structures are aligned to
+    pub boundary_unit: u32,
+    /// Start of the first partition
+    pub partition_offset: u32,
+}
+
 /// The number of data lines in use on the SDMMC bus
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[allow(missing_docs)]
@@ -176,12 +561,81 @@ impl<Ext> From<u32> for OCR<Ext> {
     }
 }
 impl<Ext> OCR<Ext> {
+    /// Raw register value
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+    /// Register word, as accepted by `From<u32>`
+    pub fn as_words(&self) -> [u32; 1] {
+        [self.0]
+    }
     /// Card power up status bit (busy)
     pub fn is_busy(&self) -> bool {
         self.0 & 0x8000_0000 == 0 // Set active LOW
     }
 }
 
+/// Order in which a host controller presents the four response registers
+/// holding a long (R2) response
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WordOrder {
+    /// The first register holds the most significant bits (eg. STM32 SDMMC
+    /// RESP1..RESP4)
+    MostSignificantFirst,
+    /// The first register holds the least significant bits (eg. SDHCI
+    /// RESP01..RESP67)
+    LeastSignificantFirst,
+}
+
+/// Whether a host controller keeps the CRC7 and end bit of an R2 response in
+/// its response registers
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CrcIncluded {
+    /// The registers hold bits \[127:0\] of the CID/CSD, including CRC7
+    Yes,
+    /// The registers hold bits \[127:8\] of the CID/CSD shifted down by 8
+    /// bits. The CRC byte of the result is set to zero
+    No,
+}
+
+/// Normalize the four response registers of an R2 response into the little
+/// endian word layout expected by [`CID`] and [`CSD`]
+///
+/// ```
+/// # use sdio_host::sd::{r2_from_registers, CrcIncluded, WordOrder, CID, SD};
+/// // SDHCI-style controller: least significant register first, CRC dropped
+/// let regs = [0x1234_5678, 0, 0, 0x0003_5344];
+/// let words = r2_from_registers(regs, WordOrder::LeastSignificantFirst, CrcIncluded::No);
+/// assert_eq!(words, [0x3456_7800, 0x12, 0, 0x0353_4400]);
+///
+/// let cid: CID<SD> = words.into();
+/// assert_eq!(cid.manufacturer_id(), 3);
+/// ```
+pub fn r2_from_registers(words: [u32; 4], order: WordOrder, crc: CrcIncluded) -> [u32; 4] {
+    let words = match order {
+        WordOrder::MostSignificantFirst => [words[3], words[2], words[1], words[0]],
+        WordOrder::LeastSignificantFirst => words,
+    };
+    match crc {
+        CrcIncluded::Yes => words,
+        CrcIncluded::No => [
+            words[0] << 8,
+            (words[1] << 8) | (words[0] >> 24),
+            (words[2] << 8) | (words[1] >> 24),
+            (words[3] << 8) | (words[2] >> 24),
+        ],
+    }
+}
+
+fn u128_to_words(inner: u128) -> [u32; 4] {
+    [
+        inner as u32,
+        (inner >> 32) as u32,
+        (inner >> 64) as u32,
+        (inner >> 96) as u32,
+    ]
+}
+
 /// Card Identification Register (CID)
 ///
 /// R2
@@ -200,6 +654,12 @@ impl<Ext> From<u128> for CID<Ext> {
         }
     }
 }
+/// From the register bytes, most significant first
+impl<Ext> From<[u8; 16]> for CID<Ext> {
+    fn from(bytes: [u8; 16]) -> Self {
+        u128::from_be_bytes(bytes).into()
+    }
+}
 /// From little endian words
 impl<Ext> From<[u32; 4]> for CID<Ext> {
     fn from(words: [u32; 4]) -> Self {
@@ -211,14 +671,190 @@ impl<Ext> From<[u32; 4]> for CID<Ext> {
     }
 }
 impl<Ext> CID<Ext> {
+    /// Raw register value
+    pub fn raw(&self) -> u128 {
+        self.inner
+    }
+    /// Little endian words, as accepted by `From<[u32; 4]>`
+    pub fn as_words(&self) -> [u32; 4] {
+        u128_to_words(self.inner)
+    }
+    /// Register bytes, most significant first, as accepted by
+    /// `From<[u8; 16]>`
+    pub fn to_bytes(&self) -> [u8; 16] {
+        self.bytes
+    }
     /// Manufacturer ID
     pub fn manufacturer_id(&self) -> u8 {
         self.bytes[0]
     }
-    #[allow(unused)]
-    fn crc7(&self) -> u8 {
+    /// CRC7 checksum field
+    pub fn crc7(&self) -> u8 {
         (self.bytes[15] >> 1) & 0x7F
     }
+    /// The CRC7 field matches the register contents
+    ///
+    /// Always false if the host controller does not store the CRC of R2
+    /// responses.
+    pub fn crc_valid(&self) -> bool {
+        crc::crc7(&self.bytes[..15]) == self.crc7()
+    }
+}
+
+/// Compact identity of a card, kept across deep sleep to check that the
+/// same card is still present before reusing its configuration
+///
+/// ```
+/// # use sdio_host::sd::{CardFingerprint, CID, SD};
+/// let cid = CID::<SD>::from(0x0353_4453_4331_3647_8012_3456_7801_2900);
+/// let fingerprint = CardFingerprint::new(&cid, 31_116_288, 0x2);
+/// let stored = fingerprint.to_bytes();
+///
+/// let restored = CardFingerprint::from_bytes(stored);
+/// assert!(restored.matches(&cid, 31_116_288));
+/// assert_eq!(restored.mode(), 0x2);
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct CardFingerprint {
+    cid_hash: u32,
+    block_count: u64,
+    mode: u32,
+}
+
+impl CardFingerprint {
+    /// Fingerprint of a card
+    ///
+    /// * `block_count` - Size of the card in 512 byte blocks
+    /// * `mode` - Negotiated bus mode, encoded by the host
+    pub fn new<Ext>(cid: &CID<Ext>, block_count: u64, mode: u32) -> Self {
+        Self {
+            cid_hash: Self::hash(cid),
+            block_count,
+            mode,
+        }
+    }
+    /// FNV-1a hash of the CID, excluding the CRC which some hosts don't
+    /// store
+    fn hash<Ext>(cid: &CID<Ext>) -> u32 {
+        cid.bytes[..15].iter().fold(0x811C_9DC5, |hash, &byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        })
+    }
+    /// The card has the same CID and size as the fingerprinted card
+    pub fn matches<Ext>(&self, cid: &CID<Ext>, block_count: u64) -> bool {
+        self.cid_hash == Self::hash(cid) && self.block_count == block_count
+    }
+    /// Size of the card in 512 byte blocks
+    pub fn block_count(&self) -> u64 {
+        self.block_count
+    }
+    /// Negotiated bus mode
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+    /// Serialize to 16 bytes for storage
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..4].copy_from_slice(&self.cid_hash.to_le_bytes());
+        bytes[4..12].copy_from_slice(&self.block_count.to_le_bytes());
+        bytes[12..].copy_from_slice(&self.mode.to_le_bytes());
+        bytes
+    }
+    /// Deserialize from [`CardFingerprint::to_bytes`]
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        let mut cid_hash = [0; 4];
+        let mut block_count = [0; 8];
+        let mut mode = [0; 4];
+        cid_hash.copy_from_slice(&bytes[..4]);
+        block_count.copy_from_slice(&bytes[4..12]);
+        mode.copy_from_slice(&bytes[12..]);
+        Self {
+            cid_hash: u32::from_le_bytes(cid_hash),
+            block_count: u64::from_le_bytes(block_count),
+            mode: u32::from_le_bytes(mode),
+        }
+    }
+}
+
+/// Identification fields of a CID, in a form shared by SD and eMMC
+///
+/// SD cards have a two character OEM ID, eMMC devices a one byte OID. The
+/// revision is the raw PRV byte, BCD major and minor version for both.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CardIdentity<'a> {
+    /// MID
+    pub manufacturer_id: u8,
+    /// OID
+    pub oem_id: u16,
+    /// PNM
+    pub product_name: &'a str,
+    /// PRV
+    pub revision: u8,
+}
+
+/// A rule of a [`CidFilter`]
+///
+/// Fields set to `None` or empty match any card.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CidRule<'a> {
+    /// Manufacturer ID
+    pub manufacturer_id: Option<u8>,
+    /// OEM ID, see [`CardIdentity::oem_id`]
+    pub oem_id: Option<u16>,
+    /// Start of the product name
+    pub product_prefix: &'a str,
+    /// Lowest product revision
+    pub min_revision: u8,
+}
+
+impl CidRule<'_> {
+    /// The card is covered by the rule
+    pub fn matches(&self, identity: &CardIdentity<'_>) -> bool {
+        self.manufacturer_id
+            .map_or(true, |mid| mid == identity.manufacturer_id)
+            && self.oem_id.map_or(true, |oid| oid == identity.oem_id)
+            && identity.product_name.starts_with(self.product_prefix)
+            && identity.revision >= self.min_revision
+    }
+}
+
+/// Restricts the cards a host accepts to a list of qualified cards, or
+/// rejects known bad ones
+///
+/// ```
+/// # use sdio_host::sd::{CidFilter, CidRule, CID, SD};
+/// static QUALIFIED: &[CidRule<'static>] = &[CidRule {
+///     manufacturer_id: Some(0x03),
+///     oem_id: None,
+///     product_prefix: "SU",
+///     min_revision: 0x80,
+/// }];
+/// let filter = CidFilter::allow(QUALIFIED);
+/// let cid = CID::<SD>::from([2197869198, 2149469225, 1429223495, 55788627]);
+/// assert!(filter.permits(&cid.identity()));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CidFilter<'a> {
+    rules: &'a [CidRule<'a>],
+    allow: bool,
+}
+
+impl<'a> CidFilter<'a> {
+    /// Accept only cards matching one of the rules
+    pub fn allow(rules: &'a [CidRule<'a>]) -> Self {
+        Self { rules, allow: true }
+    }
+    /// Accept all cards except those matching one of the rules
+    pub fn deny(rules: &'a [CidRule<'a>]) -> Self {
+        Self {
+            rules,
+            allow: false,
+        }
+    }
+    /// The card may be used
+    pub fn permits(&self, identity: &CardIdentity<'_>) -> bool {
+        self.rules.iter().any(|rule| rule.matches(identity)) == self.allow
+    }
 }
 
 /// Card Specific Data (CSD)
@@ -229,6 +865,12 @@ impl<Ext> From<u128> for CSD<Ext> {
         Self(inner, PhantomData)
     }
 }
+/// From the register bytes, most significant first
+impl<Ext> From<[u8; 16]> for CSD<Ext> {
+    fn from(bytes: [u8; 16]) -> Self {
+        u128::from_be_bytes(bytes).into()
+    }
+}
 /// From little endian words
 impl<Ext> From<[u32; 4]> for CSD<Ext> {
     fn from(words: [u32; 4]) -> Self {
@@ -241,14 +883,31 @@ impl<Ext> From<[u32; 4]> for CSD<Ext> {
 }
 
 impl<Ext> CSD<Ext> {
-    /// CSD structure version
-    pub fn version(&self) -> u8 {
+    /// Raw register value
+    pub fn raw(&self) -> u128 {
+        self.0
+    }
+    /// Little endian words, as accepted by `From<[u32; 4]>`
+    pub fn as_words(&self) -> [u32; 4] {
+        u128_to_words(self.0)
+    }
+    /// Register bytes, most significant first, as accepted by
+    /// `From<[u8; 16]>`
+    pub fn to_bytes(&self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+    /// CSD_STRUCTURE: Raw CSD structure version
+    pub fn csd_structure(&self) -> u8 {
         (self.0 >> 126) as u8 & 3
     }
     /// Maximum data transfer rate per one data line
     pub fn transfer_rate(&self) -> u8 {
         (self.0 >> 96) as u8
     }
+    /// Card Command Classes (CCC). Bit n is set if class n is supported
+    pub fn card_command_classes(&self) -> u16 {
+        (self.0 >> 84) as u16 & 0xFFF
+    }
     /// Maximum block length. In an SD Memory Card the WRITE_BL_LEN is
     /// always equal to READ_BL_LEN
     pub fn block_length(&self) -> BlockSize {
@@ -272,6 +931,24 @@ impl<Ext> CSD<Ext> {
             _ => BlockSize::Unknown,
         }
     }
+    /// READ_BL_PARTIAL: Blocks shorter than the block length can be read
+    pub fn read_block_partial(&self) -> bool {
+        (self.0 >> 79) & 1 != 0
+    }
+    /// WRITE_BL_PARTIAL: Blocks shorter than the block length can be
+    /// written
+    pub fn write_block_partial(&self) -> bool {
+        (self.0 >> 21) & 1 != 0
+    }
+    /// WRITE_BLK_MISALIGN: A written block may cross a physical block
+    /// boundary
+    pub fn write_block_misalign(&self) -> bool {
+        (self.0 >> 78) & 1 != 0
+    }
+    /// READ_BLK_MISALIGN: A read block may cross a physical block boundary
+    pub fn read_block_misalign(&self) -> bool {
+        (self.0 >> 77) & 1 != 0
+    }
     /// Maximum read current at the minimum VDD
     pub fn read_current_minimum_vdd(&self) -> CurrentConsumption {
         CurrentConsumption::from_minimum_reg((self.0 >> 59) & 0x7)
@@ -288,6 +965,61 @@ impl<Ext> CSD<Ext> {
     pub fn write_current_maximum_vdd(&self) -> CurrentConsumption {
         CurrentConsumption::from_maximum_reg((self.0 >> 50) & 0x7)
     }
+    /// CRC7 checksum field
+    pub fn crc7(&self) -> u8 {
+        (self.0 >> 1) as u8 & 0x7F
+    }
+    /// The CRC7 field matches the register contents
+    ///
+    /// Always false if the host controller does not store the CRC of R2
+    /// responses.
+    pub fn crc_valid(&self) -> bool {
+        crc::crc7(&self.0.to_be_bytes()[..15]) == self.crc7()
+    }
+}
+
+/// Error reported by the card in a status response
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StatusError {
+    /// The command argument was out of the allowed range
+    OutOfRange,
+    /// Misaligned address
+    AddressError,
+    /// Transferred block length is not allowed
+    BlockLenError,
+    /// Error in the sequence of erase commands
+    EraseSeqError,
+    /// Invalid selection of write blocks for erase
+    EraseParam,
+    /// Attempt to program a write protected block
+    WpViolation,
+    /// The card is locked by the host
+    CardIsLocked,
+    /// Error in the lock/unlock command, or write protect erase skipped
+    LockUnlockFailed,
+    /// CRC check of the previous command failed
+    ComCrcError,
+    /// Command not legal for the card state
+    IllegalCommand,
+    /// Card internal ECC failed to correct the data
+    CardEccFailed,
+    /// Internal card controller error
+    CcError,
+    /// General or unknown error
+    Error,
+    /// Attempt to change read-only CSD fields
+    CsdOverwrite,
+    /// Only partial address space was erased due to write protected blocks
+    WpEraseSkip,
+    /// SD only: Error in the sequence of the authentication process
+    AkeSeqError,
+}
+
+/// Card status bits defined by only one kind of card
+pub trait CardStatusExt {
+    /// The error reported by the bits specific to this kind of card, if any
+    fn specific_error(status: u32) -> Option<StatusError>;
 }
 
 /// Card Status (R1)
@@ -305,6 +1037,14 @@ impl<Ext> From<u32> for CardStatus<Ext> {
 }
 
 impl<Ext> CardStatus<Ext> {
+    /// Raw register value
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+    /// Register word, as accepted by `From<u32>`
+    pub fn as_words(&self) -> [u32; 1] {
+        [self.0]
+    }
     /// Command's argument was out of range
     pub fn out_of_range(&self) -> bool {
         self.0 & 0x8000_0000 != 0
@@ -383,10 +1123,37 @@ impl<Ext> CardStatus<Ext> {
     }
 }
 
+impl<Ext: CardStatusExt> CardStatus<Ext> {
+    /// The first error reported, if any. ERASE_RESET is not an error, it
+    /// only informs about an aborted erase sequence
+    pub fn check(&self) -> Result<(), StatusError> {
+        let errors = [
+            (self.out_of_range(), StatusError::OutOfRange),
+            (self.address_error(), StatusError::AddressError),
+            (self.block_len_error(), StatusError::BlockLenError),
+            (self.erase_seq_error(), StatusError::EraseSeqError),
+            (self.erase_param(), StatusError::EraseParam),
+            (self.wp_violation(), StatusError::WpViolation),
+            (self.lock_unlock_failed(), StatusError::LockUnlockFailed),
+            (self.com_crc_error(), StatusError::ComCrcError),
+            (self.illegal_command(), StatusError::IllegalCommand),
+            (self.card_ecc_failed(), StatusError::CardEccFailed),
+            (self.cc_error(), StatusError::CcError),
+            (self.error(), StatusError::Error),
+            (self.csd_overwrite(), StatusError::CsdOverwrite),
+            (self.wp_erase_skip(), StatusError::WpEraseSkip),
+        ];
+        match errors.iter().find(|(set, _)| *set) {
+            Some(&(_, e)) => Err(e),
+            None => Ext::specific_error(self.0).map_or(Ok(()), Err),
+        }
+    }
+}
+
 /// Relative Card Address (RCA)
 ///
 /// R6
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Copy, Clone, Default)]
 pub struct RCA<Ext>(pub(crate) u32, PhantomData<Ext>);
 impl<Ext> From<u32> for RCA<Ext> {
     fn from(word: u32) -> Self {
@@ -394,8 +1161,44 @@ impl<Ext> From<u32> for RCA<Ext> {
     }
 }
 impl<Ext> RCA<Ext> {
+    /// Raw R6 response
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+    /// Response word, as accepted by `From<u32>`
+    pub fn as_words(&self) -> [u32; 1] {
+        [self.0]
+    }
     /// Address of card
     pub fn address(&self) -> u16 {
         (self.0 >> 16) as u16
     }
+    /// R6 status bits at their position in the card status
+    pub(crate) fn r6_status(&self) -> CardStatus<Ext> {
+        let status = self.0 & 0x1FFF
+            | (self.0 & 0x8000) << 8
+            | (self.0 & 0x4000) << 8
+            | (self.0 & 0x2000) << 6;
+        CardStatus::from(status)
+    }
+}
+impl<Ext> fmt::Debug for RCA<Ext> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = self.r6_status();
+        f.debug_struct("RCA: Relative Card Address")
+            .field("Address", &format_args!("{}", self))
+            .field("State", &status.state())
+            .field("Com CRC Error", &status.com_crc_error())
+            .field("Illegal Command", &status.illegal_command())
+            .field("Error", &status.error())
+            .field("Ready for Data", &status.ready_for_data())
+            .field("App Command", &status.app_cmd())
+            .field("Auth Sequence Error", &(self.0 & 0x8 != 0))
+            .finish()
+    }
+}
+impl<Ext> fmt::Display for RCA<Ext> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06x}", self.address())
+    }
 }
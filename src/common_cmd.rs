@@ -1,5 +1,12 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
 use core::marker::PhantomData;
 
+use crate::common::{
+    BlockSize, CardCapacity, CardStatus, CardStatusExt, CurrentState, StatusError, OCR,
+};
+use crate::Error;
+
 /// Host to Card commands
 pub struct Cmd<R: Resp> {
     pub cmd: u8,
@@ -7,19 +14,66 @@ pub struct Cmd<R: Resp> {
     resp: PhantomData<R>,
 }
 
+impl<R: Resp> PartialEq for Cmd<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmd == other.cmd && self.arg == other.arg
+    }
+}
+
+impl<R: Resp> Eq for Cmd<R> {}
+
+impl<R: Resp> Hash for Cmd<R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cmd.hash(state);
+        self.arg.hash(state);
+    }
+}
+
+impl<R: Resp> fmt::Debug for Cmd<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cmd")
+            .field("cmd", &self.cmd)
+            .field("arg", &format_args!("{:#010x}", self.arg))
+            .finish()
+    }
+}
+
 impl<R: Resp> Cmd<R> {
     pub fn response_len(&self) -> ResponseLen {
         R::LENGTH
     }
+    /// Check the R1 response to this command
+    pub fn check_response<Ext: CardStatusExt>(
+        &self,
+        status: &CardStatus<Ext>,
+    ) -> Result<(), CommandError> {
+        status.check().map_err(|error| CommandError {
+            cmd: self.cmd,
+            error,
+        })
+    }
+    /// RCA of the addressed card, for commands that carry it in the upper
+    /// 16 bits of the argument (CMD7, CMD9, CMD10, CMD13, CMD15 and CMD55).
+    /// Zero for CMD7 deselecting all cards
+    pub fn rca(&self) -> Option<u16> {
+        match self.cmd {
+            7 | 9 | 10 | 13 | 15 | 55 => Some((self.arg >> 16) as u16),
+            _ => None,
+        }
+    }
 }
 
 /// Marker for commands that don't have any response
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub struct Rz;
 /// R1: Normal response
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub struct R1;
 /// R2: CID and CSD register
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub struct R2;
 /// R3: OCR register
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub struct R3;
 
 pub trait Resp {
@@ -38,7 +92,7 @@ impl Resp for R1 {}
 impl Resp for R3 {}
 
 /// Command Response type
-#[derive(Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum ResponseLen {
     /// No response expected
     Zero,
@@ -48,6 +102,26 @@ pub enum ResponseLen {
     R136,
 }
 
+impl ResponseLen {
+    /// Length of the response on the CMD line, in bits
+    pub fn bits(&self) -> u8 {
+        match self {
+            ResponseLen::Zero => 0,
+            ResponseLen::R48 => 48,
+            ResponseLen::R136 => 136,
+        }
+    }
+    /// Number of 32-bit response registers used by host controllers to hold
+    /// the response
+    pub fn word_count(&self) -> usize {
+        match self {
+            ResponseLen::Zero => 0,
+            ResponseLen::R48 => 1,
+            ResponseLen::R136 => 4,
+        }
+    }
+}
+
 pub fn cmd<R: Resp>(cmd: u8, arg: u32) -> Cmd<R> {
     Cmd {
         cmd,
@@ -71,6 +145,11 @@ pub fn select_card(rca: u16) -> Cmd<R1> {
     cmd(7, u32::from(rca) << 16)
 }
 
+/// CMD7: Deselect all cards. Deselected cards don't respond
+pub fn deselect_card() -> Cmd<Rz> {
+    cmd(7, 0)
+}
+
 /// CMD9: Send CSD
 pub fn send_csd(rca: u16) -> Cmd<R2> {
     cmd(9, u32::from(rca) << 16)
@@ -102,6 +181,15 @@ pub fn set_block_length(blocklen: u32) -> Cmd<R1> {
     cmd(16, blocklen)
 }
 
+/// CMD16: Set block len from a [`BlockSize`]. Fails for
+/// [`BlockSize::Unknown`]
+pub fn set_block_size(size: BlockSize) -> Result<Cmd<R1>, UnsupportedOperation> {
+    match size {
+        BlockSize::Unknown => Err(UnsupportedOperation),
+        size => Ok(set_block_length(1 << size as u32)),
+    }
+}
+
 /// CMD17: Read a single block from the card
 pub fn read_single_block(addr: u32) -> Cmd<R1> {
     cmd(17, addr)
@@ -132,7 +220,971 @@ pub fn erase() -> Cmd<R1> {
     cmd(38, 0)
 }
 
+/// CMD42: Lock or unlock the card, or manage its password. Followed by a
+/// [`LockUnlockData`] block
+pub fn lock_unlock() -> Cmd<R1> {
+    cmd(42, 0)
+}
+
 /// CMD55: App Command. Indicates that next command will be a app command
 pub fn app_cmd(rca: u16) -> Cmd<R1> {
     cmd(55, u32::from(rca) << 16)
 }
+
+/// Address of a 512 byte block on the card
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct BlockAddress(pub u32);
+
+/// The requested blocks are not within the card
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct OutOfRange;
+
+/// Builds block read and write commands for a specific card
+///
+/// SDSC cards are byte addressed, while SDHC/SDXC cards and sector mode eMMC
+/// devices are block addressed. The builder converts a [`BlockAddress`] to
+/// the command argument the card expects, and refuses blocks beyond the end
+/// of the card.
+///
+/// ```
+/// # use sdio_host::common_cmd::{BlockAddress, DataCmdBuilder, OutOfRange};
+/// # use sdio_host::sd::CardCapacity;
+/// let sdsc = DataCmdBuilder::new(CardCapacity::StandardCapacity, 2048);
+/// assert_eq!(sdsc.read_single_block(BlockAddress(2)).unwrap().arg, 1024);
+/// assert_eq!(sdsc.read_multiple_blocks(BlockAddress(2040), 16).err(), Some(OutOfRange));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct DataCmdBuilder {
+    capacity: CardCapacity,
+    block_count: u64,
+    set_block_count: bool,
+}
+
+impl DataCmdBuilder {
+    /// * `capacity` - Addressing mode of the card
+    /// * `block_count` - Size of the card in 512 byte blocks
+    pub fn new(capacity: CardCapacity, block_count: u64) -> Self {
+        Self {
+            capacity,
+            block_count,
+            set_block_count: false,
+        }
+    }
+    /// The card supports CMD23, eg. an SD card with
+    /// [`SCR::cmd23_support`](crate::sd::SCR::cmd23_support) or an eMMC
+    /// device. Otherwise multiple block reads are ended with CMD12
+    pub fn with_set_block_count(mut self, supported: bool) -> Self {
+        self.set_block_count = supported;
+        self
+    }
+    /// Command argument addressing `block`, if `count` blocks starting at
+    /// `block` are within the card
+    pub fn address(&self, block: BlockAddress, count: u32) -> Result<u32, OutOfRange> {
+        let end = u64::from(block.0) + u64::from(count);
+        if count == 0 || end > self.block_count {
+            return Err(OutOfRange);
+        }
+        match self.capacity {
+            CardCapacity::StandardCapacity => block.0.checked_mul(512).ok_or(OutOfRange),
+            _ => Ok(block.0),
+        }
+    }
+    /// CMD17: Read a single block from the card
+    pub fn read_single_block(&self, block: BlockAddress) -> Result<Cmd<R1>, OutOfRange> {
+        self.address(block, 1).map(read_single_block)
+    }
+    /// CMD18: Read `count` blocks from the card
+    pub fn read_multiple_blocks(
+        &self,
+        block: BlockAddress,
+        count: u32,
+    ) -> Result<Cmd<R1>, OutOfRange> {
+        self.address(block, count).map(read_multiple_blocks)
+    }
+    /// CMD24: Write a single block
+    pub fn write_single_block(&self, block: BlockAddress) -> Result<Cmd<R1>, OutOfRange> {
+        self.address(block, 1).map(write_single_block)
+    }
+    /// CMD25: Write `count` blocks
+    pub fn write_multiple_blocks(
+        &self,
+        block: BlockAddress,
+        count: u32,
+    ) -> Result<Cmd<R1>, OutOfRange> {
+        self.address(block, count).map(write_multiple_blocks)
+    }
+    /// Commands reading a [`ReadRun`]. Runs of more than one block are read
+    /// with CMD23 followed by CMD18, or with CMD18 stopped by CMD12 if the
+    /// card does not support CMD23
+    pub fn read_run(&self, run: ReadRun) -> Result<ReadCmds, OutOfRange> {
+        if run.count == 1 {
+            return self.read_single_block(run.start).map(ReadCmds::Single);
+        }
+        let read = self.read_multiple_blocks(run.start, run.count)?;
+        if self.set_block_count {
+            Ok(ReadCmds::Multiple {
+                set_block_count: crate::sd_cmd::set_block_count(run.count),
+                read,
+            })
+        } else {
+            Ok(ReadCmds::OpenEnded {
+                read,
+                stop: stop_transmission(),
+            })
+        }
+    }
+    /// Resumable read of a [`ReadRun`], using the same commands as
+    /// [`read_run`](DataCmdBuilder::read_run)
+    pub fn read_transfer(&self, run: ReadRun) -> Result<Transfer, OutOfRange> {
+        self.transfer(false, run.start, run.count)
+    }
+    /// Resumable write of `count` blocks starting at `block`
+    pub fn write_transfer(&self, block: BlockAddress, count: u32) -> Result<Transfer, OutOfRange> {
+        self.transfer(true, block, count)
+    }
+    fn transfer(
+        &self,
+        write: bool,
+        block: BlockAddress,
+        count: u32,
+    ) -> Result<Transfer, OutOfRange> {
+        let set_block_count = self.set_block_count && count > 1;
+        Ok(Transfer {
+            write,
+            address: self.address(block, count)?,
+            count,
+            set_block_count,
+            state: if set_block_count {
+                TransferState::SetBlockCount
+            } else {
+                TransferState::Command
+            },
+            result: Ok(()),
+        })
+    }
+}
+
+/// Consecutive blocks to read in one transaction
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReadRun {
+    /// First block
+    pub start: BlockAddress,
+    /// Number of blocks
+    pub count: u32,
+}
+
+/// Commands for a [`ReadRun`]
+#[derive(Debug, Eq, PartialEq)]
+pub enum ReadCmds {
+    /// CMD17
+    Single(Cmd<R1>),
+    /// CMD23 and CMD18. Requires a card supporting CMD23
+    Multiple {
+        set_block_count: Cmd<R1>,
+        read: Cmd<R1>,
+    },
+    /// CMD18, then CMD12 once all blocks of the run are received
+    OpenEnded { read: Cmd<R1>, stop: Cmd<R1> },
+}
+
+/// Event reported by the host controller while a [`Transfer`] is running,
+/// typically from its interrupt handler
+#[derive(Copy, Clone)]
+pub enum TransferEvent<Ext> {
+    /// The response to the last command was received
+    CommandComplete(CardStatus<Ext>),
+    /// The data phase ended and the card is no longer busy
+    DataComplete,
+    /// The command or data phase failed
+    Error(TransientError),
+}
+
+/// The next thing to do for a [`Transfer`]
+#[derive(Debug, Eq, PartialEq)]
+pub enum TransferStep {
+    /// Send the command, then report [`TransferEvent::CommandComplete`]
+    Send(Cmd<R1>),
+    /// Run the data phase, then report [`TransferEvent::DataComplete`]
+    Data,
+    /// All blocks were transferred
+    Done,
+    /// The transfer failed. If the failure happened after the transfer
+    /// command was accepted, the card may need an [`ErrorRecovery`]
+    Failed(Error),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum TransferState {
+    SetBlockCount,
+    Command,
+    Data,
+    Stop,
+    Done,
+}
+
+/// Block transfer split into resumable steps
+///
+/// Nothing blocks: the host starts the current [`TransferStep`] and passes
+/// the event that ends it to [`Transfer::advance`], eg. from the interrupt
+/// handler of its controller.
+///
+/// ```
+/// # use sdio_host::common_cmd::{
+/// #     BlockAddress, DataCmdBuilder, ReadRun, TransferEvent, TransferStep,
+/// # };
+/// # use sdio_host::sd::{CardCapacity, CardStatus, SD};
+/// let builder = DataCmdBuilder::new(CardCapacity::HighCapacity, 1024);
+/// let run = ReadRun { start: BlockAddress(8), count: 4 };
+/// let mut read = builder.read_transfer(run).unwrap();
+/// let transfer = TransferEvent::CommandComplete(CardStatus::<SD>::from(4 << 9));
+/// assert_eq!(read.step(), TransferStep::Send(sdio_host::common_cmd::read_multiple_blocks(8)));
+/// assert_eq!(read.advance(transfer), TransferStep::Data);
+/// // Without CMD23 the read is stopped with CMD12
+/// let stop = TransferStep::Send(sdio_host::common_cmd::stop_transmission());
+/// assert_eq!(read.advance::<SD>(TransferEvent::DataComplete), stop);
+/// assert_eq!(read.advance(transfer), TransferStep::Done);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Transfer {
+    write: bool,
+    address: u32,
+    count: u32,
+    set_block_count: bool,
+    state: TransferState,
+    result: Result<(), Error>,
+}
+
+impl Transfer {
+    /// Number of blocks transferred
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+    /// The current step
+    pub fn step(&self) -> TransferStep {
+        match self.state {
+            TransferState::SetBlockCount | TransferState::Command | TransferState::Stop => {
+                TransferStep::Send(self.command())
+            }
+            TransferState::Data => TransferStep::Data,
+            TransferState::Done => match self.result {
+                Ok(()) => TransferStep::Done,
+                Err(e) => TransferStep::Failed(e),
+            },
+        }
+    }
+    /// Pass the event that ended the current step and get the next step
+    ///
+    /// A failed data phase of a multiple block transfer is stopped with
+    /// CMD12. The card status in the response to CMD12 is not checked,
+    /// since it reports the errors of the aborted transfer.
+    pub fn advance<Ext: CardStatusExt>(&mut self, event: TransferEvent<Ext>) -> TransferStep {
+        let multiple = self.count > 1;
+        self.state = match (self.state, event) {
+            (TransferState::SetBlockCount, TransferEvent::CommandComplete(status))
+            | (TransferState::Command, TransferEvent::CommandComplete(status)) => {
+                match self.command().check_response(&status) {
+                    Ok(()) if self.state == TransferState::SetBlockCount => TransferState::Command,
+                    Ok(()) => TransferState::Data,
+                    Err(e) => {
+                        self.result = Err(e.into());
+                        TransferState::Done
+                    }
+                }
+            }
+            (TransferState::Data, TransferEvent::DataComplete) => {
+                if multiple && !self.set_block_count {
+                    TransferState::Stop
+                } else {
+                    TransferState::Done
+                }
+            }
+            (TransferState::Data, TransferEvent::Error(e)) => {
+                self.result = Err(e.into());
+                if multiple {
+                    TransferState::Stop
+                } else {
+                    TransferState::Done
+                }
+            }
+            (TransferState::Stop, TransferEvent::CommandComplete(_)) => TransferState::Done,
+            (TransferState::Done, _) => TransferState::Done,
+            (_, TransferEvent::Error(e)) => {
+                if self.result.is_ok() {
+                    self.result = Err(e.into());
+                }
+                TransferState::Done
+            }
+            // Unexpected event, stay in the current step
+            (state, _) => state,
+        };
+        self.step()
+    }
+    /// Command sent in the current state
+    fn command(&self) -> Cmd<R1> {
+        match (self.state, self.write, self.count) {
+            (TransferState::SetBlockCount, ..) => crate::sd_cmd::set_block_count(self.count),
+            (TransferState::Stop, ..) => stop_transmission(),
+            (_, false, 1) => read_single_block(self.address),
+            (_, false, _) => read_multiple_blocks(self.address),
+            (_, true, 1) => write_single_block(self.address),
+            (_, true, _) => write_multiple_blocks(self.address),
+        }
+    }
+}
+
+/// Coalesces single block read requests into multiple block reads
+///
+/// Requests for adjacent blocks are merged until the run reaches the
+/// maximum span.
+///
+/// ```
+/// # use sdio_host::common_cmd::{BlockAddress, ReadPlanner, ReadRun};
+/// let mut planner = ReadPlanner::new(16);
+/// assert_eq!(planner.push(BlockAddress(8)), None);
+/// assert_eq!(planner.push(BlockAddress(9)), None);
+/// assert_eq!(
+///     planner.push(BlockAddress(100)),
+///     Some(ReadRun { start: BlockAddress(8), count: 2 })
+/// );
+/// assert_eq!(planner.finish(), Some(ReadRun { start: BlockAddress(100), count: 1 }));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct ReadPlanner {
+    max_span: u32,
+    pending: Option<ReadRun>,
+}
+
+impl ReadPlanner {
+    /// Planner merging up to `max_span` blocks per run. Limited to
+    /// 1 ..= [`MAX_BLOCK_COUNT`]
+    pub fn new(max_span: u32) -> Self {
+        Self {
+            max_span: max_span.max(1).min(MAX_BLOCK_COUNT),
+            pending: None,
+        }
+    }
+    /// Request a block. Returns a run that is complete and should be read
+    /// now
+    pub fn push(&mut self, block: BlockAddress) -> Option<ReadRun> {
+        if let Some(run) = self.pending.as_mut() {
+            let next = u64::from(run.start.0) + u64::from(run.count);
+            if u64::from(block.0) == next && run.count < self.max_span {
+                run.count += 1;
+                return None;
+            }
+        }
+        self.pending.replace(ReadRun {
+            start: block,
+            count: 1,
+        })
+    }
+    /// Take the pending run, if any
+    pub fn finish(&mut self) -> Option<ReadRun> {
+        self.pending.take()
+    }
+}
+
+/// Maximum number of blocks in a single multiple block transfer
+pub const MAX_BLOCK_COUNT: u32 = 65535;
+
+/// A data buffer that can't be used for a block transfer
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BufferError {
+    /// The buffer is empty
+    Empty,
+    /// The block size is zero
+    InvalidBlockSize,
+    /// The length is not a multiple of the block size
+    PartialBlock,
+    /// The buffer holds more than [`MAX_BLOCK_COUNT`] blocks
+    TooManyBlocks,
+    /// The buffer does not start at the alignment required by the host
+    /// controller
+    Misaligned,
+}
+
+/// Check a data buffer before starting a transfer, returning the number of
+/// blocks it holds
+///
+/// * `block_size` - Negotiated block size in bytes
+/// * `alignment` - Start address alignment required by the host controller
+///   or its DMA, in bytes. 1 if there is no requirement
+///
+/// ```
+/// # use sdio_host::common_cmd::{check_buffer, BufferError};
+/// let buffer = [0u8; 1024];
+/// assert_eq!(check_buffer(&buffer, 512, 1), Ok(2));
+/// assert_eq!(check_buffer(&buffer[..1000], 512, 1), Err(BufferError::PartialBlock));
+/// assert_eq!(check_buffer(&buffer, 0, 1), Err(BufferError::InvalidBlockSize));
+/// ```
+pub fn check_buffer(buffer: &[u8], block_size: u32, alignment: usize) -> Result<u32, BufferError> {
+    if buffer.is_empty() {
+        return Err(BufferError::Empty);
+    }
+    if block_size == 0 {
+        return Err(BufferError::InvalidBlockSize);
+    }
+    if alignment > 1 && buffer.as_ptr() as usize % alignment != 0 {
+        return Err(BufferError::Misaligned);
+    }
+    let block_size = block_size as usize;
+    if buffer.len() % block_size != 0 {
+        return Err(BufferError::PartialBlock);
+    }
+    let blocks = buffer.len() / block_size;
+    if blocks > MAX_BLOCK_COUNT as usize {
+        return Err(BufferError::TooManyBlocks);
+    }
+    Ok(blocks as u32)
+}
+
+/// The card does not support the requested operation
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UnsupportedOperation;
+
+/// The card did not perform a SWITCH (CMD6)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SwitchError {
+    /// eMMC: The device set SWITCH_ERROR in the card status
+    Rejected,
+    /// SD: The function group (1 - 6) was not switched to the requested
+    /// function
+    FunctionGroup(u8),
+}
+
+/// Commands of an erase operation over a range of blocks, issued in order
+#[derive(Debug)]
+pub struct EraseSequence {
+    /// First block of the range (SD: CMD32, eMMC: CMD35)
+    pub start: Cmd<R1>,
+    /// Last block of the range (SD: CMD33, eMMC: CMD36)
+    pub end: Cmd<R1>,
+    /// CMD38: Erase the range. The card signals busy until done
+    pub erase: Cmd<R1>,
+}
+
+/// Category of a command, used to select a retry budget
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CommandClass {
+    /// All other commands
+    Control,
+    /// Block read commands (CMD17, CMD18)
+    Read,
+    /// Block write commands (CMD24, CMD25)
+    Write,
+}
+
+impl<R: Resp> Cmd<R> {
+    /// Category of this command
+    pub fn class(&self) -> CommandClass {
+        match self.cmd {
+            17 | 18 => CommandClass::Read,
+            24 | 25 => CommandClass::Write,
+            _ => CommandClass::Control,
+        }
+    }
+}
+
+/// Error reported in the response to a command
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CommandError {
+    /// Index of the failed command
+    pub cmd: u8,
+    /// The reported error
+    pub error: StatusError,
+}
+
+/// Transient failure of a command that may be retried
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TransientError {
+    /// CRC check of the response or data failed
+    Crc,
+    /// No response or data within the timeout
+    Timeout,
+}
+
+/// Number of retries allowed for each kind of transient error
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Retries {
+    pub crc: u8,
+    pub timeout: u8,
+}
+
+impl Retries {
+    fn for_error(&self, error: TransientError) -> u8 {
+        match error {
+            TransientError::Crc => self.crc,
+            TransientError::Timeout => self.timeout,
+        }
+    }
+}
+
+/// Describes how often a command should be retried after a transient error
+///
+/// The defaults retry control commands freely, since re-issuing them is
+/// harmless. A write that timed out is never re-issued: the card may still
+/// be programming and should be polled with CMD13 instead.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RetryPolicy {
+    pub control: Retries,
+    pub read: Retries,
+    pub write: Retries,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            control: Retries { crc: 3, timeout: 3 },
+            read: Retries { crc: 3, timeout: 1 },
+            write: Retries { crc: 1, timeout: 0 },
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        let none = Retries { crc: 0, timeout: 0 };
+        RetryPolicy {
+            control: none,
+            read: none,
+            write: none,
+        }
+    }
+    /// Number of retries allowed for `class` after `error`
+    pub fn retries(&self, class: CommandClass, error: TransientError) -> u8 {
+        match class {
+            CommandClass::Control => self.control.for_error(error),
+            CommandClass::Read => self.read.for_error(error),
+            CommandClass::Write => self.write.for_error(error),
+        }
+    }
+    /// Whether `cmd` should be issued again after failing `attempt` times
+    /// (starting at 1) with `error`
+    pub fn should_retry<R: Resp>(&self, cmd: &Cmd<R>, error: TransientError, attempt: u8) -> bool {
+        attempt <= self.retries(cmd.class(), error)
+    }
+}
+
+/// Diagnostic counters for a card interface
+///
+/// Updated by the host driver as commands are issued, and queried by the
+/// application to spot marginal cards or sockets. All counters saturate.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Commands issued, including retries
+    pub commands: u32,
+    /// Commands or transfers that failed with a CRC error
+    pub crc_errors: u32,
+    /// Commands or transfers that timed out
+    pub timeouts: u32,
+    /// Commands issued again after a transient error
+    pub retries: u32,
+    /// Data bytes read from the card
+    pub bytes_read: u64,
+    /// Data bytes written to the card
+    pub bytes_written: u64,
+}
+
+impl Stats {
+    /// Count an issued command
+    pub fn record_command(&mut self) {
+        self.commands = self.commands.saturating_add(1);
+    }
+    /// Count a transient error
+    pub fn record_error(&mut self, error: TransientError) {
+        match error {
+            TransientError::Crc => self.crc_errors = self.crc_errors.saturating_add(1),
+            TransientError::Timeout => self.timeouts = self.timeouts.saturating_add(1),
+        }
+    }
+    /// Count a retried command
+    pub fn record_retry(&mut self) {
+        self.retries = self.retries.saturating_add(1);
+    }
+    /// Count bytes transferred from the card
+    pub fn record_read(&mut self, bytes: usize) {
+        self.bytes_read = self.bytes_read.saturating_add(bytes as u64);
+    }
+    /// Count bytes transferred to the card
+    pub fn record_write(&mut self, bytes: usize) {
+        self.bytes_written = self.bytes_written.saturating_add(bytes as u64);
+    }
+    /// Clear all counters
+    pub fn reset(&mut self) {
+        *self = Stats::default();
+    }
+}
+
+/// A command to send during [`ErrorRecovery`], or its outcome
+#[derive(Debug, Eq, PartialEq)]
+pub enum RecoveryStep {
+    /// Send the command and pass its response to [`ErrorRecovery::update`]
+    Send(Cmd<R1>),
+    /// The card is back in the transfer state
+    Done,
+    /// The card did not return to the transfer state. It must be
+    /// initialized again
+    Failed,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum RecoveryState {
+    Stop,
+    Poll,
+    Select,
+}
+
+/// Recovery from a data error during a transfer
+///
+/// Stops the transfer with CMD12, then polls the card status with CMD13
+/// until the card is back in the transfer state. A card that fell back to
+/// the stand-by state is selected again with CMD7.
+///
+/// ```
+/// # use sdio_host::common_cmd::{card_status, stop_transmission, ErrorRecovery, RecoveryStep};
+/// # use sdio_host::sd::{CardStatus, SD};
+/// let mut recovery = ErrorRecovery::new(0x1234, 10);
+/// assert_eq!(recovery.step(), RecoveryStep::Send(stop_transmission()));
+/// // Response to CMD12, the card is still programming
+/// let programming = CardStatus::<SD>::from(7 << 9);
+/// let poll = RecoveryStep::Send(card_status(0x1234, false));
+/// assert_eq!(recovery.update(Some(&programming)), poll);
+/// assert_eq!(recovery.update(Some(&programming)), poll);
+/// let transfer = CardStatus::<SD>::from(4 << 9);
+/// assert_eq!(recovery.update(Some(&transfer)), RecoveryStep::Done);
+/// ```
+///
+/// Ref PLSS_v7_10 Section 4.3.3 and 4.8
+#[derive(Debug, Copy, Clone)]
+pub struct ErrorRecovery {
+    rca: u16,
+    state: RecoveryState,
+    polls: u32,
+    max_polls: u32,
+}
+
+impl ErrorRecovery {
+    /// Recovery of the card at `rca`, giving up after `max_polls` further
+    /// commands
+    pub fn new(rca: u16, max_polls: u32) -> Self {
+        Self {
+            rca,
+            state: RecoveryState::Stop,
+            polls: 0,
+            max_polls,
+        }
+    }
+    /// The current step
+    pub fn step(&self) -> RecoveryStep {
+        if self.polls > self.max_polls {
+            return RecoveryStep::Failed;
+        }
+        match self.state {
+            RecoveryState::Stop => RecoveryStep::Send(stop_transmission()),
+            RecoveryState::Poll => RecoveryStep::Send(card_status(self.rca, false)),
+            RecoveryState::Select => RecoveryStep::Send(select_card(self.rca)),
+        }
+    }
+    /// Pass the response to the last command, or `None` if it failed, and
+    /// get the next step
+    ///
+    /// The response to CMD12 is ignored, since it fails if the card already
+    /// left the data state.
+    pub fn update<Ext>(&mut self, status: Option<&CardStatus<Ext>>) -> RecoveryStep {
+        match (self.state, status) {
+            (RecoveryState::Stop, _) | (RecoveryState::Select, Some(_)) => {
+                self.state = RecoveryState::Poll
+            }
+            (RecoveryState::Poll, Some(status)) => match status.state() {
+                CurrentState::Transfer => return RecoveryStep::Done,
+                CurrentState::Standby => self.state = RecoveryState::Select,
+                CurrentState::Sending | CurrentState::Receiving => self.state = RecoveryState::Stop,
+                _ => {}
+            },
+            (_, None) => {}
+        }
+        self.polls += 1;
+        self.step()
+    }
+}
+
+/// The password is longer than 16 bytes
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidPassword;
+
+/// Maximum length of a card password
+pub const MAX_PASSWORD_LEN: usize = 16;
+
+/// Data block of a [`lock_unlock`] command
+///
+/// The block length must be set to [`LockUnlockData::len`] with CMD16
+/// before sending it.
+///
+/// Ref PLSS_v7_10 Section 4.3.7
+#[derive(Copy, Clone)]
+pub struct LockUnlockData {
+    bytes: [u8; 2 + 2 * MAX_PASSWORD_LEN],
+    len: usize,
+}
+
+impl LockUnlockData {
+    const SET_PWD: u8 = 0x01;
+    const CLR_PWD: u8 = 0x02;
+    const LOCK_UNLOCK: u8 = 0x04;
+    const ERASE: u8 = 0x08;
+
+    fn new(flags: u8, old: &[u8], new: &[u8]) -> Result<Self, InvalidPassword> {
+        if old.len() > MAX_PASSWORD_LEN || new.len() > MAX_PASSWORD_LEN {
+            return Err(InvalidPassword);
+        }
+        let pwd_len = old.len() + new.len();
+        let mut bytes = [0; 2 + 2 * MAX_PASSWORD_LEN];
+        bytes[0] = flags;
+        bytes[1] = pwd_len as u8;
+        bytes[2..2 + old.len()].copy_from_slice(old);
+        bytes[2 + old.len()..2 + pwd_len].copy_from_slice(new);
+        Ok(Self {
+            bytes,
+            len: 2 + pwd_len,
+        })
+    }
+    /// Unlock the card
+    pub fn unlock(password: &[u8]) -> Result<Self, InvalidPassword> {
+        Self::new(0, password, &[])
+    }
+    /// Lock the card
+    pub fn lock(password: &[u8]) -> Result<Self, InvalidPassword> {
+        Self::new(Self::LOCK_UNLOCK, password, &[])
+    }
+    /// Set or replace the password. `old` is empty if no password is set
+    pub fn set_password(old: &[u8], new: &[u8]) -> Result<Self, InvalidPassword> {
+        Self::new(Self::SET_PWD, old, new)
+    }
+    /// Set or replace the password and lock the card with the new password
+    /// in the same command
+    pub fn set_password_and_lock(old: &[u8], new: &[u8]) -> Result<Self, InvalidPassword> {
+        Self::new(Self::SET_PWD | Self::LOCK_UNLOCK, old, new)
+    }
+    /// Remove the password
+    pub fn clear_password(password: &[u8]) -> Result<Self, InvalidPassword> {
+        Self::new(Self::CLR_PWD, password, &[])
+    }
+    /// Erase all user data and remove the password of a card with a
+    /// forgotten password
+    pub fn force_erase() -> Self {
+        Self {
+            bytes: {
+                let mut bytes = [0; 2 + 2 * MAX_PASSWORD_LEN];
+                bytes[0] = Self::ERASE;
+                bytes
+            },
+            len: 1,
+        }
+    }
+    /// The data block to send
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+    /// Length of the data block
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Never true. A data block holds at least the flags
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl PartialEq for LockUnlockData {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for LockUnlockData {}
+
+impl fmt::Debug for LockUnlockData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LockUnlockData")
+            .field("bytes", &self.as_bytes())
+            .finish()
+    }
+}
+
+/// A command to send during [`LockUnlock`], or its outcome
+#[derive(Debug, Eq, PartialEq)]
+pub enum LockUnlockStep {
+    /// Send the command and pass its response to [`LockUnlock::update`].
+    /// After CMD42, write [`LockUnlock::data`] as a data block
+    Send(Cmd<R1>),
+    /// The operation succeeded
+    Done {
+        /// The card is locked now
+        locked: bool,
+    },
+    /// The card rejected the operation, eg. because of a wrong password
+    Failed(StatusError),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum LockUnlockState {
+    SetBlockLength,
+    LockUnlock,
+    Status,
+    RestoreBlockLength,
+    Done,
+}
+
+/// Password lock, unlock or password change of a card
+///
+/// Sets the block length for the password data, sends CMD42 and checks the
+/// result with CMD13. The block length is restored to 512 bytes afterwards,
+/// also if the operation failed.
+///
+/// ```
+/// # use sdio_host::common_cmd::{LockUnlock, LockUnlockData, LockUnlockStep};
+/// # use sdio_host::sd::{CardStatus, SD};
+/// let mut unlock = LockUnlock::new(0x1234, LockUnlockData::unlock(b"secret").unwrap());
+/// let transfer = CardStatus::<SD>::from(4 << 9);
+/// while let LockUnlockStep::Send(cmd) = unlock.step() {
+///     // Send cmd, and the data block after CMD42
+///     unlock.update(&transfer);
+/// }
+/// assert_eq!(unlock.step(), LockUnlockStep::Done { locked: false });
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct LockUnlock {
+    rca: u16,
+    data: LockUnlockData,
+    state: LockUnlockState,
+    result: Result<bool, StatusError>,
+}
+
+impl LockUnlock {
+    /// Operation on the card at `rca`
+    pub fn new(rca: u16, data: LockUnlockData) -> Self {
+        Self {
+            rca,
+            data,
+            state: LockUnlockState::SetBlockLength,
+            result: Ok(false),
+        }
+    }
+    /// The data block to write after CMD42
+    pub fn data(&self) -> &LockUnlockData {
+        &self.data
+    }
+    /// The current step
+    pub fn step(&self) -> LockUnlockStep {
+        match self.state {
+            LockUnlockState::SetBlockLength => {
+                LockUnlockStep::Send(set_block_length(self.data.len() as u32))
+            }
+            LockUnlockState::LockUnlock => LockUnlockStep::Send(lock_unlock()),
+            LockUnlockState::Status => LockUnlockStep::Send(card_status(self.rca, false)),
+            LockUnlockState::RestoreBlockLength => LockUnlockStep::Send(set_block_length(512)),
+            LockUnlockState::Done => match self.result {
+                Ok(locked) => LockUnlockStep::Done { locked },
+                Err(e) => LockUnlockStep::Failed(e),
+            },
+        }
+    }
+    /// Pass the response to the last command and get the next step
+    pub fn update<Ext: CardStatusExt>(&mut self, status: &CardStatus<Ext>) -> LockUnlockStep {
+        self.state = match self.state {
+            LockUnlockState::SetBlockLength | LockUnlockState::LockUnlock => match status.check() {
+                Ok(()) if self.state == LockUnlockState::SetBlockLength => {
+                    LockUnlockState::LockUnlock
+                }
+                Ok(()) => LockUnlockState::Status,
+                Err(e) => {
+                    self.result = Err(e);
+                    LockUnlockState::RestoreBlockLength
+                }
+            },
+            LockUnlockState::Status => {
+                self.result = if status.lock_unlock_failed() {
+                    Err(StatusError::LockUnlockFailed)
+                } else {
+                    status.check().map(|_| status.card_is_locked())
+                };
+                LockUnlockState::RestoreBlockLength
+            }
+            LockUnlockState::RestoreBlockLength | LockUnlockState::Done => LockUnlockState::Done,
+        };
+        self.step()
+    }
+}
+
+/// Maximum time for a card to finish power up after the first ACMD41 or
+/// CMD1
+///
+/// Ref PLSS_v7_10 Section 4.2.3 and JEDEC 84-A43 Section 7.2.1
+pub const OP_COND_TIMEOUT_MS: u32 = 1000;
+
+/// Result of polling the OCR busy bit, see [OpCondPoll]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OpCondState {
+    /// The card finished power up
+    Ready,
+    /// The card is still busy. Send ACMD41 or CMD1 again
+    KeepPolling,
+    /// The card did not finish power up in time
+    TimedOut,
+}
+
+/// Polling of the busy bit in the OCR returned by ACMD41 (SD) or CMD1
+/// (eMMC)
+///
+/// ```
+/// # use sdio_host::common_cmd::{OpCondPoll, OpCondState, OP_COND_TIMEOUT_MS};
+/// # use sdio_host::sd::{OCR, SD};
+/// let mut poll = OpCondPoll::new(100).timeout_ms(OP_COND_TIMEOUT_MS);
+/// let busy = OCR::<SD>::from(0x00FF_8000);
+/// assert_eq!(poll.poll(&busy, 10), OpCondState::KeepPolling);
+/// let ready = OCR::<SD>::from(0x80FF_8000);
+/// assert_eq!(poll.poll(&ready, 20), OpCondState::Ready);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct OpCondPoll {
+    attempts: u32,
+    max_attempts: u32,
+    timeout_ms: Option<u32>,
+}
+
+impl OpCondPoll {
+    /// Polling giving up after `max_attempts` responses
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            attempts: 0,
+            max_attempts,
+            timeout_ms: None,
+        }
+    }
+    /// Also give up when `ms` milliseconds have elapsed
+    pub fn timeout_ms(mut self, ms: u32) -> Self {
+        self.timeout_ms = Some(ms);
+        self
+    }
+    /// Number of responses seen so far
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+    /// Classify the next OCR response
+    ///
+    /// * `elapsed_ms` - Time since the first ACMD41 or CMD1 was sent. Only
+    ///   used if a timeout is set
+    pub fn poll<Ext>(&mut self, ocr: &OCR<Ext>, elapsed_ms: u32) -> OpCondState {
+        if !ocr.is_busy() {
+            return OpCondState::Ready;
+        }
+        self.attempts = self.attempts.saturating_add(1);
+        let timed_out = self.timeout_ms.map_or(false, |ms| elapsed_ms >= ms);
+        if self.attempts >= self.max_attempts || timed_out {
+            OpCondState::TimedOut
+        } else {
+            OpCondState::KeepPolling
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Command queue task bookkeeping
+//!
+//! With command queueing the host queues up to 32 tasks, each identified
+//! by a task ID, and polls the Queue Status Register (QSR) to learn which
+//! tasks the card is ready to execute. [`TaskSlotManager`] keeps track of
+//! the task IDs in use and checks that the QSR and the execution order are
+//! consistent.
+//!
+//! ```
+//! # use sdio_host::cq::{Task, TaskSlotManager, TaskState};
+//! let mut tasks = TaskSlotManager::new(32);
+//! let id = tasks.queue(Task { read: true, start: 0x1000, count: 8 }).unwrap();
+//! // CMD44/CMD45 sent, QSR read with CMD13
+//! tasks.update_qsr(1 << id).unwrap();
+//! assert_eq!(tasks.state(id), TaskState::Ready);
+//! tasks.execute(id).unwrap();
+//! // CMD46/CMD47 and data transfer
+//! tasks.complete(id).unwrap();
+//! assert_eq!(tasks.state(id), TaskState::Free);
+//! ```
+//!
+//! Ref PLSS_v7_10 Section 4.20 and JEDEC 84-B51 Section 6.6.39
+
+/// Maximum number of tasks
+pub const MAX_TASKS: u8 = 32;
+
+/// A queued data transfer
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct Task {
+    /// Read from the card, otherwise write
+    pub read: bool,
+    /// First block
+    pub start: u32,
+    /// Number of blocks
+    pub count: u16,
+}
+
+/// State of a task ID
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TaskState {
+    /// Not in use
+    Free,
+    /// Queued, but not ready for execution yet
+    Queued,
+    /// The QSR reports the task as ready for execution
+    Ready,
+    /// The task is being executed
+    Executing,
+}
+
+/// Invalid task operation
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TaskError {
+    /// All task IDs of the queue are in use
+    Full,
+    /// The task ID is not valid for the queue depth, or not in the state
+    /// required by the operation
+    InvalidTask(u8),
+    /// Another task is being executed
+    Busy,
+    /// The QSR reports tasks that are not queued. Bit n is set for task n
+    InvalidQsr(u32),
+}
+
+/// Task ID allocation and state tracking for command queueing
+#[derive(Debug, Clone)]
+pub struct TaskSlotManager {
+    depth: u8,
+    queued: u32,
+    ready: u32,
+    executing: Option<u8>,
+    tasks: [Task; MAX_TASKS as usize],
+}
+
+impl TaskSlotManager {
+    /// Manager for a queue of `depth` tasks, limited to 1 ..= 32
+    pub fn new(depth: u8) -> Self {
+        Self {
+            depth: depth.max(1).min(MAX_TASKS),
+            queued: 0,
+            ready: 0,
+            executing: None,
+            tasks: [Task::default(); MAX_TASKS as usize],
+        }
+    }
+    /// Queue depth
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+    /// Number of task IDs in use
+    pub fn in_use(&self) -> u8 {
+        self.queued.count_ones() as u8
+    }
+    /// Allocate the lowest free task ID for `task`
+    pub fn queue(&mut self, task: Task) -> Result<u8, TaskError> {
+        let id = (!self.queued).trailing_zeros() as u8;
+        if id >= self.depth {
+            return Err(TaskError::Full);
+        }
+        self.queued |= 1 << id;
+        self.tasks[usize::from(id)] = task;
+        Ok(id)
+    }
+    /// The task with ID `id`, if queued
+    pub fn task(&self, id: u8) -> Option<&Task> {
+        match self.state(id) {
+            TaskState::Free => None,
+            _ => Some(&self.tasks[usize::from(id)]),
+        }
+    }
+    /// State of task ID `id`
+    pub fn state(&self, id: u8) -> TaskState {
+        let bit = 1u32.checked_shl(u32::from(id)).unwrap_or(0);
+        if self.executing == Some(id) {
+            TaskState::Executing
+        } else if self.ready & bit != 0 {
+            TaskState::Ready
+        } else if self.queued & bit != 0 {
+            TaskState::Queued
+        } else {
+            TaskState::Free
+        }
+    }
+    /// Update the ready tasks from a Queue Status Register read with CMD13
+    ///
+    /// Fails without changing any state if the QSR reports a task that
+    /// isn't queued, or the task being executed.
+    pub fn update_qsr(&mut self, qsr: u32) -> Result<(), TaskError> {
+        let executing = self.executing.map_or(0, |id| 1 << id);
+        let invalid = qsr & !(self.queued & !executing);
+        if invalid != 0 {
+            return Err(TaskError::InvalidQsr(invalid));
+        }
+        self.ready = qsr;
+        Ok(())
+    }
+    /// Start executing a ready task, with CMD46 or CMD47
+    pub fn execute(&mut self, id: u8) -> Result<&Task, TaskError> {
+        if self.executing.is_some() {
+            return Err(TaskError::Busy);
+        }
+        if self.state(id) != TaskState::Ready {
+            return Err(TaskError::InvalidTask(id));
+        }
+        self.ready &= !(1 << id);
+        self.executing = Some(id);
+        Ok(&self.tasks[usize::from(id)])
+    }
+    /// The task being executed finished its data transfer. Frees its ID
+    pub fn complete(&mut self, id: u8) -> Result<(), TaskError> {
+        if self.executing != Some(id) {
+            return Err(TaskError::InvalidTask(id));
+        }
+        self.executing = None;
+        self.queued &= !(1 << id);
+        Ok(())
+    }
+    /// The task was discarded with CMD43 or failed. Frees its ID
+    pub fn abort(&mut self, id: u8) -> Result<(), TaskError> {
+        if self.state(id) == TaskState::Free {
+            return Err(TaskError::InvalidTask(id));
+        }
+        if self.executing == Some(id) {
+            self.executing = None;
+        }
+        self.queued &= !(1 << id);
+        self.ready &= !(1 << id);
+        Ok(())
+    }
+    /// The whole queue was discarded with CMD43, or the card was reset
+    pub fn abort_all(&mut self) {
+        self.queued = 0;
+        self.ready = 0;
+        self.executing = None;
+    }
+}
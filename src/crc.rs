@@ -0,0 +1,158 @@
+//! CRC algorithms used by the SD/MMC protocols
+
+/// CRC7 with polynomial x^7 + x^3 + 1, as used for commands and responses
+///
+/// Returns the 7-bit CRC in the lower bits of the result.
+///
+/// ```
+/// # use sdio_host::crc::crc7;
+/// // CMD0 with argument 0
+/// assert_eq!(crc7(&[0x40, 0, 0, 0, 0]), 0x4A);
+/// ```
+pub fn crc7(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for byte in data {
+        for bit in (0..8).rev() {
+            let feedback = ((byte >> bit) ^ (crc >> 6)) & 1;
+            crc = (crc << 1) & 0x7F;
+            if feedback != 0 {
+                crc ^= 0x09;
+            }
+        }
+    }
+    crc
+}
+
+/// CRC16 with polynomial x^16 + x^12 + x^5 + 1, as used for data blocks
+///
+/// ```
+/// # use sdio_host::crc::crc16;
+/// assert_eq!(crc16(&[0xFF; 512]), 0x7FA1);
+/// ```
+pub fn crc16(data: &[u8]) -> u16 {
+    crc16_update(0, data)
+}
+
+/// Continue a CRC16 computation over more `data`
+fn crc16_update(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Failure of [BlockVerifier::verify]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VerifyError {
+    /// Fewer bytes than the block length were received
+    Incomplete,
+    /// The CRC16 doesn't match the data
+    Mismatch,
+}
+
+/// Incremental CRC16 check of a data block
+///
+/// Data can be fed in chunks of any size as it arrives, eg. from DMA half
+/// and full transfer interrupts, so that the check overlaps with the
+/// transfer.
+///
+/// ```
+/// # use sdio_host::crc::{BlockVerifier, VerifyError};
+/// let block = [0xFF; 512];
+/// let mut verifier = BlockVerifier::new(512);
+/// for chunk in block.chunks(100) {
+///     verifier.update(chunk);
+/// }
+/// assert_eq!(verifier.verify(0x7FA1), Ok(()));
+/// assert_eq!(verifier.verify(0x1234), Err(VerifyError::Mismatch));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BlockVerifier {
+    crc: u16,
+    remaining: usize,
+}
+
+impl BlockVerifier {
+    /// Verifier for a block of `block_len` bytes
+    pub fn new(block_len: usize) -> Self {
+        Self {
+            crc: 0,
+            remaining: block_len,
+        }
+    }
+    /// Feed the next chunk of the block. Bytes beyond the block length are
+    /// ignored; returns the number of bytes used
+    pub fn update(&mut self, chunk: &[u8]) -> usize {
+        let used = chunk.len().min(self.remaining);
+        self.crc = crc16_update(self.crc, &chunk[..used]);
+        self.remaining -= used;
+        used
+    }
+    /// The whole block was fed
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+    /// CRC16 of the data fed so far
+    pub fn crc(&self) -> u16 {
+        self.crc
+    }
+    /// Check the CRC16 received after the block
+    pub fn verify(&self, crc: u16) -> Result<(), VerifyError> {
+        if !self.is_complete() {
+            Err(VerifyError::Incomplete)
+        } else if self.crc != crc {
+            Err(VerifyError::Mismatch)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// CRC implementation, eg. a CRC peripheral of the MCU
+///
+/// Both methods default to the software implementations in this module,
+/// so a hardware backend only needs to provide the ones it accelerates.
+///
+/// ```
+/// # use sdio_host::crc::{crc16, CrcBackend};
+/// struct CrcPeripheral;
+/// impl CrcBackend for CrcPeripheral {
+///     fn crc16(&mut self, data: &[u8]) -> u16 {
+///         // Configure for polynomial 0x1021, initial value 0, feed data
+/// #       crc16(data)
+///     }
+/// }
+/// assert_eq!(CrcPeripheral.crc16(&[0xFF; 512]), 0x7FA1);
+/// ```
+pub trait CrcBackend {
+    /// CRC7 of `data`, see [crc7]
+    fn crc7(&mut self, data: &[u8]) -> u8 {
+        crc7(data)
+    }
+    /// CRC16 of `data`, see [crc16]
+    fn crc16(&mut self, data: &[u8]) -> u16 {
+        crc16(data)
+    }
+}
+
+/// The software CRC implementations of this module
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Software;
+
+impl CrcBackend for Software {}
+
+impl<B: CrcBackend> CrcBackend for &mut B {
+    fn crc7(&mut self, data: &[u8]) -> u8 {
+        (**self).crc7(data)
+    }
+    fn crc16(&mut self, data: &[u8]) -> u16 {
+        (**self).crc16(data)
+    }
+}
@@ -0,0 +1,156 @@
+//! Card detect debouncing and probe diagnostics
+//!
+//! Mechanical card detect switches bounce when a card is inserted or
+//! removed. [`Debouncer`] turns sampled pin levels into clean
+//! [`CardEvent`]s, independent of how the pin is read or time is kept.
+//! [`Probe`] interprets the first command outcomes to spot wiring
+//! problems.
+//!
+//! ```
+//! # use sdio_host::detect::{CardEvent, Debouncer};
+//! let mut cd = Debouncer::new(50);
+//! assert_eq!(cd.update(true, 0), None);
+//! assert_eq!(cd.update(false, 10), None); // bounce
+//! assert_eq!(cd.update(true, 20), None);
+//! assert_eq!(cd.update(true, 70), Some(CardEvent::Inserted));
+//! assert_eq!(cd.update(true, 80), None);
+//! ```
+
+/// Debounced change of the card detect state
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CardEvent {
+    /// A card was inserted. The card must be (re-)initialized
+    Inserted,
+    /// The card was removed. Any cached card state is invalid
+    Removed,
+}
+
+/// Card detect debouncer
+#[derive(Debug, Copy, Clone)]
+pub struct Debouncer {
+    settle_ms: u32,
+    stable: Option<bool>,
+    candidate: Option<bool>,
+    since_ms: u32,
+}
+
+impl Debouncer {
+    /// Debouncer that reports a new state once the pin has been steady for
+    /// `settle_ms` milliseconds
+    pub fn new(settle_ms: u32) -> Self {
+        Self {
+            settle_ms,
+            stable: None,
+            candidate: None,
+            since_ms: 0,
+        }
+    }
+    /// Feed a sample of the card detect line
+    ///
+    /// * `present` - The pin level indicates that a card is present
+    /// * `now_ms` - Timestamp of the sample. May wrap around
+    ///
+    /// The first settled state is always reported as an event.
+    pub fn update(&mut self, present: bool, now_ms: u32) -> Option<CardEvent> {
+        if self.candidate != Some(present) {
+            self.candidate = Some(present);
+            self.since_ms = now_ms;
+        }
+        if self.stable == Some(present) || now_ms.wrapping_sub(self.since_ms) < self.settle_ms {
+            return None;
+        }
+
+        self.stable = Some(present);
+        Some(if present {
+            CardEvent::Inserted
+        } else {
+            CardEvent::Removed
+        })
+    }
+    /// Debounced card state, or `None` if the pin has not settled yet
+    pub fn is_present(&self) -> Option<bool> {
+        self.stable
+    }
+}
+
+/// Outcome of a command sent while probing for a card
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ProbeOutcome {
+    /// Valid response
+    Response,
+    /// No response within the timeout
+    Timeout,
+    /// Response with a CRC error
+    Crc,
+    /// Response with a wrong command index or end bit
+    Garbled,
+}
+
+/// Likely cause of probing failures, see [`Probe`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ProbeDiagnosis {
+    /// Nothing recorded yet
+    Inconclusive,
+    /// All commands were answered
+    Ok,
+    /// No command was answered. No card, no supply, no clock, or an
+    /// unconnected CMD line. In SD mode a card also ignores the bus if it
+    /// entered SPI mode because DAT3 was low during CMD0; check the DAT3
+    /// pull-up
+    NoResponse,
+    /// Responses were received but none were valid. The CMD line is
+    /// stuck, shorted or missing its pull-up
+    CmdLineFault,
+    /// Commands were answered sometimes and timed out otherwise. Loose
+    /// contacts or an unstable supply
+    Intermittent,
+    /// Valid and corrupted responses. The clock is too fast for the wiring,
+    /// or pull-ups are too weak
+    SignalIntegrity,
+}
+
+/// Pre-initialization diagnostic
+///
+/// Collects the outcome of the first commands (CMD8, ACMD41, CMD1, ...) and
+/// tells wiring problems apart from protocol issues.
+///
+/// ```
+/// # use sdio_host::detect::{Probe, ProbeDiagnosis, ProbeOutcome};
+/// let mut probe = Probe::new();
+/// probe.record(ProbeOutcome::Response);
+/// probe.record(ProbeOutcome::Crc);
+/// assert_eq!(probe.diagnosis(), ProbeDiagnosis::SignalIntegrity);
+/// ```
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Probe {
+    responses: u16,
+    timeouts: u16,
+    corrupted: u16,
+}
+
+impl Probe {
+    /// Empty probe
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Record the outcome of a command
+    pub fn record(&mut self, outcome: ProbeOutcome) {
+        let count = match outcome {
+            ProbeOutcome::Response => &mut self.responses,
+            ProbeOutcome::Timeout => &mut self.timeouts,
+            ProbeOutcome::Crc | ProbeOutcome::Garbled => &mut self.corrupted,
+        };
+        *count = count.saturating_add(1);
+    }
+    /// Likely cause of the recorded outcomes
+    pub fn diagnosis(&self) -> ProbeDiagnosis {
+        match (self.responses, self.timeouts, self.corrupted) {
+            (0, 0, 0) => ProbeDiagnosis::Inconclusive,
+            (_, 0, 0) => ProbeDiagnosis::Ok,
+            (0, _, 0) => ProbeDiagnosis::NoResponse,
+            (0, _, _) => ProbeDiagnosis::CmdLineFault,
+            (_, _, 0) => ProbeDiagnosis::Intermittent,
+            _ => ProbeDiagnosis::SignalIntegrity,
+        }
+    }
+}
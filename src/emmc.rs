@@ -2,13 +2,19 @@
 
 pub use crate::common::*;
 
+use crate::emmc_cmd::EraseFunction;
+
 use core::{fmt, str};
 
 /// Type marker for eMMC-specific extensions.
-#[derive(Clone, Copy, Default, Debug)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub struct EMMC;
 
 impl OCR<EMMC> {
+    /// OCR \[23:15\]. The device supports 2.7 - 3.6V
+    pub fn is_high_voltage_card(&self) -> bool {
+        self.0 & 0x00FF_8000 == 0x00FF_8000
+    }
     /// OCR \[7\]. False for High Voltage, true for Dual voltage
     pub fn is_dual_voltage_card(&self) -> bool {
         self.0 & 0x0000_0080 != 0
@@ -18,12 +24,13 @@ impl OCR<EMMC> {
     /// 0b00: byte mode
     /// 0b10: sector mode
     pub fn access_mode(&self) -> u8 {
-        (self.0 & 0x6000_0000 >> 29) as u8
+        ((self.0 & 0x6000_0000) >> 29) as u8
     }
 }
 impl fmt::Debug for OCR<EMMC> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("OCR: Operation Conditions Register")
+            .field("2.7 - 3.6V", &self.is_high_voltage_card())
             .field(
                 "Dual Voltage",
                 &if self.is_dual_voltage_card() {
@@ -61,7 +68,7 @@ impl CID<EMMC> {
             0b00 => DeviceType::RemovableDevice,
             0b01 => DeviceType::BGA,
             0b10 => DeviceType::POP,
-            _ => DeviceType::POP,
+            _ => DeviceType::Unknown,
         }
     }
 
@@ -74,7 +81,7 @@ impl CID<EMMC> {
 
     /// PNM field, indicating product name.
     pub fn product_name(&self) -> &str {
-        str::from_utf8(&self.bytes[3..9]).unwrap_or(&"<ERR>")
+        str::from_utf8(&self.bytes[3..9]).unwrap_or("<ERR>")
     }
 
     /// PRV field, indicating product revision.
@@ -100,7 +107,47 @@ impl CID<EMMC> {
         let year = (self.inner >> 8) as u8 & 0xF;
         (month, year)
     }
+
+    /// Manufacturing year, resolving the MDT year offset with EXT_CSD_REV
+    /// ([`ExtCSD::extended_csd_revision`])
+    ///
+    /// From EXT_CSD_REV 5 on, codes 0 - 12 are 2013 - 2025 and codes
+    /// 13 - 15 are 2010 - 2012.
+    pub fn manufacturing_year(&self, ext_csd_rev: u8) -> u16 {
+        let (_, year) = self.manufacturing_date();
+        let year = 1997 + u16::from(year);
+        if ext_csd_rev > 4 && year < 2010 {
+            year + 16
+        } else {
+            year
+        }
+    }
+
+    /// Identification fields, for matching against a [`CidFilter`]
+    pub fn identity(&self) -> CardIdentity<'_> {
+        CardIdentity {
+            manufacturer_id: self.manufacturer_id(),
+            oem_id: u16::from(self.oem_application_id()),
+            product_name: self.product_name(),
+            revision: self.bytes[9],
+        }
+    }
 }
+impl CID<EMMC> {
+    /// Sanity check of the register contents
+    ///
+    /// Checks the CRC and that reserved bits are zero, to detect garbled R2
+    /// responses. Requires a host controller that stores the CRC.
+    pub fn is_plausible(&self) -> bool {
+        self.crc_valid() && self.bytes[1] & 0xFC == 0
+    }
+}
+impl Plausible for CID<EMMC> {
+    fn is_plausible(&self) -> bool {
+        CID::<EMMC>::is_plausible(self)
+    }
+}
+
 impl fmt::Debug for CID<EMMC> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CID: Card Identification")
@@ -116,6 +163,11 @@ impl fmt::Debug for CID<EMMC> {
 }
 
 impl CSD<EMMC> {
+    /// CSD structure version. 3 indicates that the version is coded in the
+    /// EXT_CSD
+    pub fn version(&self) -> u8 {
+        self.csd_structure()
+    }
     /// Erase size (in blocks)
     ///
     /// Minimum number of write blocks that must be erased in a single erase
@@ -127,6 +179,24 @@ impl CSD<EMMC> {
         (erase_grp_size as u32 + 1) + (erase_grp_mult as u32 + 1)
     }
 }
+impl CSD<EMMC> {
+    /// Sanity check of the register contents
+    ///
+    /// Checks the CRC, block length and that the mandatory basic command
+    /// class is supported, to detect garbled R2 responses. Requires a host
+    /// controller that stores the CRC.
+    pub fn is_plausible(&self) -> bool {
+        self.crc_valid()
+            && (self.block_length() as u8) <= 11
+            && self.card_command_classes() & 1 != 0
+    }
+}
+impl Plausible for CSD<EMMC> {
+    fn is_plausible(&self) -> bool {
+        CSD::<EMMC>::is_plausible(self)
+    }
+}
+
 impl fmt::Debug for CSD<EMMC> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CSD: Card Specific Data")
@@ -152,6 +222,13 @@ impl CardStatus<EMMC> {
         self.0 & 0x40 != 0
     }
 }
+
+/// Bit 3, AKE_SEQ_ERROR on SD cards, is reserved
+impl CardStatusExt for EMMC {
+    fn specific_error(_: u32) -> Option<StatusError> {
+        None
+    }
+}
 impl fmt::Debug for CardStatus<EMMC> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Card Status")
@@ -183,6 +260,497 @@ impl fmt::Debug for CardStatus<EMMC> {
     }
 }
 
+/// Exception events of the EXCEPTION_EVENTS_STATUS and
+/// EXCEPTION_EVENTS_CTRL fields of the EXT_CSD
+///
+/// Ref JESD84-B51 Section 7.4.32
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct ExceptionEvents(pub u16);
+
+impl ExceptionEvents {
+    /// Urgent background operations needed. Status only, always enabled
+    pub const URGENT_BKOPS: u16 = 1 << 0;
+    /// Device needs the host to release capacity
+    pub const DYNCAP_NEEDED: u16 = 1 << 1;
+    /// System pool exhausted
+    pub const SYSPOOL_EXHAUSTED: u16 = 1 << 2;
+    /// Packed command failed
+    pub const PACKED_FAILURE: u16 = 1 << 3;
+    /// Extended security protocol failed
+    pub const EXTENDED_SECURITY_FAILURE: u16 = 1 << 4;
+
+    pub fn urgent_bkops(&self) -> bool {
+        self.0 & Self::URGENT_BKOPS != 0
+    }
+    pub fn dyncap_needed(&self) -> bool {
+        self.0 & Self::DYNCAP_NEEDED != 0
+    }
+    pub fn syspool_exhausted(&self) -> bool {
+        self.0 & Self::SYSPOOL_EXHAUSTED != 0
+    }
+    pub fn packed_failure(&self) -> bool {
+        self.0 & Self::PACKED_FAILURE != 0
+    }
+    pub fn extended_security_failure(&self) -> bool {
+        self.0 & Self::EXTENDED_SECURITY_FAILURE != 0
+    }
+}
+impl fmt::Debug for ExceptionEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Exception Events")
+            .field("Urgent BKOPS", &self.urgent_bkops())
+            .field("DYNCAP Needed", &self.dyncap_needed())
+            .field("SYSPOOL Exhausted", &self.syspool_exhausted())
+            .field("Packed Failure", &self.packed_failure())
+            .field(
+                "Extended Security Failure",
+                &self.extended_security_failure(),
+            )
+            .finish()
+    }
+}
+
+/// I/O driver strength types
+///
+/// Ref JESD84-B51 Section 10.5.4
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DriverStrength {
+    /// 50 Ohm, mandatory
+    Type0 = 0,
+    /// 33 Ohm
+    Type1 = 1,
+    /// 66 Ohm
+    Type2 = 2,
+    /// 100 Ohm
+    Type3 = 3,
+    /// 40 Ohm
+    Type4 = 4,
+}
+
+/// Timing interface selected by HS_TIMING
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HsTiming {
+    /// Backwards compatible interface timing
+    Legacy = 0,
+    /// High speed (HS26/HS52/DDR52)
+    HighSpeed = 1,
+    /// HS200
+    HS200 = 2,
+    /// HS400
+    HS400 = 3,
+}
+
+/// Timing modes supported by the device, from the DEVICE_TYPE field of the
+/// EXT_CSD
+///
+/// Ref JEDEC 84-B51 Section 7.4.54 DEVICE_TYPE
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct DeviceTimings(pub u8);
+
+impl DeviceTimings {
+    /// High speed at 26MHz
+    pub const HS26: u8 = 1 << 0;
+    /// High speed at 52MHz
+    pub const HS52: u8 = 1 << 1;
+    /// DDR52 at 1.8V or 3V I/O
+    pub const DDR52_1V8: u8 = 1 << 2;
+    /// DDR52 at 1.2V I/O
+    pub const DDR52_1V2: u8 = 1 << 3;
+    /// HS200 at 1.8V I/O
+    pub const HS200_1V8: u8 = 1 << 4;
+    /// HS200 at 1.2V I/O
+    pub const HS200_1V2: u8 = 1 << 5;
+    /// HS400 at 1.8V I/O
+    pub const HS400_1V8: u8 = 1 << 6;
+    /// HS400 at 1.2V I/O
+    pub const HS400_1V2: u8 = 1 << 7;
+
+    /// High speed at 26MHz is supported
+    pub fn hs26(&self) -> bool {
+        self.0 & Self::HS26 != 0
+    }
+    /// High speed at 52MHz is supported
+    pub fn hs52(&self) -> bool {
+        self.0 & Self::HS52 != 0
+    }
+    /// DDR52 is supported at any I/O voltage
+    pub fn ddr52(&self) -> bool {
+        self.0 & (Self::DDR52_1V8 | Self::DDR52_1V2) != 0
+    }
+    /// HS200 is supported at any I/O voltage
+    pub fn hs200(&self) -> bool {
+        self.0 & (Self::HS200_1V8 | Self::HS200_1V2) != 0
+    }
+    /// HS400 is supported at any I/O voltage
+    pub fn hs400(&self) -> bool {
+        self.0 & (Self::HS400_1V8 | Self::HS400_1V2) != 0
+    }
+    /// The device supports `timing`
+    pub fn supports(&self, timing: HsTiming) -> bool {
+        match timing {
+            HsTiming::Legacy => true,
+            HsTiming::HighSpeed => self.hs26() || self.hs52(),
+            HsTiming::HS200 => self.hs200(),
+            HsTiming::HS400 => self.hs400(),
+        }
+    }
+    /// Highest bus clock (Hz) for `timing`, or `None` if not supported
+    pub fn max_clock_hz(&self, timing: HsTiming) -> Option<u32> {
+        match timing {
+            HsTiming::Legacy => Some(26_000_000),
+            HsTiming::HighSpeed if self.hs52() => Some(52_000_000),
+            HsTiming::HighSpeed if self.hs26() => Some(26_000_000),
+            HsTiming::HS200 | HsTiming::HS400 if self.supports(timing) => Some(200_000_000),
+            _ => None,
+        }
+    }
+    /// Fastest supported timing
+    pub fn fastest(&self) -> HsTiming {
+        if self.hs400() {
+            HsTiming::HS400
+        } else if self.hs200() {
+            HsTiming::HS200
+        } else if self.supports(HsTiming::HighSpeed) {
+            HsTiming::HighSpeed
+        } else {
+            HsTiming::Legacy
+        }
+    }
+}
+impl fmt::Debug for DeviceTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Device Timings")
+            .field("HS26", &self.hs26())
+            .field("HS52", &self.hs52())
+            .field("DDR52 1.8V/3V", &(self.0 & Self::DDR52_1V8 != 0))
+            .field("DDR52 1.2V", &(self.0 & Self::DDR52_1V2 != 0))
+            .field("HS200 1.8V", &(self.0 & Self::HS200_1V8 != 0))
+            .field("HS200 1.2V", &(self.0 & Self::HS200_1V2 != 0))
+            .field("HS400 1.8V", &(self.0 & Self::HS400_1V8 != 0))
+            .field("HS400 1.2V", &(self.0 & Self::HS400_1V2 != 0))
+            .finish()
+    }
+}
+
+/// Data phase of a transfer: bus width and data rate, as selected by
+/// BUS_WIDTH
+///
+/// In dual data rate modes (DDR52, HS400) data is sampled on both clock
+/// edges, and each data line carries two CRC16s per block: one for the
+/// bytes on the rising edge and one for the falling edge.
+///
+/// Ref JEDEC 84-A43 Section 8.4 BUS_WIDTH and Section 7.11
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DataPhase {
+    /// Number of data lines
+    pub bus_width: BusWidth,
+    /// Dual data rate
+    pub ddr: bool,
+    /// Enhanced strobe (HS400 only)
+    pub enhanced_strobe: bool,
+}
+
+impl DataPhase {
+    /// Single data rate with `bus_width` data lines
+    pub fn sdr(bus_width: BusWidth) -> Self {
+        Self {
+            bus_width,
+            ddr: false,
+            enhanced_strobe: false,
+        }
+    }
+    /// Dual data rate with `bus_width` data lines. 4 or 8 bit only
+    pub fn ddr(bus_width: BusWidth) -> Self {
+        Self {
+            bus_width,
+            ddr: true,
+            enhanced_strobe: false,
+        }
+    }
+    /// Data phase from the BUS_WIDTH byte. `None` if reserved
+    pub fn from_bus_width(value: u8) -> Option<Self> {
+        let mut phase = match value & 0xF {
+            0 => Self::sdr(BusWidth::One),
+            1 => Self::sdr(BusWidth::Four),
+            2 => Self::sdr(BusWidth::Eight),
+            5 => Self::ddr(BusWidth::Four),
+            6 => Self::ddr(BusWidth::Eight),
+            _ => return None,
+        };
+        phase.enhanced_strobe = value & 0x80 != 0;
+        Some(phase)
+    }
+    /// BUS_WIDTH byte selecting this data phase. `None` for unsupported
+    /// combinations (1 bit DDR)
+    pub fn bus_width_value(&self) -> Option<u8> {
+        let value = match (self.bus_width, self.ddr) {
+            (BusWidth::One, false) => 0,
+            (BusWidth::Four, false) => 1,
+            (BusWidth::Eight, false) => 2,
+            (BusWidth::Four, true) => 5,
+            (BusWidth::Eight, true) => 6,
+            _ => return None,
+        };
+        Some(value | if self.enhanced_strobe { 0x80 } else { 0 })
+    }
+    /// Number of CRC16s per block on each data line
+    pub fn crcs_per_line(&self) -> u8 {
+        if self.ddr {
+            2
+        } else {
+            1
+        }
+    }
+    /// Number of CRC bytes transferred after each block, over all data
+    /// lines
+    pub fn crc_bytes_per_block(&self) -> u32 {
+        u32::from(self.bus_width as u8) * u32::from(self.crcs_per_line()) * 2
+    }
+}
+
+/// Supply voltage a power class is selected for
+///
+/// The PWR_CL fields are given for a VCC level. VCCQ only decides whether
+/// HS200 and HS400 are available.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PowerClassVoltage {
+    /// 1.1 - 1.3V, VCCQ only
+    V1_2,
+    /// 1.70 - 1.95V
+    V1_8,
+    /// 2.7 - 3.6V
+    V3_3,
+}
+
+/// Level of outstanding background operations (BKOPS_STATUS)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BkopsStatus {
+    NotRequired,
+    Outstanding,
+    PerformanceImpacted,
+    Critical,
+}
+
+/// Partition booted from, selected by BOOT_PARTITION_ENABLE
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BootPartition {
+    /// Device not boot enabled
+    NotEnabled = 0,
+    /// Boot partition 1
+    Boot1 = 1,
+    /// Boot partition 2
+    Boot2 = 2,
+    /// User area
+    User = 7,
+}
+
+/// Partition accessed by read and write commands, selected by
+/// PARTITION_ACCESS
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PartitionAccess {
+    /// User area, the default
+    User,
+    /// Boot partition 1
+    Boot1,
+    /// Boot partition 2
+    Boot2,
+    /// Replay Protected Memory Block
+    Rpmb,
+    /// General purpose partition 1 - 4
+    General(u8),
+}
+
+impl PartitionAccess {
+    /// PARTITION_ACCESS value, or `None` for a general purpose partition
+    /// other than 1 - 4
+    pub fn value(&self) -> Option<u8> {
+        match *self {
+            PartitionAccess::User => Some(0),
+            PartitionAccess::Boot1 => Some(1),
+            PartitionAccess::Boot2 => Some(2),
+            PartitionAccess::Rpmb => Some(3),
+            PartitionAccess::General(n @ 1..=4) => Some(3 + n),
+            PartitionAccess::General(_) => None,
+        }
+    }
+}
+
+/// PARTITION_CONFIG field of the EXT_CSD
+///
+/// Ref JEDEC 84-A43 Section 8.4 PARTITION_CONFIG
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct PartitionConfig(pub u8);
+
+impl PartitionConfig {
+    /// Configuration booting from `boot` and accessing `access`. Returns
+    /// `None` if `access` is not a valid partition
+    pub fn new(boot_ack: bool, boot: BootPartition, access: PartitionAccess) -> Option<Self> {
+        let access = access.value()?;
+        Some(Self(u8::from(boot_ack) << 6 | (boot as u8) << 3 | access))
+    }
+    /// BOOT_ACK: The device sends boot acknowledge during boot
+    pub fn boot_ack(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+    /// BOOT_PARTITION_ENABLE, or `None` if reserved
+    pub fn boot_partition(&self) -> Option<BootPartition> {
+        match (self.0 >> 3) & 0x7 {
+            0 => Some(BootPartition::NotEnabled),
+            1 => Some(BootPartition::Boot1),
+            2 => Some(BootPartition::Boot2),
+            7 => Some(BootPartition::User),
+            _ => None,
+        }
+    }
+    /// PARTITION_ACCESS
+    pub fn partition_access(&self) -> PartitionAccess {
+        match self.0 & 0x7 {
+            0 => PartitionAccess::User,
+            1 => PartitionAccess::Boot1,
+            2 => PartitionAccess::Boot2,
+            3 => PartitionAccess::Rpmb,
+            n => PartitionAccess::General(n - 3),
+        }
+    }
+}
+impl fmt::Debug for PartitionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Partition Config")
+            .field("Boot Ack", &self.boot_ack())
+            .field("Boot Partition", &self.boot_partition())
+            .field("Partition Access", &self.partition_access())
+            .finish()
+    }
+}
+
+/// Timing of the boot operation, selected by BOOT_MODE
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BootMode {
+    /// Single data rate, backward compatible timing
+    Sdr = 0,
+    /// Single data rate, high speed timing
+    SdrHighSpeed = 1,
+    /// Dual data rate
+    Ddr = 2,
+}
+
+/// BOOT_BUS_CONDITIONS field of the EXT_CSD
+///
+/// Ref JEDEC 84-A43 Section 8.4 BOOT_BUS_CONDITIONS
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct BootBusConditions(pub u8);
+
+impl BootBusConditions {
+    /// BOOT_MODE, or `None` if reserved
+    pub fn boot_mode(&self) -> Option<BootMode> {
+        match (self.0 >> 3) & 0x3 {
+            0 => Some(BootMode::Sdr),
+            1 => Some(BootMode::SdrHighSpeed),
+            2 => Some(BootMode::Ddr),
+            _ => None,
+        }
+    }
+    /// RESET_BOOT_BUS_CONDITIONS: The bus width and mode are kept after
+    /// boot, otherwise they are reset to 1 bit backward compatible timing
+    pub fn retain_after_boot(&self) -> bool {
+        self.0 & 0x4 != 0
+    }
+    /// BOOT_BUS_WIDTH
+    pub fn bus_width(&self) -> BusWidth {
+        match self.0 & 0x3 {
+            0 => BusWidth::One,
+            1 => BusWidth::Four,
+            2 => BusWidth::Eight,
+            _ => BusWidth::Unknown,
+        }
+    }
+}
+impl fmt::Debug for BootBusConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Boot Bus Conditions")
+            .field("Boot Mode", &self.boot_mode())
+            .field("Retain After Boot", &self.retain_after_boot())
+            .field("Bus Width", &self.bus_width())
+            .finish()
+    }
+}
+
+/// Estimated device lifetime used (DEVICE_LIFE_TIME_EST_TYP_A/B)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LifeTimeEstimate {
+    /// Not reported by the device
+    NotDefined,
+    /// Between `n - 10` and `n` percent of the lifetime used, with `n` a
+    /// multiple of 10
+    Used(u8),
+    /// The estimated lifetime has been exceeded
+    Exceeded,
+    /// Reserved value
+    Reserved(u8),
+}
+
+impl From<u8> for LifeTimeEstimate {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => LifeTimeEstimate::NotDefined,
+            0x01..=0x0A => LifeTimeEstimate::Used(value * 10),
+            0x0B => LifeTimeEstimate::Exceeded,
+            _ => LifeTimeEstimate::Reserved(value),
+        }
+    }
+}
+
+/// Consumption of reserved blocks (PRE_EOL_INFO)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PreEolInfo {
+    /// Not reported by the device
+    NotDefined,
+    /// Normal
+    Normal,
+    /// 80% of the reserved blocks are consumed
+    Warning,
+    /// 90% of the reserved blocks are consumed
+    Urgent,
+    /// Reserved value
+    Reserved(u8),
+}
+
+impl From<u8> for PreEolInfo {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => PreEolInfo::NotDefined,
+            0x01 => PreEolInfo::Normal,
+            0x02 => PreEolInfo::Warning,
+            0x03 => PreEolInfo::Urgent,
+            _ => PreEolInfo::Reserved(value),
+        }
+    }
+}
+
+/// eMMC specification version, from EXT_CSD_REV
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EmmcVersion {
+    /// Version 4.0
+    V4_0,
+    /// Version 4.1
+    V4_1,
+    /// Version 4.2
+    V4_2,
+    /// Version 4.3
+    V4_3,
+    /// Version 4.41
+    V4_41,
+    /// Version 4.5 and 4.51
+    V4_5,
+    /// Version 5.0 and 5.01
+    V5_0,
+    /// Version 5.1
+    V5_1,
+    /// Version not known by this crate
+    Unknown,
+}
+
 /// Extended Card Specific Data
 ///
 /// Ref JEDEC 84-A43 Section 8.4
@@ -201,7 +769,415 @@ impl From<[u32; 128]> for ExtCSD {
         Self { inner }
     }
 }
+/// From the 512 byte data block returned by CMD8, in transfer order
+impl From<[u8; 512]> for ExtCSD {
+    fn from(bytes: [u8; 512]) -> Self {
+        let mut inner = [0; 128];
+        for (word, chunk) in inner.iter_mut().zip(bytes.chunks(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        Self { inner }
+    }
+}
+
+impl Geometry {
+    /// eMMC geometry from the EXT_CSD. eMMC has no allocation unit, the
+    /// high capacity erase group is used instead
+    pub fn emmc(ext_csd: &ExtCSD) -> Self {
+        let erase_group = ext_csd.erase_group_blocks();
+        Geometry {
+            block_size: 512,
+            block_count: u64::from(ext_csd.sector_count()),
+            erase_sector_blocks: erase_group,
+            au_size: erase_group,
+        }
+    }
+}
 impl ExtCSD {
+    /// Raw register words
+    pub fn raw(&self) -> &[u32; 128] {
+        &self.inner
+    }
+    /// Words, as accepted by `From<[u32; 128]>`
+    pub fn as_words(&self) -> [u32; 128] {
+        self.inner
+    }
+    /// Register bytes, in transfer order, as accepted by `From<[u8; 512]>`
+    pub fn to_bytes(&self) -> [u8; 512] {
+        let mut bytes = [0; 512];
+        for (chunk, word) in bytes.chunks_mut(4).zip(self.inner.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+    /// Byte `index` of the EXT_CSD
+    fn byte(&self, index: usize) -> u8 {
+        (self.inner[index / 4] >> (24 - 8 * (index % 4))) as u8
+    }
+    /// EXCEPTION_EVENTS_STATUS: Exception events that have occurred
+    pub fn exception_events_status(&self) -> ExceptionEvents {
+        ExceptionEvents(u16::from_le_bytes([self.byte(54), self.byte(55)]))
+    }
+    /// EXCEPTION_EVENTS_CTRL: Exception events that raise the
+    /// EXCEPTION_EVENT bit of the card status
+    pub fn exception_events_ctrl(&self) -> ExceptionEvents {
+        ExceptionEvents(u16::from_le_bytes([self.byte(56), self.byte(57)]))
+    }
+    /// DYNCAP_NEEDED: Number of write protect groups the device asks the
+    /// host to release
+    pub fn dyncap_needed(&self) -> u8 {
+        self.byte(58)
+    }
+    /// HC_WP_GRP_SIZE: Write protect group size in units of erase groups
+    pub fn hc_wp_group_size(&self) -> u8 {
+        self.byte(221)
+    }
+    /// Write protect group size in 512 byte blocks
+    pub fn wp_group_blocks(&self) -> u32 {
+        u32::from(self.hc_wp_group_size()) * self.erase_group_blocks()
+    }
+    /// Number of 512 byte blocks the host should release to satisfy
+    /// DYNCAP_NEEDED. Released ranges must be aligned to write protect
+    /// groups
+    pub fn dyncap_release_blocks(&self) -> u64 {
+        u64::from(self.dyncap_needed()) * u64::from(self.wp_group_blocks())
+    }
+    /// BKOPS_EN: Manual background operations are enabled
+    pub fn bkops_manual_enabled(&self) -> bool {
+        self.byte(163) & 0x1 != 0
+    }
+    /// BKOPS_EN: Device may perform background operations autonomously
+    pub fn bkops_auto_enabled(&self) -> bool {
+        self.byte(163) & 0x2 != 0
+    }
+    /// BKOPS_STATUS: Level of outstanding background operations
+    pub fn bkops_status(&self) -> BkopsStatus {
+        match self.byte(246) & 0x3 {
+            0 => BkopsStatus::NotRequired,
+            1 => BkopsStatus::Outstanding,
+            2 => BkopsStatus::PerformanceImpacted,
+            _ => BkopsStatus::Critical,
+        }
+    }
+    /// BKOPS_SUPPORT: Background operations are supported
+    pub fn bkops_support(&self) -> bool {
+        self.byte(502) & 0x1 != 0
+    }
+    /// CACHE_SIZE: Size of the volatile cache in KiB. Zero if the device
+    /// has no cache
+    pub fn cache_size_kib(&self) -> u32 {
+        u32::from_le_bytes([
+            self.byte(249),
+            self.byte(250),
+            self.byte(251),
+            self.byte(252),
+        ])
+    }
+    /// Size of the volatile cache in bytes
+    pub fn cache_size_bytes(&self) -> u64 {
+        u64::from(self.cache_size_kib()) * 1024
+    }
+    /// CACHE_CTRL: The volatile cache is enabled
+    pub fn cache_enabled(&self) -> bool {
+        self.byte(33) & 0x1 != 0
+    }
+    /// CACHE_FLUSH_POLICY: The device flushes the cache in the order the
+    /// data was written
+    pub fn cache_flush_fifo(&self) -> bool {
+        self.byte(240) & 0x1 != 0
+    }
+    /// BARRIER_SUPPORT: Cache barrier commands are supported
+    pub fn barrier_support(&self) -> bool {
+        self.byte(486) & 0x1 != 0
+    }
+    /// BARRIER_CTRL: Cache barrier commands are enabled
+    pub fn barrier_enabled(&self) -> bool {
+        self.byte(31) & 0x1 != 0
+    }
+    /// PRE_EOL_INFO: Device life time reflected by the average reserved
+    /// blocks
+    pub fn pre_eol_info(&self) -> PreEolInfo {
+        self.byte(267).into()
+    }
+    /// DEVICE_LIFE_TIME_EST_TYP_A: Estimated life time used of the SLC
+    /// area (type A memory)
+    pub fn life_time_est_a(&self) -> LifeTimeEstimate {
+        self.byte(268).into()
+    }
+    /// DEVICE_LIFE_TIME_EST_TYP_B: Estimated life time used of the MLC
+    /// area (type B memory)
+    pub fn life_time_est_b(&self) -> LifeTimeEstimate {
+        self.byte(269).into()
+    }
+    /// LARGE_UNIT_SIZE_M1: Large unit size in MiB, minus one
+    pub fn large_unit_size_m1(&self) -> u8 {
+        self.byte(495)
+    }
+    /// Large unit size in bytes
+    pub fn large_unit_size_bytes(&self) -> u32 {
+        (u32::from(self.large_unit_size_m1()) + 1) * 1024 * 1024
+    }
+    /// OPTIMAL_READ_SIZE: Optimal read size in units of 4KiB. Zero if not
+    /// defined
+    pub fn optimal_read_size(&self) -> u8 {
+        self.byte(266)
+    }
+    /// Optimal read size in bytes. Zero if not defined
+    pub fn optimal_read_size_bytes(&self) -> u32 {
+        u32::from(self.optimal_read_size()) * 4096
+    }
+    /// OPTIMAL_WRITE_SIZE: Optimal write size in units of 4KiB. Zero if not
+    /// defined
+    pub fn optimal_write_size(&self) -> u8 {
+        self.byte(265)
+    }
+    /// Optimal write size in bytes. Zero if not defined
+    pub fn optimal_write_size_bytes(&self) -> u32 {
+        u32::from(self.optimal_write_size()) * 4096
+    }
+    /// OPTIMAL_TRIM_UNIT_SIZE: Optimal trim unit size is 4KiB * 2^(N - 1).
+    /// Zero if not defined
+    pub fn optimal_trim_unit_size(&self) -> u8 {
+        self.byte(264)
+    }
+    /// Optimal trim unit size in bytes. `None` if not defined, or if the
+    /// (reserved) value does not fit in a u64
+    pub fn optimal_trim_unit_size_bytes(&self) -> Option<u64> {
+        match self.optimal_trim_unit_size() {
+            0 => None,
+            // 4KiB * 2^(N - 1) == 2^(N + 11)
+            n => 1u64.checked_shl(u32::from(n) + 11),
+        }
+    }
+    /// CMDQ_SUPPORT: The device supports command queueing
+    pub fn cmdq_support(&self) -> bool {
+        self.byte(308) & 0x1 != 0
+    }
+    /// CMDQ_DEPTH: Number of tasks the command queue holds, zero if not
+    /// supported
+    pub fn cmdq_depth(&self) -> u8 {
+        if self.cmdq_support() {
+            (self.byte(307) & 0x1F) + 1
+        } else {
+            0
+        }
+    }
+    /// CMDQ_MODE_EN: Command queueing is enabled
+    pub fn cmdq_enabled(&self) -> bool {
+        self.byte(15) & 0x1 != 0
+    }
+    /// DATA_TAG_SUPPORT: The device supports the data tag mechanism
+    pub fn data_tag_support(&self) -> bool {
+        self.byte(499) & 0x1 != 0
+    }
+    /// TAG_UNIT_SIZE: Tag unit is 2^N sectors
+    pub fn tag_unit_size(&self) -> u8 {
+        self.byte(498)
+    }
+    /// TAG_RES_SIZE: Resources available for tagged data, as a fraction of
+    /// the device size. Ref JESD84-B51 Section 7.4.14
+    pub fn tag_resources_size(&self) -> u8 {
+        self.byte(497)
+    }
+    /// SEC_FEATURE_SUPPORT: Supported secure and trim features
+    pub fn sec_feature_support(&self) -> u8 {
+        self.byte(231)
+    }
+    /// SEC_GB_CL_EN: TRIM and secure trim are supported
+    pub fn trim_support(&self) -> bool {
+        self.sec_feature_support() & 0x10 != 0
+    }
+    /// DISCARD is supported. Requires TRIM support and eMMC 4.5 or later
+    pub fn discard_support(&self) -> bool {
+        self.trim_support() && self.extended_csd_revision() >= 6
+    }
+    /// ERASE_TIMEOUT_MULT: Erase timeout per erase group in units of 300ms
+    pub fn erase_timeout_mult(&self) -> u8 {
+        self.byte(223)
+    }
+    /// HC_ERASE_GRP_SIZE: Erase group size in units of 512KiB
+    pub fn hc_erase_group_size(&self) -> u8 {
+        self.byte(224)
+    }
+    /// Erase group size in 512 byte blocks
+    pub fn erase_group_blocks(&self) -> u32 {
+        u32::from(self.hc_erase_group_size()) * 1024
+    }
+    /// SEC_TRIM_MULT: Secure trim timeout multiplier
+    pub fn sec_trim_mult(&self) -> u8 {
+        self.byte(229)
+    }
+    /// SEC_ERASE_MULT: Secure erase timeout multiplier
+    pub fn sec_erase_mult(&self) -> u8 {
+        self.byte(230)
+    }
+    /// TRIM_MULT: Trim timeout in units of 300ms
+    pub fn trim_mult(&self) -> u8 {
+        self.byte(232)
+    }
+    /// Upper bound for the busy time of CMD38, in milliseconds
+    ///
+    /// * `erase_groups` - Number of erase groups spanned by the range
+    ///
+    /// Assumes high capacity erase groups (ERASE_GROUP_DEF set).
+    pub fn erase_timeout_ms(&self, function: EraseFunction, erase_groups: u32) -> u64 {
+        let erase = u64::from(self.erase_timeout_mult());
+        let per_group = 300
+            * match function {
+                EraseFunction::Erase => erase,
+                EraseFunction::Trim | EraseFunction::Discard => u64::from(self.trim_mult()),
+                EraseFunction::SecureErase => erase * u64::from(self.sec_erase_mult()),
+                EraseFunction::SecureTrimStep1 | EraseFunction::SecureTrimStep2 => {
+                    erase * u64::from(self.sec_trim_mult())
+                }
+            };
+        per_group * u64::from(erase_groups)
+    }
+    /// PWR_CL_52_195: Power classes at 52MHz and VCC 1.95V
+    pub fn pwr_cl_52_195(&self) -> u8 {
+        self.byte(200)
+    }
+    /// PWR_CL_26_195: Power classes at 26MHz and VCC 1.95V
+    pub fn pwr_cl_26_195(&self) -> u8 {
+        self.byte(201)
+    }
+    /// PWR_CL_52_360: Power classes at 52MHz and VCC 3.6V
+    pub fn pwr_cl_52_360(&self) -> u8 {
+        self.byte(202)
+    }
+    /// PWR_CL_26_360: Power classes at 26MHz and VCC 3.6V
+    pub fn pwr_cl_26_360(&self) -> u8 {
+        self.byte(203)
+    }
+    /// PWR_CL_200_195: Power classes at 200MHz and VCC 1.95V
+    pub fn pwr_cl_200_195(&self) -> u8 {
+        self.byte(236)
+    }
+    /// PWR_CL_200_360: Power classes at 200MHz and VCC 3.6V
+    pub fn pwr_cl_200_360(&self) -> u8 {
+        self.byte(237)
+    }
+    /// PWR_CL_DDR_52_195: Power classes at 52MHz DDR and VCC 1.95V
+    pub fn pwr_cl_ddr_52_195(&self) -> u8 {
+        self.byte(238)
+    }
+    /// PWR_CL_DDR_52_360: Power classes at 52MHz DDR and VCC 3.6V
+    pub fn pwr_cl_ddr_52_360(&self) -> u8 {
+        self.byte(239)
+    }
+    /// PWR_CL_DDR_200_360: Power classes of HS400 at VCC 3.6V
+    pub fn pwr_cl_ddr_200_360(&self) -> u8 {
+        self.byte(253)
+    }
+    /// POWER_CLASS: Selected power class
+    pub fn power_class(&self) -> u8 {
+        self.byte(187) & 0xF
+    }
+    /// Power class the host writes to POWER_CLASS before switching to
+    /// `timing` and `phase`
+    ///
+    /// The field is picked by VCC as Linux' `mmc_select_powerclass` does.
+    /// Returns `None` for combinations without a power class, eg. HS200 at
+    /// 3.3V VCCQ or 4 bit HS400. 1 bit buses always use class 0.
+    ///
+    /// Ref JEDEC 84-B51 Section 6.6.5
+    pub fn power_class_for(
+        &self,
+        timing: HsTiming,
+        phase: DataPhase,
+        vcc: PowerClassVoltage,
+        vccq: PowerClassVoltage,
+    ) -> Option<u8> {
+        use PowerClassVoltage::*;
+        if phase.bus_width == BusWidth::One {
+            return Some(0);
+        }
+        let field = match (timing, phase.ddr, vcc) {
+            (HsTiming::Legacy, false, V1_8) => self.pwr_cl_26_195(),
+            (HsTiming::Legacy, false, V3_3) => self.pwr_cl_26_360(),
+            (HsTiming::HighSpeed, false, V1_8) => self.pwr_cl_52_195(),
+            (HsTiming::HighSpeed, false, V3_3) => self.pwr_cl_52_360(),
+            (HsTiming::HighSpeed, true, V1_8) => self.pwr_cl_ddr_52_195(),
+            (HsTiming::HighSpeed, true, V3_3) => self.pwr_cl_ddr_52_360(),
+            (HsTiming::HS200, false, _) | (HsTiming::HS400, true, _) if vccq == V3_3 => {
+                return None;
+            }
+            (HsTiming::HS400, true, _) if phase.bus_width != BusWidth::Eight => return None,
+            (HsTiming::HS200, false, V1_8) | (HsTiming::HS400, true, V1_8) => {
+                self.pwr_cl_200_195()
+            }
+            (HsTiming::HS200, false, V3_3) => self.pwr_cl_200_360(),
+            (HsTiming::HS400, true, V3_3) => self.pwr_cl_ddr_200_360(),
+            _ => return None,
+        };
+        match phase.bus_width {
+            BusWidth::Four => Some(field & 0xF),
+            BusWidth::Eight => Some(field >> 4),
+            _ => None,
+        }
+    }
+    /// BOOT_SIZE_MULT: Size of each boot partition in units of 128KiB
+    pub fn boot_size_mult(&self) -> u8 {
+        self.byte(226)
+    }
+    /// Size of each boot partition in bytes
+    pub fn boot_partition_bytes(&self) -> u32 {
+        u32::from(self.boot_size_mult()) * 128 * 1024
+    }
+    /// RPMB_SIZE_MULT: Size of the RPMB partition in units of 128KiB
+    pub fn rpmb_size_mult(&self) -> u8 {
+        self.byte(168)
+    }
+    /// Size of the RPMB partition in bytes
+    pub fn rpmb_partition_bytes(&self) -> u32 {
+        u32::from(self.rpmb_size_mult()) * 128 * 1024
+    }
+    /// REL_WR_SEC_C: Number of sectors written atomically by a reliable
+    /// write. Only used by devices without enhanced reliable write
+    pub fn rel_wr_sec_c(&self) -> u8 {
+        self.byte(222)
+    }
+    /// WR_REL_PARAM: The reliability settings of the partitions can be
+    /// changed with WR_REL_SET
+    pub fn wr_rel_set_supported(&self) -> bool {
+        self.byte(166) & 0x01 != 0
+    }
+    /// WR_REL_PARAM: The device supports enhanced reliable write, with a
+    /// granularity of a single sector
+    pub fn enhanced_reliable_write(&self) -> bool {
+        self.byte(166) & 0x04 != 0
+    }
+    /// WR_REL_PARAM: RPMB transfers of up to 8KiB are written reliably,
+    /// otherwise only 256 or 512 bytes
+    pub fn rpmb_reliable_write_8k(&self) -> bool {
+        self.byte(166) & 0x10 != 0
+    }
+    /// Number of 512 byte sectors written atomically by a reliable write
+    pub fn reliable_write_sectors(&self) -> u32 {
+        if self.enhanced_reliable_write() {
+            1
+        } else {
+            u32::from(self.rel_wr_sec_c())
+        }
+    }
+    /// PARTITION_CONFIG: Boot partition and partition access
+    pub fn partition_config(&self) -> PartitionConfig {
+        PartitionConfig(self.byte(179))
+    }
+    /// BOOT_CONFIG_PROT: PARTITION_CONFIG and BOOT_BUS_CONDITIONS are
+    /// permanently write protected
+    pub fn boot_config_perm_protected(&self) -> bool {
+        self.byte(178) & 0x10 != 0
+    }
+    /// BOOT_CONFIG_PROT: PARTITION_CONFIG and BOOT_BUS_CONDITIONS are
+    /// write protected until the next power cycle
+    pub fn boot_config_pwr_protected(&self) -> bool {
+        self.byte(178) & 0x01 != 0
+    }
+    /// BOOT_BUS_CONDITIONS: Bus width and timing used during boot
+    pub fn boot_bus_conditions(&self) -> BootBusConditions {
+        BootBusConditions(self.byte(177))
+    }
     pub fn boot_info(&self) -> u8 {
         // byte 228
         (self.inner[57] >> 24) as u8
@@ -214,18 +1190,51 @@ impl ExtCSD {
         // byte 216
         (self.inner[54] >> 24) as u8
     }
+    /// SEC_COUNT: Device density in 512 byte sectors, for sector mode
+    /// devices. Zero for byte mode devices, use the CSD instead
     pub fn sector_count(&self) -> u32 {
-        // bytes [215:212]
-        self.inner[53]
+        // bytes [215:212], least significant byte first
+        u32::from_le_bytes([
+            self.byte(212),
+            self.byte(213),
+            self.byte(214),
+            self.byte(215),
+        ])
+    }
+    /// Device capacity in bytes, from SEC_COUNT
+    pub fn device_capacity_bytes(&self) -> u64 {
+        u64::from(self.sector_count()) * 512
     }
     pub fn driver_strength(&self) -> u8 {
         // byte 197
         (self.inner[49] >> 16) as u8
     }
+    /// The device supports driver strength `strength` in HS200/HS400 mode
+    pub fn supports_driver_strength(&self, strength: DriverStrength) -> bool {
+        self.driver_strength() & (1 << strength as u8) != 0
+    }
+    /// BUS_WIDTH: Data phase selected for transfers, or `None` if reserved
+    pub fn data_phase(&self) -> Option<DataPhase> {
+        DataPhase::from_bus_width(self.byte(183))
+    }
+    /// HS_TIMING: Selected timing interface, or `None` if reserved
+    pub fn hs_timing(&self) -> Option<HsTiming> {
+        match self.byte(185) & 0xF {
+            0 => Some(HsTiming::Legacy),
+            1 => Some(HsTiming::HighSpeed),
+            2 => Some(HsTiming::HS200),
+            3 => Some(HsTiming::HS400),
+            _ => None,
+        }
+    }
     pub fn card_type(&self) -> u8 {
         // byte 196
         (self.inner[49] >> 24) as u8
     }
+    /// DEVICE_TYPE: Supported timing modes
+    pub fn device_timings(&self) -> DeviceTimings {
+        DeviceTimings(self.card_type())
+    }
     pub fn csd_structure_version(&self) -> u8 {
         // byte 194
         (self.inner[48] >> 8) as u8
@@ -234,6 +1243,20 @@ impl ExtCSD {
         // byte 192
         (self.inner[48] >> 24) as u8
     }
+    /// Specification version the device conforms to
+    pub fn version(&self) -> EmmcVersion {
+        match self.extended_csd_revision() {
+            0 => EmmcVersion::V4_0,
+            1 => EmmcVersion::V4_1,
+            2 => EmmcVersion::V4_2,
+            3 => EmmcVersion::V4_3,
+            5 => EmmcVersion::V4_41,
+            6 => EmmcVersion::V4_5,
+            7 => EmmcVersion::V5_0,
+            8 => EmmcVersion::V5_1,
+            _ => EmmcVersion::Unknown,
+        }
+    }
     pub fn data_sector_size(&self) -> u8 {
         // byte 61
         (self.inner[15] >> 16) as u8
@@ -246,14 +1269,52 @@ impl ExtCSD {
 impl fmt::Debug for ExtCSD {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Extended CSD")
+            .field("Exception Events Status", &self.exception_events_status())
+            .field("Exception Events Ctrl", &self.exception_events_ctrl())
+            .field("DYNCAP Needed", &self.dyncap_needed())
+            .field("BKOPS Support", &self.bkops_support())
+            .field("BKOPS Status", &self.bkops_status())
+            .field("Cache Size (KiB)", &self.cache_size_kib())
+            .field("Cache Enabled", &self.cache_enabled())
+            .field("Cache Flush FIFO", &self.cache_flush_fifo())
+            .field("Barrier Support", &self.barrier_support())
+            .field("Pre EOL Info", &self.pre_eol_info())
+            .field("Life Time Estimate A", &self.life_time_est_a())
+            .field("Life Time Estimate B", &self.life_time_est_b())
+            .field("Command Queue Depth", &self.cmdq_depth())
+            .field("Command Queue Enabled", &self.cmdq_enabled())
+            .field("Large Unit Size (B)", &self.large_unit_size_bytes())
+            .field("Optimal Read Size (B)", &self.optimal_read_size_bytes())
+            .field("Optimal Write Size (B)", &self.optimal_write_size_bytes())
+            .field(
+                "Optimal Trim Unit Size (B)",
+                &self.optimal_trim_unit_size_bytes(),
+            )
             .field("Boot Info", &self.boot_info())
+            .field("Boot Partition Size (B)", &self.boot_partition_bytes())
+            .field("Partition Config", &self.partition_config())
+            .field("RPMB Partition Size (B)", &self.rpmb_partition_bytes())
+            .field("Reliable Write Sectors", &self.reliable_write_sectors())
+            .field("Boot Bus Conditions", &self.boot_bus_conditions())
+            .field(
+                "Boot Config Protection",
+                &(
+                    self.boot_config_perm_protected(),
+                    self.boot_config_pwr_protected(),
+                ),
+            )
             .field("Sleep/Awake Timeout", &self.sleep_awake_timeout())
             .field("Sleep Notification Time", &self.sleep_notification_time())
             .field("Sector Count", &self.sector_count())
+            .field("Capacity (bytes)", &self.device_capacity_bytes())
             .field("Driver Strength", &self.driver_strength())
-            .field("Card Type", &self.card_type())
+            .field("HS Timing", &self.hs_timing())
+            .field("Power Class", &self.power_class())
+            .field("Data Phase", &self.data_phase())
+            .field("Device Timings", &self.device_timings())
             .field("CSD Structure Version", &self.csd_structure_version())
             .field("Extended CSD Revision", &self.extended_csd_revision())
+            .field("Version", &self.version())
             .field("Sector Size", &self.data_sector_size())
             .field("Secure removal type", &self.secure_removal_type())
             .finish()
@@ -267,3 +1328,200 @@ impl From<u16> for RCA<EMMC> {
         Self::from((address as u32) << 16)
     }
 }
+
+// Ref JEDEC 84-B51 Table 76
+static CID_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("MID", 127, 120),
+    FieldDesc::names("CBX", 113, 112, &["Removable", "BGA", "POP"]),
+    FieldDesc::new("OID", 111, 104),
+    FieldDesc::ascii("PNM", 103, 56),
+    FieldDesc::new("PRV", 55, 48),
+    FieldDesc::new("PSN", 47, 16),
+    FieldDesc::new("MDT", 15, 8),
+    FieldDesc::new("CRC", 7, 1),
+];
+
+// Ref JEDEC 84-B51 Table 78
+static CSD_FIELDS: &[FieldDesc] = &[
+    FieldDesc::names("CSD_STRUCTURE", 127, 126, &["1.0", "1.1", "1.2", "EXT_CSD"]),
+    FieldDesc::names(
+        "SPEC_VERS",
+        125,
+        122,
+        &["1.0 - 1.2", "1.4", "2.0 - 2.2", "3.1 - 3.31", "4.x"],
+    ),
+    FieldDesc::new("TAAC", 119, 112),
+    FieldDesc::new("NSAC", 111, 104),
+    FieldDesc::new("TRAN_SPEED", 103, 96),
+    FieldDesc::new("CCC", 95, 84),
+    FieldDesc::new("READ_BL_LEN", 83, 80),
+    FieldDesc::new("READ_BL_PARTIAL", 79, 79),
+    FieldDesc::new("WRITE_BLK_MISALIGN", 78, 78),
+    FieldDesc::new("READ_BLK_MISALIGN", 77, 77),
+    FieldDesc::new("DSR_IMP", 76, 76),
+    FieldDesc::new("C_SIZE", 73, 62),
+    FieldDesc::new("VDD_R_CURR_MIN", 61, 59),
+    FieldDesc::new("VDD_R_CURR_MAX", 58, 56),
+    FieldDesc::new("VDD_W_CURR_MIN", 55, 53),
+    FieldDesc::new("VDD_W_CURR_MAX", 52, 50),
+    FieldDesc::new("C_SIZE_MULT", 49, 47),
+    FieldDesc::new("ERASE_GRP_SIZE", 46, 42),
+    FieldDesc::new("ERASE_GRP_MULT", 41, 37),
+    FieldDesc::new("WP_GRP_SIZE", 36, 32),
+    FieldDesc::new("WP_GRP_ENABLE", 31, 31),
+    FieldDesc::new("DEFAULT_ECC", 30, 29),
+    FieldDesc::new("R2W_FACTOR", 28, 26),
+    FieldDesc::new("WRITE_BL_LEN", 25, 22),
+    FieldDesc::new("WRITE_BL_PARTIAL", 21, 21),
+    FieldDesc::new("CONTENT_PROT_APP", 16, 16),
+    FieldDesc::new("FILE_FORMAT_GRP", 15, 15),
+    FieldDesc::new("COPY", 14, 14),
+    FieldDesc::new("PERM_WRITE_PROTECT", 13, 13),
+    FieldDesc::new("TMP_WRITE_PROTECT", 12, 12),
+    FieldDesc::new("FILE_FORMAT", 11, 10),
+    FieldDesc::new("ECC", 9, 8),
+    FieldDesc::new("CRC", 7, 1),
+];
+
+// Ref JEDEC 84-B51 Table 53
+static OCR_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("BUSY", 31, 31),
+    FieldDesc::names("ACCESS_MODE", 30, 29, &["Byte", "", "Sector"]),
+    FieldDesc::new("VDD_27_36", 23, 15),
+    FieldDesc::new("VDD_20_26", 14, 8),
+    FieldDesc::new("VDD_170_195", 7, 7),
+];
+
+// Ref JEDEC 84-B51 Table 68
+static CARD_STATUS_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("ADDRESS_OUT_OF_RANGE", 31, 31),
+    FieldDesc::new("ADDRESS_MISALIGN", 30, 30),
+    FieldDesc::new("BLOCK_LEN_ERROR", 29, 29),
+    FieldDesc::new("ERASE_SEQ_ERROR", 28, 28),
+    FieldDesc::new("ERASE_PARAM", 27, 27),
+    FieldDesc::new("WP_VIOLATION", 26, 26),
+    FieldDesc::new("DEVICE_IS_LOCKED", 25, 25),
+    FieldDesc::new("LOCK_UNLOCK_FAILED", 24, 24),
+    FieldDesc::new("COM_CRC_ERROR", 23, 23),
+    FieldDesc::new("ILLEGAL_COMMAND", 22, 22),
+    FieldDesc::new("DEVICE_ECC_FAILED", 21, 21),
+    FieldDesc::new("CC_ERROR", 20, 20),
+    FieldDesc::new("ERROR", 19, 19),
+    FieldDesc::new("CID/CSD_OVERWRITE", 16, 16),
+    FieldDesc::new("WP_ERASE_SKIP", 15, 15),
+    FieldDesc::new("ERASE_RESET", 13, 13),
+    FieldDesc::names("CURRENT_STATE", 12, 9, CURRENT_STATE_NAMES),
+    FieldDesc::new("READY_FOR_DATA", 8, 8),
+    FieldDesc::new("SWITCH_ERROR", 7, 7),
+    FieldDesc::new("EXCEPTION_EVENT", 6, 6),
+    FieldDesc::new("APP_CMD", 5, 5),
+];
+
+/// EXT_CSD field in bytes `msb ..= lsb`
+const fn ext(name: &'static str, msb: u16, lsb: u16) -> FieldDesc {
+    FieldDesc::new(name, msb * 8 + 7, lsb * 8)
+}
+
+/// Single byte EXT_CSD field with named values
+const fn ext_names(name: &'static str, byte: u16, names: &'static [&'static str]) -> FieldDesc {
+    FieldDesc::names(name, byte * 8 + 7, byte * 8, names)
+}
+
+static LIFE_TIME_NAMES: &[&str] = &[
+    "Not defined",
+    "0 - 10%",
+    "10 - 20%",
+    "20 - 30%",
+    "30 - 40%",
+    "40 - 50%",
+    "50 - 60%",
+    "60 - 70%",
+    "70 - 80%",
+    "80 - 90%",
+    "90 - 100%",
+    "Exceeded",
+];
+
+// Ref JEDEC 84-B51 Table 49. Bit n is bit n % 8 of byte n / 8, multi byte
+// fields are little endian
+static EXT_CSD_FIELDS: &[FieldDesc] = &[
+    ext("S_CMD_SET", 504, 504),
+    ext("BKOPS_SUPPORT", 502, 502),
+    ext("CMDQ_SUPPORT", 308, 308),
+    ext("CMDQ_DEPTH", 307, 307),
+    ext_names("DEVICE_LIFE_TIME_EST_TYP_B", 269, LIFE_TIME_NAMES),
+    ext_names("DEVICE_LIFE_TIME_EST_TYP_A", 268, LIFE_TIME_NAMES),
+    ext_names(
+        "PRE_EOL_INFO",
+        267,
+        &["Not defined", "Normal", "Warning", "Urgent"],
+    ),
+    ext("CACHE_SIZE", 252, 249),
+    ext("GENERIC_CMD6_TIME", 248, 248),
+    ext_names(
+        "BKOPS_STATUS",
+        246,
+        &["Not required", "Outstanding", "Performance impacted", "Critical"],
+    ),
+    ext("TRIM_MULT", 232, 232),
+    ext("SEC_ERASE_MULT", 230, 230),
+    ext("SEC_TRIM_MULT", 229, 229),
+    ext("BOOT_SIZE_MULT", 226, 226),
+    ext("HC_ERASE_GRP_SIZE", 224, 224),
+    ext("ERASE_TIMEOUT_MULT", 223, 223),
+    ext("REL_WR_SEC_C", 222, 222),
+    ext("HC_WP_GRP_SIZE", 221, 221),
+    ext("SEC_COUNT", 215, 212),
+    ext("PARTITION_SWITCH_TIME", 199, 199),
+    ext("DEVICE_TYPE", 196, 196),
+    ext_names("CSD_STRUCTURE", 194, &["1.0", "1.1", "1.2"]),
+    ext_names(
+        "EXT_CSD_REV",
+        192,
+        &["4.0", "4.1", "4.2", "4.3", "Obsolete", "4.41", "4.5", "5.0", "5.1"],
+    ),
+    ext("POWER_CLASS", 187, 187),
+    ext("HS_TIMING", 185, 185),
+    ext_names(
+        "BUS_WIDTH",
+        183,
+        &["1 bit", "4 bit", "8 bit", "", "", "4 bit DDR", "8 bit DDR"],
+    ),
+    ext("PARTITION_CONFIG", 179, 179),
+    ext("BOOT_CONFIG_PROT", 178, 178),
+    ext("BOOT_BUS_CONDITIONS", 177, 177),
+    ext("ERASE_GROUP_DEF", 175, 175),
+    ext("RPMB_SIZE_MULT", 168, 168),
+    ext("BKOPS_EN", 163, 163),
+    ext("EXCEPTION_EVENTS_STATUS", 55, 54),
+    ext("CACHE_CTRL", 33, 33),
+    ext("CMDQ_MODE_EN", 15, 15),
+];
+
+impl Register for CID<EMMC> {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(CID_FIELDS, &self.as_words())
+    }
+}
+impl Register for CSD<EMMC> {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(CSD_FIELDS, &self.as_words())
+    }
+}
+impl Register for OCR<EMMC> {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(OCR_FIELDS, &self.as_words())
+    }
+}
+impl Register for CardStatus<EMMC> {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(CARD_STATUS_FIELDS, &self.as_words())
+    }
+}
+impl Register for ExtCSD {
+    /// The main fields of the properties and modes segments. Vendor
+    /// specific and reserved bytes are not listed
+    fn fields(&self) -> Fields<'_> {
+        Fields::ext_csd(EXT_CSD_FIELDS, &self.inner)
+    }
+}
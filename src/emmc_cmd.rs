@@ -1,6 +1,13 @@
 //! eMMC-specific command definitions.
 
-use crate::common_cmd::{cmd, Cmd, R1, R3};
+use crate::common_cmd::{
+    card_status, cmd, deselect_card, Cmd, EraseSequence, Resp, Rz, SwitchError,
+    UnsupportedOperation, R1, R3,
+};
+use crate::emmc::{
+    BootBusConditions, CardStatus, DataPhase, DriverStrength, ExceptionEvents, ExtCSD, HsTiming,
+    PartitionConfig, EMMC,
+};
 
 /// CMD1: Ask all cards to send their supported OCR, or become inactive if they cannot be
 /// supported.
@@ -8,12 +15,58 @@ pub fn send_op_cond(ocr: u32) -> Cmd<R3> {
     cmd(1, ocr)
 }
 
+/// CMD1 argument: High voltage (2.7 - 3.6V) host, byte access mode
+pub const OCR_HIGH_VOLTAGE: u32 = 0x00FF_8000;
+/// CMD1 argument: Dual voltage host, also supporting 1.70 - 1.95V
+pub const OCR_DUAL_VOLTAGE: u32 = 0x00FF_8080;
+/// CMD1 argument: Sector access mode. Required for devices larger than
+/// 2GB, combine with one of the voltage presets
+pub const OCR_SECTOR_MODE: u32 = 0x4000_0000;
+
+/// Invalid CMD1 argument
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InvalidOpCond {
+    /// Access mode bits \[30:29\] are neither byte (0b00) nor sector (0b10)
+    /// mode
+    AccessMode,
+    /// Reserved bits \[28:24\] are set. These carry S18R and XPC in the SD
+    /// ACMD41 argument and have no meaning for eMMC
+    ReservedBits,
+    /// No voltage range requested
+    NoVoltage,
+}
+
+/// CMD1 with a validated argument
+///
+/// ```
+/// # use sdio_host::emmc_cmd::{send_op_cond_checked, OCR_DUAL_VOLTAGE, OCR_SECTOR_MODE};
+/// assert!(send_op_cond_checked(OCR_DUAL_VOLTAGE | OCR_SECTOR_MODE).is_ok());
+/// // The SD ACMD41 argument with HCS, XPC and S18R set
+/// assert!(send_op_cond_checked(0x5100_8000).is_err());
+/// ```
+///
+/// Ref JEDEC 84-A43 Section 8.1 OCR register
+pub fn send_op_cond_checked(ocr: u32) -> Result<Cmd<R3>, InvalidOpCond> {
+    match (ocr >> 29) & 0b11 {
+        0b00 | 0b10 => {}
+        _ => return Err(InvalidOpCond::AccessMode),
+    }
+    if ocr & 0x1F00_0000 != 0 {
+        return Err(InvalidOpCond::ReservedBits);
+    }
+    if ocr & 0x00FF_FF80 == 0 {
+        return Err(InvalidOpCond::NoVoltage);
+    }
+    Ok(send_op_cond(ocr))
+}
+
 /// CMD3: Assigns relative address (RCA) to the Device
 pub fn assign_relative_address(address: u16) -> Cmd<R1> {
     cmd(3, (address as u32) << 16)
 }
 
 /// Specifies a method of modifying a field of EXT_CSD. Used for CMD6.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum AccessMode {
     // The 0b00 pattern corresponds to Command Set, which has different semantics.
     SetBits = 0b01,
@@ -22,11 +75,163 @@ pub enum AccessMode {
 }
 
 /// Uses CMD6 to modify a field of the EXT_CSD.
+///
+/// See [`switch`] for a builder that includes the verification step.
 pub fn modify_ext_csd(access_mode: AccessMode, index: u8, value: u8) -> Cmd<R1> {
-    let arg = 0u32 | ((access_mode as u32) << 24) | ((index as u32) << 16) | ((value as u32) << 8);
+    let arg = ((access_mode as u32) << 24) | ((index as u32) << 16) | ((value as u32) << 8);
     cmd(6, arg)
 }
 
+/// A CMD6 modification of the EXT_CSD, and the check the host must do
+/// afterwards
+///
+/// After the device is no longer busy, the host must read the card status
+/// with [`EmmcSwitch::status_cmd`] and pass it to [`EmmcSwitch::verify`].
+#[must_use = "the result of a switch must be verified with CMD13"]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct EmmcSwitch {
+    access_mode: AccessMode,
+    index: u8,
+    value: u8,
+}
+
+impl EmmcSwitch {
+    /// CMD6 to send
+    pub fn cmd(&self) -> Cmd<R1> {
+        modify_ext_csd(self.access_mode, self.index, self.value)
+    }
+    /// CMD13 reading the status to verify
+    pub fn status_cmd(&self, rca: u16) -> Cmd<R1> {
+        card_status(rca, false)
+    }
+    /// Check the status read after the switch
+    pub fn verify(&self, status: &CardStatus<EMMC>) -> Result<(), SwitchError> {
+        if status.switch_error() {
+            Err(SwitchError::Rejected)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Uses CMD6 to modify a field of the EXT_CSD, returning the switch to
+/// verify
+pub fn switch(access_mode: AccessMode, index: u8, value: u8) -> EmmcSwitch {
+    EmmcSwitch {
+        access_mode,
+        index,
+        value,
+    }
+}
+
+/// Values of the POWER_OFF_NOTIFICATION field of EXT_CSD
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PowerOffNotification {
+    /// Host will notify before powering off the device
+    PoweredOn = 0x01,
+    /// Host is going to power off the device soon
+    PowerOffShort = 0x02,
+    /// Host is going to power off the device, and allows a longer timeout
+    PowerOffLong = 0x03,
+    /// Host is going to put the device to sleep with CMD5
+    SleepNotification = 0x04,
+}
+
+/// Uses CMD6 to write the POWER_OFF_NOTIFICATION field of the EXT_CSD
+pub fn power_off_notification(notification: PowerOffNotification) -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 34, notification as u8)
+}
+
+/// Uses CMD6 to write HS_TIMING, selecting the timing interface and the
+/// driver strength of the device
+///
+/// Check [`ExtCSD::supports_driver_strength`] before selecting anything but
+/// [`DriverStrength::Type0`].
+pub fn set_hs_timing(timing: HsTiming, strength: DriverStrength) -> EmmcSwitch {
+    let value = (strength as u8) << 4 | timing as u8;
+    switch(AccessMode::WriteByte, 185, value)
+}
+
+/// Uses CMD6 to write POWER_CLASS. Select the class with
+/// [`ExtCSD::power_class_for`] before switching the timing or bus width
+pub fn set_power_class(class: u8) -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 187, class & 0xF)
+}
+
+/// Uses CMD6 to write BUS_WIDTH, selecting the data phase of transfers
+///
+/// DDR requires HS_TIMING to be set to high speed first. Fails for 1 bit
+/// DDR, which doesn't exist.
+pub fn set_bus_width(phase: DataPhase) -> Result<EmmcSwitch, UnsupportedOperation> {
+    let value = phase.bus_width_value().ok_or(UnsupportedOperation)?;
+    Ok(switch(AccessMode::WriteByte, 183, value))
+}
+
+/// Uses CMD6 to write PARTITION_CONFIG, selecting the boot partition and
+/// the partition accessed by following commands
+///
+/// Fails if [`ExtCSD::boot_config_perm_protected`] or
+/// [`ExtCSD::boot_config_pwr_protected`] is set.
+pub fn set_partition_config(config: PartitionConfig) -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 179, config.0)
+}
+
+/// Uses CMD6 to write BOOT_BUS_CONDITIONS
+pub fn set_boot_bus_conditions(conditions: BootBusConditions) -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 177, conditions.0)
+}
+
+/// Uses CMD6 to write EXCEPTION_EVENTS_CTRL, enabling the given exception
+/// events. URGENT_BKOPS is always enabled and can't be controlled
+pub fn set_exception_events_ctrl(events: ExceptionEvents) -> EmmcSwitch {
+    let value = events.0 & !ExceptionEvents::URGENT_BKOPS;
+    switch(AccessMode::WriteByte, 56, value as u8)
+}
+
+/// Uses CMD6 to set BKOPS_EN, enabling manually started background
+/// operations
+pub fn enable_manual_bkops() -> EmmcSwitch {
+    switch(AccessMode::SetBits, 163, 0x1)
+}
+
+/// Uses CMD6 to write BKOPS_START, starting background operations. The
+/// device signals busy while they are performed
+pub fn start_bkops() -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 164, 1)
+}
+
+/// Uses CMD6 to write CMDQ_MODE_EN, enabling or disabling command queueing
+///
+/// Only for devices reporting [`ExtCSD::cmdq_support`]. The queue must be
+/// empty when command queueing is disabled.
+pub fn set_cmdq_mode(enable: bool) -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 15, u8::from(enable))
+}
+
+/// Uses CMD6 to write CACHE_CTRL, enabling or disabling the volatile cache
+///
+/// Only for devices with a non-zero [`ExtCSD::cache_size_kib`]. Disabling
+/// the cache flushes it.
+pub fn set_cache(enable: bool) -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 33, u8::from(enable))
+}
+
+/// Uses CMD6 to write FLUSH_CACHE, writing the cached data to the
+/// non-volatile storage. The device signals busy until done
+///
+/// Hosts flush the cache before power-down or sleep.
+pub fn flush_cache() -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 32, 0x1)
+}
+
+/// Uses CMD6 to write FLUSH_CACHE with the barrier bit, ordering the
+/// cached data written before it ahead of the data written after it
+///
+/// Requires [`ExtCSD::barrier_enabled`].
+pub fn cache_barrier() -> EmmcSwitch {
+    switch(AccessMode::WriteByte, 32, 0x2)
+}
+
 /// CMD8: Device sends its EXT_CSD register as a block of data.
 pub fn send_ext_csd() -> Cmd<R1> {
     cmd(8, 0)
@@ -42,12 +247,54 @@ pub fn bustest_write() -> Cmd<R1> {
     cmd(19, 0)
 }
 
+/// CMD21: Device sends the tuning block for HS200 sampling point tuning
+pub fn send_tuning_block_hs200() -> Cmd<R1> {
+    cmd(21, 0)
+}
+
 /// CMD23: Defines the number of blocks (read/write) for a block read or write
 /// operation
 pub fn set_block_count(blockcount: u16) -> Cmd<R1> {
     cmd(23, blockcount as u32)
 }
 
+/// Argument of CMD23 with the eMMC specific request flags
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct BlockCountArg {
+    /// Number of blocks
+    pub count: u16,
+    /// Reliable write request
+    pub reliable_write: bool,
+    /// Packed command. The first block of the following transfer is the
+    /// packed command header
+    pub packed: bool,
+    /// Tag request. Marks the data of the following write, eg. file system
+    /// metadata, as hot data
+    pub tag_request: bool,
+    /// Context ID (0 - 15)
+    pub context_id: u8,
+    /// Forced programming, bypassing the volatile cache
+    pub forced_programming: bool,
+}
+
+impl BlockCountArg {
+    /// Argument word of CMD23
+    pub fn arg(&self) -> u32 {
+        u32::from(self.reliable_write) << 31
+            | u32::from(self.packed) << 30
+            | u32::from(self.tag_request) << 29
+            | u32::from(self.context_id & 0xF) << 25
+            | u32::from(self.forced_programming) << 24
+            | u32::from(self.count)
+    }
+}
+
+/// CMD23: Defines the number of blocks and request flags for the following
+/// block read or write operation
+pub fn set_block_count_with(arg: BlockCountArg) -> Cmd<R1> {
+    cmd(23, arg.arg())
+}
+
 /// CMD35: Sets the address of the first erase group within a range to be
 /// selected for erase
 ///
@@ -63,3 +310,182 @@ pub fn erase_group_start(address: u32) -> Cmd<R1> {
 pub fn erase_group_end(address: u32) -> Cmd<R1> {
     cmd(36, address)
 }
+
+/// Erase function selected by the argument of CMD38
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(u32)]
+pub enum EraseFunction {
+    /// Erase the selected erase groups
+    Erase = 0x0000_0000,
+    /// Erase the selected write blocks
+    Trim = 0x0000_0001,
+    /// Discard the selected write blocks. Their contents become undefined
+    Discard = 0x0000_0003,
+    /// Securely erase the selected erase groups
+    SecureErase = 0x8000_0000,
+    /// First step of a secure trim
+    SecureTrimStep1 = 0x8000_0001,
+    /// Second step of a secure trim
+    SecureTrimStep2 = 0x8000_8000,
+}
+
+/// CMD38: Erase with the given erase function
+pub fn erase_function(function: EraseFunction) -> Cmd<R1> {
+    cmd(38, function as u32)
+}
+
+/// Trim the write blocks from `start` to `end`, inclusive
+///
+/// Fails if the device doesn't support trim according to SEC_FEATURE_SUPPORT.
+pub fn trim(ext_csd: &ExtCSD, start: u32, end: u32) -> Result<EraseSequence, UnsupportedOperation> {
+    if !ext_csd.trim_support() {
+        return Err(UnsupportedOperation);
+    }
+    Ok(EraseSequence {
+        start: erase_group_start(start),
+        end: erase_group_end(end),
+        erase: erase_function(EraseFunction::Trim),
+    })
+}
+
+/// Discard the write blocks from `start` to `end`, inclusive
+///
+/// Fails if the device doesn't support discard.
+pub fn discard(
+    ext_csd: &ExtCSD,
+    start: u32,
+    end: u32,
+) -> Result<EraseSequence, UnsupportedOperation> {
+    if !ext_csd.discard_support() {
+        return Err(UnsupportedOperation);
+    }
+    Ok(EraseSequence {
+        start: erase_group_start(start),
+        end: erase_group_end(end),
+        erase: erase_function(EraseFunction::Discard),
+    })
+}
+
+/// R5: Interrupt request response to CMD40
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct R5;
+
+impl Resp for R5 {}
+
+/// CMD40: Sets the system into interrupt mode
+pub fn go_irq_state() -> Cmd<R5> {
+    cmd(40, 0)
+}
+
+/// Response to CMD40
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IrqResponse(pub u32);
+
+impl IrqResponse {
+    /// RCA of the device that raised the interrupt. Zero for the response
+    /// generated by the host
+    pub fn rca(&self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+    /// Interrupt data, device specific
+    pub fn data(&self) -> u16 {
+        self.0 as u16
+    }
+    /// The response was generated by the host to leave interrupt mode
+    pub fn from_host(&self) -> bool {
+        self.rca() == 0
+    }
+}
+
+/// A single step of the interrupt mode handshake
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IrqStep {
+    /// Deselect all devices with CMD7, interrupt mode is entered from the
+    /// stand-by state. Then call [`IrqMode::deselected`]
+    Deselect,
+    /// Send CMD40 and wait for its response. There is no response timeout:
+    /// the wait lasts until a device raises an interrupt or the host
+    /// aborts with [`IrqMode::abort`]. Pass the response to
+    /// [`IrqMode::response`]
+    Wait,
+    /// Drive the CMD40 response with the given argument (RCA 0) on the CMD
+    /// line, then pass the response seen on the bus to
+    /// [`IrqMode::response`]. A device answering at the same time wins the
+    /// bus arbitration
+    Abort(u32),
+    /// A device raised an interrupt. All devices are back in stand-by
+    Interrupt(IrqResponse),
+    /// Interrupt mode was left without an interrupt. All devices are back
+    /// in stand-by
+    Aborted,
+}
+
+/// Sans-IO interrupt mode handshake built on CMD40
+///
+/// ```
+/// # use sdio_host::emmc_cmd::{IrqMode, IrqResponse, IrqStep};
+/// let mut irq = IrqMode::new();
+/// assert_eq!(irq.step(), IrqStep::Deselect);
+/// assert_eq!(irq.deselected(), IrqStep::Wait);
+/// // Device 0x0002 raised an interrupt
+/// assert_eq!(irq.response(0x0002_0001), IrqStep::Interrupt(IrqResponse(0x0002_0001)));
+/// ```
+///
+/// Ref JEDEC 84-A43 Section 6.11 Interrupt mode
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IrqMode {
+    step: IrqStep,
+}
+
+impl Default for IrqMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IrqMode {
+    /// Handshake starting with all devices selected or in stand-by
+    pub fn new() -> Self {
+        Self {
+            step: IrqStep::Deselect,
+        }
+    }
+    /// The current step
+    pub fn step(&self) -> IrqStep {
+        self.step
+    }
+    /// CMD7 to send for [`IrqStep::Deselect`]
+    pub fn deselect_cmd(&self) -> Cmd<Rz> {
+        deselect_card()
+    }
+    /// CMD40 to send for [`IrqStep::Wait`]
+    pub fn irq_cmd(&self) -> Cmd<R5> {
+        go_irq_state()
+    }
+    /// All devices were deselected
+    pub fn deselected(&mut self) -> IrqStep {
+        if self.step == IrqStep::Deselect {
+            self.step = IrqStep::Wait;
+        }
+        self.step
+    }
+    /// Stop waiting for an interrupt
+    pub fn abort(&mut self) -> IrqStep {
+        if self.step == IrqStep::Wait {
+            self.step = IrqStep::Abort(0);
+        }
+        self.step
+    }
+    /// Response received on the CMD line while waiting or aborting
+    pub fn response(&mut self, word: u32) -> IrqStep {
+        let response = IrqResponse(word);
+        match self.step {
+            IrqStep::Wait | IrqStep::Abort(_) if response.from_host() => {
+                self.step = IrqStep::Aborted
+            }
+            IrqStep::Wait | IrqStep::Abort(_) => self.step = IrqStep::Interrupt(response),
+            _ => {}
+        }
+        self.step
+    }
+}
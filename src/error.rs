@@ -0,0 +1,152 @@
+//! Crate-level error type
+//!
+//! The SD bus and SPI layers report failures with their own error types.
+//! [`Error`] covers all of them, so that applications supporting both
+//! transports can share error handling.
+
+use crate::common::StatusError;
+use crate::common_cmd::{
+    BufferError, CommandError, InvalidPassword, OutOfRange, SwitchError, TransientError,
+    UnsupportedOperation,
+};
+use crate::sd::{BusWidthFault, CapabilityError, VoltageError};
+use crate::sd_cmd::PartialError;
+use crate::spi::{DataCrcError, DataErrorToken, SpiInitError};
+use crate::tuning::TuningError;
+
+/// Any error reported by this crate
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// No response or data within the timeout
+    Timeout,
+    /// CRC check of a response or data block failed
+    Crc,
+    /// The card reported an error in its status
+    Status(StatusError),
+    /// The card reported an error in the response to a command
+    Command(CommandError),
+    /// The card sent an SPI data error token
+    DataToken(DataErrorToken),
+    /// SPI initialization failed
+    SpiInit(SpiInitError),
+    /// A switch was not performed
+    Switch(SwitchError),
+    /// Tuning did not find a sampling point
+    Tuning(TuningError),
+    /// The card and host voltage ranges are not compatible
+    Voltage(VoltageError),
+    /// The card doesn't support the command
+    Capability(CapabilityError),
+    /// The card doesn't support the operation
+    Unsupported,
+    /// The blocks are not within the card
+    OutOfRange,
+    /// The data buffer can't be used for the transfer
+    Buffer(BufferError),
+    /// The card password is too long
+    InvalidPassword,
+    /// The data lines don't work at the selected bus width
+    BusWidth(BusWidthFault),
+    /// A partial block access is not possible
+    Partial(PartialError),
+}
+
+impl From<TransientError> for Error {
+    fn from(e: TransientError) -> Self {
+        match e {
+            TransientError::Crc => Error::Crc,
+            TransientError::Timeout => Error::Timeout,
+        }
+    }
+}
+
+impl From<DataCrcError> for Error {
+    fn from(_: DataCrcError) -> Self {
+        Error::Crc
+    }
+}
+
+impl From<StatusError> for Error {
+    fn from(e: StatusError) -> Self {
+        Error::Status(e)
+    }
+}
+
+impl From<CommandError> for Error {
+    fn from(e: CommandError) -> Self {
+        Error::Command(e)
+    }
+}
+
+impl From<DataErrorToken> for Error {
+    fn from(e: DataErrorToken) -> Self {
+        Error::DataToken(e)
+    }
+}
+
+impl From<SpiInitError> for Error {
+    fn from(e: SpiInitError) -> Self {
+        Error::SpiInit(e)
+    }
+}
+
+impl From<SwitchError> for Error {
+    fn from(e: SwitchError) -> Self {
+        Error::Switch(e)
+    }
+}
+
+impl From<TuningError> for Error {
+    fn from(e: TuningError) -> Self {
+        Error::Tuning(e)
+    }
+}
+
+impl From<VoltageError> for Error {
+    fn from(e: VoltageError) -> Self {
+        Error::Voltage(e)
+    }
+}
+
+impl From<CapabilityError> for Error {
+    fn from(e: CapabilityError) -> Self {
+        Error::Capability(e)
+    }
+}
+
+impl From<UnsupportedOperation> for Error {
+    fn from(_: UnsupportedOperation) -> Self {
+        Error::Unsupported
+    }
+}
+
+impl From<OutOfRange> for Error {
+    fn from(_: OutOfRange) -> Self {
+        Error::OutOfRange
+    }
+}
+
+impl From<BufferError> for Error {
+    fn from(e: BufferError) -> Self {
+        Error::Buffer(e)
+    }
+}
+
+impl From<InvalidPassword> for Error {
+    fn from(_: InvalidPassword) -> Self {
+        Error::InvalidPassword
+    }
+}
+
+impl From<BusWidthFault> for Error {
+    fn from(e: BusWidthFault) -> Self {
+        Error::BusWidth(e)
+    }
+}
+
+impl From<PartialError> for Error {
+    fn from(e: PartialError) -> Self {
+        Error::Partial(e)
+    }
+}
@@ -15,14 +15,31 @@
 //! 7.10. March 25, 2020. (C) SD Card Association
 
 #![no_std]
+#![forbid(unsafe_code)]
 
 pub mod common_cmd;
 #[doc(inline)]
 pub use common_cmd::Cmd;
+pub mod error;
+#[doc(inline)]
+pub use error::Error;
 pub mod sd_cmd;
+pub mod spi;
 pub mod emmc_cmd;
+#[cfg(feature = "trace")]
+pub mod trace;
+pub mod crc;
 
 mod common;
 
 pub mod sd;
 pub mod emmc;
+pub mod detect;
+pub mod power;
+pub mod tuning;
+pub mod time;
+pub mod cq;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+#[cfg(feature = "recorder")]
+pub mod recorder;
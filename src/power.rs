@@ -0,0 +1,278 @@
+//! Card power-up and power-down sequencing
+//!
+//! Sans-IO description of the power-up requirements shared by SD cards and
+//! eMMC devices. The host walks through the [`PowerUpStep`]s and performs
+//! each one with its own supply switch, delay and clock control.
+//! [`PowerDown`] describes the reverse.
+//!
+//! ```
+//! # use sdio_host::power::{PowerUp, PowerUpStep};
+//! for step in PowerUp::new(400_000).steps() {
+//!     match step {
+//!         PowerUpStep::PowerOff { ms } => { /* VDD off, wait ms */ }
+//!         PowerUpStep::PowerOn { ms } => { /* VDD on, wait ms */ }
+//!         PowerUpStep::Wait { ms } => { /* wait ms */ }
+//!         PowerUpStep::InitClocks { hz, us, .. } => { /* clock on, CMD high, wait us */ }
+//!     }
+//! }
+//! // Ready for CMD0
+//! ```
+//!
+//! Ref PLSS_v7_10 Section 6.4.1
+
+use crate::common::Strictness;
+
+/// Minimum time VDD must be held below 0.5 V before powering up again
+pub const MIN_POWER_OFF_MS: u32 = 1;
+/// Maximum supply ramp up time from 0.5 V to VDD(min)
+pub const MAX_RAMP_UP_MS: u32 = 35;
+/// Minimum delay after the supply is stable before clocking the card
+pub const SUPPLY_STABLE_DELAY_MS: u32 = 1;
+/// Minimum number of clock cycles, with CMD held high, before the first
+/// command
+pub const MIN_INIT_CLOCK_CYCLES: u32 = 74;
+/// Maximum bus clock during card identification
+pub const MAX_IDENTIFICATION_CLOCK_HZ: u32 = 400_000;
+
+/// A single step of the power-up sequence
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PowerUpStep {
+    /// Hold VDD below 0.5 V for at least `ms` milliseconds
+    PowerOff { ms: u32 },
+    /// Enable VDD and wait `ms` milliseconds for it to ramp up
+    PowerOn { ms: u32 },
+    /// Wait `ms` milliseconds with the supply stable
+    Wait { ms: u32 },
+    /// Run the bus clock at `hz` with CMD held high for at least `cycles`
+    /// cycles, which takes `us` microseconds
+    InitClocks { hz: u32, cycles: u32, us: u32 },
+}
+
+/// Power-up sequence parameters
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PowerUp {
+    clock_hz: u32,
+    ramp_up_ms: u32,
+    power_cycle: bool,
+    strictness: Strictness,
+}
+
+impl PowerUp {
+    /// Power-up sequence with the identification clock running at
+    /// `clock_hz`. The clock is limited to 1 Hz ..= 400 kHz
+    pub fn new(clock_hz: u32) -> Self {
+        Self {
+            clock_hz: clock_hz.max(1).min(MAX_IDENTIFICATION_CLOCK_HZ),
+            ramp_up_ms: MAX_RAMP_UP_MS,
+            power_cycle: true,
+            strictness: Strictness::Lenient,
+        }
+    }
+    /// In strict mode the shortened ramp up time and skipped power cycle
+    /// are ignored, and the full sequence with the maximum waits is used
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+    /// Supply ramp up time of the board, if known to be shorter than the
+    /// maximum allowed by the spec
+    pub fn ramp_up_ms(mut self, ms: u32) -> Self {
+        self.ramp_up_ms = ms.min(MAX_RAMP_UP_MS);
+        self
+    }
+    /// Skip the initial power off step. Only valid if the supply is known
+    /// to have been off for at least [`MIN_POWER_OFF_MS`]
+    pub fn without_power_cycle(mut self) -> Self {
+        self.power_cycle = false;
+        self
+    }
+    /// Time needed for the initialization clock cycles, rounded up
+    pub fn init_clocks_us(&self) -> u32 {
+        let us = u64::from(MIN_INIT_CLOCK_CYCLES) * 1_000_000;
+        ((us + u64::from(self.clock_hz) - 1) / u64::from(self.clock_hz)) as u32
+    }
+    /// The steps to perform, in order
+    pub fn steps(&self) -> PowerUpSteps {
+        let mut sequence = *self;
+        if self.strictness == Strictness::Strict {
+            sequence.ramp_up_ms = MAX_RAMP_UP_MS;
+            sequence.power_cycle = true;
+        }
+        PowerUpSteps {
+            sequence,
+            index: if sequence.power_cycle { 0 } else { 1 },
+        }
+    }
+}
+
+/// Iterator over the steps of a [`PowerUp`] sequence
+///
+/// Hosts that can't block while waiting keep the iterator and call
+/// [`advance`](PowerUpSteps::advance) when the current step has finished,
+/// eg. from a timer interrupt.
+///
+/// ```
+/// # use sdio_host::power::{PowerUp, PowerUpStep};
+/// let mut sequence = PowerUp::new(400_000).without_power_cycle().steps();
+/// assert_eq!(sequence.step(), Some(PowerUpStep::PowerOn { ms: 35 }));
+/// // The ramp up time has elapsed
+/// assert_eq!(sequence.advance(), Some(PowerUpStep::Wait { ms: 1 }));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PowerUpSteps {
+    sequence: PowerUp,
+    index: u8,
+}
+
+impl PowerUpSteps {
+    /// The step in progress. `None` once the card is ready for CMD0
+    pub fn step(&self) -> Option<PowerUpStep> {
+        self.clone().next()
+    }
+    /// The step in progress has finished. Returns the next step to start
+    pub fn advance(&mut self) -> Option<PowerUpStep> {
+        self.next();
+        self.step()
+    }
+}
+
+impl Iterator for PowerUpSteps {
+    type Item = PowerUpStep;
+
+    fn next(&mut self) -> Option<PowerUpStep> {
+        let step = match self.index {
+            0 => PowerUpStep::PowerOff {
+                ms: MIN_POWER_OFF_MS,
+            },
+            1 => PowerUpStep::PowerOn {
+                ms: self.sequence.ramp_up_ms,
+            },
+            2 => PowerUpStep::Wait {
+                ms: SUPPLY_STABLE_DELAY_MS,
+            },
+            3 => PowerUpStep::InitClocks {
+                hz: self.sequence.clock_hz,
+                cycles: MIN_INIT_CLOCK_CYCLES,
+                us: self.sequence.init_clocks_us(),
+            },
+            _ => return None,
+        };
+        self.index += 1;
+        Some(step)
+    }
+}
+
+/// State a card is left in by a [`PowerDown`] sequence
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PowerDownTarget {
+    /// Stand-by state. The card can be selected again with CMD7
+    Standby,
+    /// Inactive state. The card ignores all commands until it is power
+    /// cycled
+    Inactive,
+    /// Supply removed
+    Off,
+}
+
+impl PowerDownTarget {
+    /// Whether register contents read before the power down (CID, CSD,
+    /// SCR, EXT_CSD, ...) still describe the card afterwards. Otherwise the
+    /// card must be initialized again.
+    pub fn registers_valid(&self) -> bool {
+        match self {
+            PowerDownTarget::Standby => true,
+            PowerDownTarget::Inactive | PowerDownTarget::Off => false,
+        }
+    }
+}
+
+/// A single step of the power-down sequence
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PowerDownStep {
+    /// eMMC only: notify the device with
+    /// [`emmc_cmd::power_off_notification`](crate::emmc_cmd::power_off_notification)
+    /// and wait until it is no longer busy
+    PowerOffNotification,
+    /// Deselect the card with
+    /// [`common_cmd::deselect_card`](crate::common_cmd::deselect_card)
+    Deselect,
+    /// Send the card to the inactive state with
+    /// [`common_cmd::go_inactive_state`](crate::common_cmd::go_inactive_state)
+    GoInactive,
+    /// Remove VDD
+    PowerOff,
+}
+
+/// Power-down sequence, the counterpart of [`PowerUp`]
+///
+/// ```
+/// # use sdio_host::power::{PowerDown, PowerDownStep, PowerDownTarget};
+/// let steps = PowerDown::new(PowerDownTarget::Off).with_power_off_notification().steps();
+/// assert_eq!(
+///     steps.collect::<Vec<_>>(),
+///     [PowerDownStep::PowerOffNotification, PowerDownStep::Deselect, PowerDownStep::PowerOff]
+/// );
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PowerDown {
+    target: PowerDownTarget,
+    notify: bool,
+}
+
+impl PowerDown {
+    /// Power-down sequence leaving the card in `target`
+    pub fn new(target: PowerDownTarget) -> Self {
+        Self {
+            target,
+            notify: false,
+        }
+    }
+    /// Notify an eMMC device before it is deselected. Only applies when
+    /// powering off
+    pub fn with_power_off_notification(mut self) -> Self {
+        self.notify = true;
+        self
+    }
+    /// State the card is left in
+    pub fn target(&self) -> PowerDownTarget {
+        self.target
+    }
+    /// The steps to perform, in order
+    pub fn steps(&self) -> PowerDownSteps {
+        PowerDownSteps {
+            sequence: *self,
+            index: 0,
+        }
+    }
+}
+
+/// Iterator over the steps of a [`PowerDown`] sequence
+#[derive(Debug, Clone)]
+pub struct PowerDownSteps {
+    sequence: PowerDown,
+    index: u8,
+}
+
+impl Iterator for PowerDownSteps {
+    type Item = PowerDownStep;
+
+    fn next(&mut self) -> Option<PowerDownStep> {
+        let PowerDown { target, notify } = self.sequence;
+        loop {
+            let step = match self.index {
+                0 if notify && target == PowerDownTarget::Off => {
+                    Some(PowerDownStep::PowerOffNotification)
+                }
+                1 => Some(PowerDownStep::Deselect),
+                2 if target == PowerDownTarget::Inactive => Some(PowerDownStep::GoInactive),
+                3 if target == PowerDownTarget::Off => Some(PowerDownStep::PowerOff),
+                0..=3 => None,
+                _ => return None,
+            };
+            self.index += 1;
+            if step.is_some() {
+                return step;
+            }
+        }
+    }
+}
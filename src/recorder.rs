@@ -0,0 +1,97 @@
+//! Command sequence recording for conformance tests
+//!
+//! Only available with the `recorder` feature. A [`Recorder`] stores the
+//! commands a state machine emits in a caller provided buffer, so the
+//! sequence can be compared to the one described by the specification.
+//!
+//! ```
+//! # use sdio_host::common_cmd::{ErrorRecovery, RecoveryStep};
+//! # use sdio_host::recorder::{Recorded, Recorder};
+//! # use sdio_host::sd::{CardStatus, SD};
+//! let mut buf = [Recorded::default(); 8];
+//! let mut recorder = Recorder::new(&mut buf);
+//!
+//! let mut recovery = ErrorRecovery::new(0x1234, 10);
+//! let transfer = CardStatus::<SD>::from(4 << 9);
+//! let mut step = recovery.step();
+//! while let RecoveryStep::Send(cmd) = step {
+//!     recorder.record(&cmd);
+//!     step = recovery.update(Some(&transfer));
+//! }
+//! assert_eq!(recorder.commands(), &[Recorded::new(12, 0), Recorded::new(13, 0x1234_0000)]);
+//! ```
+
+use crate::common_cmd::{Cmd, Resp};
+use crate::trace::Tracer;
+
+/// A recorded command
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct Recorded {
+    /// Command index
+    pub cmd: u8,
+    /// Command argument
+    pub arg: u32,
+}
+
+impl Recorded {
+    /// Recorded command `cmd` with argument `arg`
+    pub fn new(cmd: u8, arg: u32) -> Self {
+        Self { cmd, arg }
+    }
+}
+
+/// Records commands into a fixed size buffer
+///
+/// Commands that don't fit are dropped and flagged, see
+/// [`Recorder::overflowed`].
+#[derive(Debug)]
+pub struct Recorder<'a> {
+    buf: &'a mut [Recorded],
+    len: usize,
+    overflowed: bool,
+}
+
+impl<'a> Recorder<'a> {
+    /// Recorder storing commands in `buf`
+    pub fn new(buf: &'a mut [Recorded]) -> Self {
+        Self {
+            buf,
+            len: 0,
+            overflowed: false,
+        }
+    }
+    /// Record a command
+    pub fn record<R: Resp>(&mut self, cmd: &Cmd<R>) {
+        self.record_raw(cmd.cmd, cmd.arg);
+    }
+    /// Record a command by index and argument
+    pub fn record_raw(&mut self, cmd: u8, arg: u32) {
+        match self.buf.get_mut(self.len) {
+            Some(slot) => {
+                *slot = Recorded::new(cmd, arg);
+                self.len += 1;
+            }
+            None => self.overflowed = true,
+        }
+    }
+    /// The recorded commands, in order
+    pub fn commands(&self) -> &[Recorded] {
+        &self.buf[..self.len]
+    }
+    /// Commands were dropped because the buffer was full
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+    /// Forget all recorded commands
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.overflowed = false;
+    }
+}
+
+/// Records the commands, responses are not stored
+impl Tracer for Recorder<'_> {
+    fn on_cmd<R: Resp>(&mut self, cmd: &Cmd<R>) {
+        self.record(cmd);
+    }
+}
@@ -2,12 +2,29 @@
 
 pub use crate::common::*;
 
+use crate::common_cmd::{set_block_size, BlockAddress, Cmd, DataCmdBuilder, OutOfRange, Resp, R1};
+
 /// Type marker for SD-specific extensions.
-#[derive(Clone, Copy, Default)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub struct SD;
 
 use core::{fmt, str};
 
+/// CSD structure version
+///
+/// Ref PLSS_v7_10 Section 5.3.1
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CsdStructureVersion {
+    /// Version 1.0: Standard Capacity (SDSC)
+    V1,
+    /// Version 2.0: High Capacity and Extended Capacity (SDHC / SDXC)
+    V2,
+    /// Version 3.0: Ultra Capacity (SDUC)
+    V3,
+    /// Reserved
+    Unknown,
+}
+
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum SDSpecVersion {
@@ -41,6 +58,14 @@ impl From<[u32; 2]> for SCR {
     }
 }
 impl SCR {
+    /// Raw register value
+    pub fn raw(&self) -> u64 {
+        self.0
+    }
+    /// Little endian words, as accepted by `From<[u32; 2]>`
+    pub fn as_words(&self) -> [u32; 2] {
+        [self.0 as u32, (self.0 >> 32) as u32]
+    }
     /// Physical Layer Specification Version Number
     pub fn version(&self) -> SDSpecVersion {
         let spec = (self.0 >> 56) & 0xF;
@@ -74,13 +99,74 @@ impl SCR {
     pub fn bus_width_four(&self) -> bool {
         (self.0 >> 50) & 1 != 0
     }
+    /// Optional commands supported. Ref PLSS_v7_10 Table 5-23
+    pub fn cmd_support(&self) -> u8 {
+        ((self.0 >> 32) as u8) & 0xF
+    }
+    /// Supports CMD20 speed class control
+    pub fn cmd20_support(&self) -> bool {
+        (self.0 >> 32) & 1 != 0
+    }
+    /// Supports CMD23 set block count
+    pub fn cmd23_support(&self) -> bool {
+        (self.0 >> 33) & 1 != 0
+    }
+    /// Supports CMD48/CMD49 extension register single block commands
+    pub fn cmd48_49_support(&self) -> bool {
+        (self.0 >> 34) & 1 != 0
+    }
+    /// Supports CMD58/CMD59 extension register multi-block commands
+    pub fn cmd58_59_support(&self) -> bool {
+        (self.0 >> 35) & 1 != 0
+    }
 }
+/// Problem found by [verify_bus_width]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BusWidthFault {
+    /// The SD Status does not report the 4-bit bus width. ACMD6 wasn't
+    /// accepted, or the host controller is still in 1-bit mode
+    NotApplied,
+    /// The SCR read in 4-bit mode differs from the one read in 1-bit mode.
+    /// Bit n is set if DATn carried wrong data, typically a floating,
+    /// shorted or unconnected line
+    DataLines(u8),
+}
+
+/// Check that the data lines work after switching to the 4-bit bus width
+///
+/// SD has no bus test command. Instead the SCR (ACMD51) is read in 1-bit
+/// mode before ACMD6 and again in 4-bit mode afterwards, together with the
+/// SD Status (ACMD13).
+///
+/// In 4-bit mode bit n of each nibble is transferred on DATn, so
+/// differences between the two SCRs point at the faulty lines. Data
+/// blocks that fail their CRC check should be treated as
+/// `DataLines(0xF)`.
+pub fn verify_bus_width(
+    scr_1bit: &SCR,
+    scr_4bit: &SCR,
+    status: &SDStatus,
+) -> Result<(), BusWidthFault> {
+    let diff = scr_1bit.0 ^ scr_4bit.0;
+    if diff != 0 {
+        let lines = (0..16).fold(0, |lines, nibble| {
+            lines | (diff >> (4 * nibble)) as u8 & 0xF
+        });
+        return Err(BusWidthFault::DataLines(lines));
+    }
+    if status.bus_width() != BusWidth::Four {
+        return Err(BusWidthFault::NotApplied);
+    }
+    Ok(())
+}
+
 impl core::fmt::Debug for SCR {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("SCR: SD CARD Configuration Register")
             .field("Version", &self.version())
             .field("1-bit width", &self.bus_width_one())
             .field("4-bit width", &self.bus_width_four())
+            .field("CMD Support", &self.cmd_support())
             .finish()
     }
 }
@@ -111,6 +197,13 @@ impl OCR<SD> {
             Some((min, max))
         }
     }
+    /// Voltage range (mV) supported by both the host and the card
+    ///
+    /// * `host_mv` - (min, max) supply voltage the host can provide
+    pub fn usable_voltage_mv(&self, host_mv: (u16, u16)) -> Result<(u16, u16), VoltageError> {
+        let card_mv = self.voltage_window_mv().ok_or(VoltageError::NoCardWindow)?;
+        intersect_voltage_window(host_mv, card_mv)
+    }
     /// Switching to 1.8V Accepted (S18A). Only UHS-I cards support this bit
     // 00000000 00000000 00000000 00000000
     //        1
@@ -139,6 +232,26 @@ impl OCR<SD> {
         self.0 & 0x4000_0000 != 0
     }
 }
+/// Failure to find a supply voltage usable by both host and card
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VoltageError {
+    /// The OCR does not contain a voltage window
+    NoCardWindow,
+    /// The host and card voltage ranges do not overlap
+    NoOverlap,
+}
+
+/// Intersection of two (min, max) voltage ranges in mV
+pub fn intersect_voltage_window(a: (u16, u16), b: (u16, u16)) -> Result<(u16, u16), VoltageError> {
+    let min = a.0.max(b.0);
+    let max = a.1.min(b.1);
+    if min <= max {
+        Ok((min, max))
+    } else {
+        Err(VoltageError::NoOverlap)
+    }
+}
+
 impl fmt::Debug for OCR<SD> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("OCR: Operation Conditions Register")
@@ -165,11 +278,11 @@ impl fmt::Debug for OCR<SD> {
 impl CID<SD> {
     /// OEM/Application ID
     pub fn oem_id(&self) -> &str {
-        str::from_utf8(&self.bytes[1..3]).unwrap_or(&"<ERR>")
+        str::from_utf8(&self.bytes[1..3]).unwrap_or("<ERR>")
     }
     /// Product name
     pub fn product_name(&self) -> &str {
-        str::from_utf8(&self.bytes[3..8]).unwrap_or(&"<ERR>")
+        str::from_utf8(&self.bytes[3..8]).unwrap_or("<ERR>")
     }
     /// Product revision
     pub fn product_revision(&self) -> u8 {
@@ -186,6 +299,30 @@ impl CID<SD> {
             ((self.inner >> 12) as u16 & 0xFF) + 2000, // Year
         )
     }
+    /// Identification fields, for matching against a [`CidFilter`]
+    pub fn identity(&self) -> CardIdentity<'_> {
+        CardIdentity {
+            manufacturer_id: self.manufacturer_id(),
+            oem_id: u16::from_be_bytes([self.bytes[1], self.bytes[2]]),
+            product_name: self.product_name(),
+            revision: self.product_revision(),
+        }
+    }
+}
+
+impl CID<SD> {
+    /// Sanity check of the register contents
+    ///
+    /// Checks the CRC and that reserved bits are zero, to detect garbled R2
+    /// responses. Requires a host controller that stores the CRC.
+    pub fn is_plausible(&self) -> bool {
+        self.crc_valid() && (self.inner >> 20) & 0xF == 0
+    }
+}
+impl Plausible for CID<SD> {
+    fn is_plausible(&self) -> bool {
+        CID::<SD>::is_plausible(self)
+    }
 }
 
 impl fmt::Debug for CID<SD> {
@@ -202,25 +339,34 @@ impl fmt::Debug for CID<SD> {
 }
 
 impl CSD<SD> {
+    /// CSD structure version
+    pub fn version(&self) -> CsdStructureVersion {
+        match self.csd_structure() {
+            0 => CsdStructureVersion::V1,
+            1 => CsdStructureVersion::V2,
+            2 => CsdStructureVersion::V3,
+            _ => CsdStructureVersion::Unknown,
+        }
+    }
     /// Number of blocks in the card
     pub fn block_count(&self) -> u64 {
         match self.version() {
-            0 => {
+            CsdStructureVersion::V1 => {
                 // SDSC
                 let c_size: u16 = ((self.0 >> 62) as u16) & 0xFFF;
                 let c_size_mult: u8 = ((self.0 >> 47) as u8) & 7;
 
                 ((c_size + 1) as u64) * ((1 << (c_size_mult + 2)) as u64)
             }
-            1 => {
+            CsdStructureVersion::V2 => {
                 // SDHC/SDXC
                 (((self.0 >> 48) as u64 & 0x3F_FFFF) + 1) * 1024
             }
-            2 => {
+            CsdStructureVersion::V3 => {
                 // SDUC
                 (((self.0 >> 48) as u64 & 0xFFF_FFFF) + 1) * 1024
             }
-            _ => 0,
+            CsdStructureVersion::Unknown => 0,
         }
     }
     /// Card size in bytes
@@ -229,6 +375,35 @@ impl CSD<SD> {
 
         self.block_count() * block_size_bytes
     }
+    /// Addressing mode of the card
+    pub fn capacity(&self) -> CardCapacity {
+        match self.version() {
+            CsdStructureVersion::V1 => CardCapacity::StandardCapacity,
+            _ => CardCapacity::HighCapacity,
+        }
+    }
+    /// CMD16 setting the block length to 512 bytes, if needed
+    ///
+    /// SDSC cards default to READ_BL_LEN, which may be 1024 or 2048 bytes.
+    /// SDHC and later cards have a fixed 512 byte block length and don't
+    /// need CMD16.
+    pub fn block_length_cmd(&self) -> Option<Cmd<R1>> {
+        match self.capacity() {
+            CardCapacity::StandardCapacity if self.block_length() != BlockSize::B512 => {
+                set_block_size(BlockSize::B512).ok()
+            }
+            _ => None,
+        }
+    }
+    /// Builder for block read and write commands addressing this card
+    pub fn data_cmd_builder(&self) -> DataCmdBuilder {
+        DataCmdBuilder::new(self.capacity(), self.card_size() / 512)
+    }
+    /// Check that `count` 512 byte blocks starting at `address` are within
+    /// the card, and addressable by the card's addressing mode
+    pub fn check_range(&self, address: BlockAddress, count: u32) -> Result<(), OutOfRange> {
+        self.data_cmd_builder().address(address, count).map(|_| ())
+    }
     /// Erase size (in blocks)
     pub fn erase_size_blocks(&self) -> u32 {
         if (self.0 >> 46) & 1 == 1 {
@@ -243,9 +418,37 @@ impl CSD<SD> {
     }
 }
 
+impl CSD<SD> {
+    /// Sanity check of the register contents
+    ///
+    /// Checks the CRC, reserved bits, block length and that the mandatory
+    /// basic command class is supported, to detect garbled R2 responses.
+    /// Requires a host controller that stores the CRC.
+    pub fn is_plausible(&self) -> bool {
+        let block_length_ok = match self.version() {
+            CsdStructureVersion::V1 => (9..=11).contains(&(self.block_length() as u8)),
+            CsdStructureVersion::V2 | CsdStructureVersion::V3 => {
+                self.block_length() == BlockSize::B512
+            }
+            CsdStructureVersion::Unknown => false,
+        };
+
+        self.crc_valid()
+            && (self.0 >> 120) & 0x3F == 0
+            && block_length_ok
+            && self.card_command_classes() & 1 != 0
+    }
+}
+impl Plausible for CSD<SD> {
+    fn is_plausible(&self) -> bool {
+        CSD::<SD>::is_plausible(self)
+    }
+}
+
 impl fmt::Debug for CSD<SD> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CSD: Card Specific Data")
+            .field("Version", &self.version())
             .field("Transfer Rate", &self.transfer_rate())
             .field("Block Count", &self.block_count())
             .field("Card Size (bytes)", &self.card_size())
@@ -273,6 +476,16 @@ impl CardStatus<SD> {
     }
 }
 
+impl CardStatusExt for SD {
+    fn specific_error(status: u32) -> Option<StatusError> {
+        if CardStatus::<SD>::from(status).ake_seq_error() {
+            Some(StatusError::AkeSeqError)
+        } else {
+            None
+        }
+    }
+}
+
 impl fmt::Debug for CardStatus<SD> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Card Status")
@@ -321,6 +534,14 @@ impl From<[u32; 16]> for SDStatus {
     }
 }
 impl SDStatus {
+    /// Raw register words
+    pub fn raw(&self) -> &[u32; 16] {
+        &self.inner
+    }
+    /// Little endian words, as accepted by `From<[u32; 16]>`
+    pub fn as_words(&self) -> [u32; 16] {
+        self.inner
+    }
     /// Current data bus width
     pub fn bus_width(&self) -> BusWidth {
         match (self.inner[15] >> 30) & 3 {
@@ -337,10 +558,27 @@ impl SDStatus {
     pub fn sd_memory_card_type(&self) -> u16 {
         self.inner[15] as u16
     }
+    /// Secured mode functions supported by the card. Bits defined by the
+    /// SD Card Security Specification
+    pub fn security_functions(&self) -> u8 {
+        (self.inner[15] >> 22) as u8 & 0x7F
+    }
     /// SDHC / SDXC: Capacity of Protected Area in bytes
     pub fn protected_area_size(&self) -> u32 {
         self.inner[14]
     }
+    /// Capacity of Protected Area in bytes. SDSC cards report the size in
+    /// units of MULT * BLOCK_LEN from the CSD
+    pub fn protected_area_bytes(&self, csd: &CSD<SD>) -> u64 {
+        let size = u64::from(self.protected_area_size());
+        if csd.version() == CsdStructureVersion::V1 {
+            let c_size_mult = (csd.0 >> 47) as u32 & 7;
+            let read_bl_len = (csd.0 >> 80) as u32 & 0xF;
+            size << (c_size_mult + 2 + read_bl_len)
+        } else {
+            size
+        }
+    }
     /// Speed Class
     pub fn speed_class(&self) -> u8 {
         (self.inner[13] >> 24) as u8
@@ -361,38 +599,404 @@ impl SDStatus {
     pub fn erase_timeout(&self) -> u8 {
         (self.inner[12] >> 18) as u8 & 0x3F
     }
+    /// UHS Speed Grade. 0: less than 10MB/s, 1: 10MB/s and above, 3: 30MB/s
+    /// and above
+    pub fn uhs_speed_grade(&self) -> u8 {
+        (self.inner[12] >> 12) as u8 & 0xF
+    }
+    /// UHS mode Allocation Unit (AU) size. Lookup in PLSS v7_10 Table 4-48
+    pub fn uhs_allocation_unit_size(&self) -> u8 {
+        (self.inner[12] >> 8) as u8 & 0xF
+    }
     /// Video speed class
     pub fn video_speed_class(&self) -> u8 {
-        (self.inner[11] & 0xFF) as u8
+        (self.inner[12] & 0xFF) as u8
+    }
+    /// Video Speed Class AU size in MB
+    pub fn vsc_au_size(&self) -> u16 {
+        (self.inner[11] >> 16) as u16 & 0x3FF
+    }
+    /// Suspension address. Address of the AU that was suspended by CMD20
+    /// Suspend AU, in units of 512 kB
+    pub fn sus_addr(&self) -> u32 {
+        ((self.inner[11] & 0xFFFF) << 6) | (self.inner[10] >> 26)
     }
     /// Application Performance Class
     pub fn app_perf_class(&self) -> u8 {
-        (self.inner[9] >> 16) as u8 & 0xF
+        (self.inner[10] >> 16) as u8 & 0xF
+    }
+    /// PERFORMANCE_ENHANCE: Raw performance enhancement support field
+    pub fn performance_enhance(&self) -> u8 {
+        (self.inner[10] >> 8) as u8
+    }
+    /// The card supports card initiated self-maintenance
+    pub fn card_maintenance_support(&self) -> bool {
+        self.performance_enhance() & 0x1 != 0
+    }
+    /// The card supports host initiated self-maintenance
+    pub fn host_maintenance_support(&self) -> bool {
+        self.performance_enhance() & 0x2 != 0
+    }
+    /// The card supports a cache, controlled with
+    /// [`PerformanceEnhancement`](crate::sd_cmd::PerformanceEnhancement)
+    pub fn cache_support(&self) -> bool {
+        self.performance_enhance() & 0x4 != 0
+    }
+    /// Command queue depth, zero if command queueing is not supported
+    pub fn command_queue_depth(&self) -> u8 {
+        match self.performance_enhance() >> 3 {
+            0 => 0,
+            depth => depth + 1,
+        }
     }
     /// Discard Support
     pub fn discard_support(&self) -> bool {
-        self.inner[8] & 0x0200_0000 != 0
+        self.inner[9] & 0x0200_0000 != 0
+    }
+    /// Full User Area Logical Erase (FULE) Support
+    pub fn fule_support(&self) -> bool {
+        self.inner[9] & 0x0100_0000 != 0
+    }
+}
+
+impl Geometry {
+    /// SD card geometry. The allocation unit is read from the SD Status
+    /// (ACMD13), if available
+    pub fn sd(csd: &CSD<SD>, status: Option<&SDStatus>) -> Self {
+        Geometry {
+            block_size: 512,
+            block_count: csd.card_size() / 512,
+            erase_sector_blocks: csd.erase_size_blocks(),
+            au_size: status
+                .map(|s| au_size_bytes(s.allocation_unit_size()) / 512)
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl Geometry {
+    /// How to issue a write of `count` blocks starting at block `start`
+    ///
+    /// Ref PLSS_v7_10 Section 4.13.1 Speed Class Specification for SD
+    /// Memory Cards
+    pub fn write_advice(&self, start: u64, count: u32) -> WriteAdvice {
+        let au = self.au_size;
+        if count <= 1 {
+            WriteAdvice::Single
+        } else if au == 0 || count < au {
+            WriteAdvice::PreErase { count }
+        } else {
+            let offset = (start % u64::from(au)) as u32;
+            let head = if offset == 0 { 0 } else { au - offset };
+            let aus = (count - head) / au;
+            let tail = count - head - aus * au;
+            if aus == 0 {
+                WriteAdvice::PreErase { count }
+            } else {
+                WriteAdvice::AuAligned { head, aus, tail }
+            }
+        }
+    }
+}
+
+/// Write strategy returned by [Geometry::write_advice]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WriteAdvice {
+    /// Single block write (CMD24). Pre-erase has no benefit
+    Single,
+    /// Multiple block write (CMD25) preceded by ACMD23
+    /// ([set_wr_blk_erase_count](crate::sd_cmd::set_wr_blk_erase_count))
+    /// with `count`, so the card can erase the blocks up front
+    PreErase { count: u32 },
+    /// The write covers whole allocation units. Split it into `head`
+    /// blocks up to the next AU boundary, `aus` whole AUs and `tail`
+    /// remaining blocks. Writing whole AUs sequentially gives the
+    /// performance guaranteed by the speed class. Each write is preceded by
+    /// ACMD23 with its block count
+    AuAligned { head: u32, aus: u32, tail: u32 },
+}
+
+/// AU size in bytes for an AU_SIZE or UHS_AU_SIZE code
+///
+/// Ref PLSS_v7_10 Table 4-47
+fn au_size_bytes(code: u8) -> u32 {
+    const KB: u32 = 1024;
+    const MB: u32 = 1024 * 1024;
+    match code {
+        1..=9 => (16 * KB) << (code - 1),
+        0xA => 8 * MB,
+        0xB => 12 * MB,
+        0xC => 16 * MB,
+        0xD => 24 * MB,
+        0xE => 32 * MB,
+        0xF => 64 * MB,
+        _ => 0,
+    }
+}
+
+/// Write performance guaranteed by the card
+///
+/// Derived from the Speed Class, UHS Speed Grade and Video Speed Class the
+/// card reports. Recording applications should write whole AUs
+/// sequentially to achieve the minimum write speed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct WritePacing {
+    /// Minimum sequential write performance in MB/s. Zero if the card makes
+    /// no guarantee
+    pub min_write_mb_per_s: u8,
+    /// Size of the AU the guarantee applies to, in bytes. Zero if not
+    /// defined by the card
+    pub au_size_bytes: u32,
+}
+
+impl SDStatus {
+    /// Minimum write performance of the Speed Class in MB/s
+    pub fn speed_class_mb_per_s(&self) -> u8 {
+        match self.speed_class() {
+            1 => 2,
+            2 => 4,
+            3 => 6,
+            4 => 10,
+            _ => 0,
+        }
+    }
+    /// Minimum write performance of the UHS Speed Grade in MB/s
+    pub fn uhs_speed_grade_mb_per_s(&self) -> u8 {
+        match self.uhs_speed_grade() {
+            1 => 10,
+            3 => 30,
+            _ => 0,
+        }
+    }
+    /// Write performance hints, using the highest performance class the
+    /// card reports
+    pub fn write_pacing(&self) -> WritePacing {
+        let video = self.video_speed_class();
+        let uhs = self.uhs_speed_grade_mb_per_s();
+        let class = self.speed_class_mb_per_s();
+
+        if video > 0 && video >= uhs && video >= class {
+            WritePacing {
+                min_write_mb_per_s: video,
+                au_size_bytes: u32::from(self.vsc_au_size()) * 1024 * 1024,
+            }
+        } else if uhs > 0 && uhs >= class {
+            WritePacing {
+                min_write_mb_per_s: uhs,
+                au_size_bytes: au_size_bytes(self.uhs_allocation_unit_size()),
+            }
+        } else {
+            WritePacing {
+                min_write_mb_per_s: class,
+                au_size_bytes: au_size_bytes(self.allocation_unit_size()),
+            }
+        }
     }
 }
+
 impl fmt::Debug for SDStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SD Status")
             .field("Bus Width", &self.bus_width())
             .field("Secured Mode", &self.secure_mode())
+            .field("Security Functions", &self.security_functions())
             .field("SD Memory Card Type", &self.sd_memory_card_type())
             .field("Protected Area Size (B)", &self.protected_area_size())
             .field("Speed Class", &self.speed_class())
             .field("Video Speed Class", &self.video_speed_class())
+            .field("VSC AU Size (MB)", &self.vsc_au_size())
+            .field("Suspension Address", &self.sus_addr())
             .field("Application Performance Class", &self.app_perf_class())
             .field("Move Performance (MB/s)", &self.move_performance())
             .field("AU Size", &self.allocation_unit_size())
             .field("Erase Size (units of AU)", &self.erase_size())
             .field("Erase Timeout (s)", &self.erase_timeout())
+            .field("UHS Speed Grade", &self.uhs_speed_grade())
+            .field("UHS AU Size", &self.uhs_allocation_unit_size())
             .field("Discard Support", &self.discard_support())
+            .field("FULE Support", &self.fule_support())
+            .finish()
+    }
+}
+
+/// UHS capabilities of a card
+///
+/// Summarizes what the card supports, so that hosts can report UHS-II
+/// capable cards even when they operate them in UHS-I or default mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UhsCapabilities {
+    /// The card accepted switching to 1.8V signaling (UHS-I)
+    pub uhs1: bool,
+    /// The card supports the UHS-II interface
+    pub uhs2: bool,
+    /// UHS Speed Grade
+    pub speed_grade: u8,
+    /// UHS mode AU size
+    pub allocation_unit_size: u8,
+}
+
+impl UhsCapabilities {
+    /// Capabilities from the OCR returned by ACMD41 and the SD Status
+    pub fn new(ocr: &OCR<SD>, status: &SDStatus) -> Self {
+        Self {
+            uhs1: ocr.v18_allowed(),
+            uhs2: ocr.uhs2_card_status(),
+            speed_grade: status.uhs_speed_grade(),
+            allocation_unit_size: status.uhs_allocation_unit_size(),
+        }
+    }
+}
+
+/// Switch function status
+///
+/// The 512-bit data block returned by CMD6, in the same word order as
+/// [`SDStatus`]. Function groups are numbered 1 - 6.
+///
+/// Ref PLSS_v7_10 Section 4.3.10.4 Switch Function Status
+#[derive(Clone, Copy, Default)]
+pub struct SwitchStatus {
+    inner: [u32; 16],
+}
+/// From little endian words
+impl From<[u32; 16]> for SwitchStatus {
+    fn from(inner: [u32; 16]) -> Self {
+        Self { inner }
+    }
+}
+impl SwitchStatus {
+    /// Raw register words
+    pub fn raw(&self) -> &[u32; 16] {
+        &self.inner
+    }
+    /// Little endian words, as accepted by `From<[u32; 16]>`
+    pub fn as_words(&self) -> [u32; 16] {
+        self.inner
+    }
+    fn field(&self, start: usize, mask: u32) -> u32 {
+        (self.inner[start / 32] >> (start % 32)) & mask
+    }
+    /// Maximum current consumption (mA) of the selected functions. Zero
+    /// if the switch failed
+    pub fn max_current(&self) -> u16 {
+        (self.inner[15] >> 16) as u16
+    }
+    /// Functions supported in function group `group`, one bit per function
+    pub fn supported(&self, group: u8) -> u16 {
+        match group {
+            1..=6 => self.field(400 + 16 * (group as usize - 1), 0xFFFF) as u16,
+            _ => 0,
+        }
+    }
+    /// Function selected in function group `group`. 0xF if the requested
+    /// function could not be switched to
+    pub fn function(&self, group: u8) -> u8 {
+        match group {
+            1..=6 => self.field(376 + 4 * (group as usize - 1), 0xF) as u8,
+            _ => 0xF,
+        }
+    }
+    /// Data structure version
+    pub fn version(&self) -> u8 {
+        self.field(368, 0xFF) as u8
+    }
+}
+impl fmt::Debug for SwitchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Switch Function Status")
+            .field("Max Current (mA)", &self.max_current())
+            .field("Access Mode Support", &self.supported(1))
+            .field("Command System Support", &self.supported(2))
+            .field("Driver Strength Support", &self.supported(3))
+            .field("Power Limit Support", &self.supported(4))
+            .field("Access Mode", &self.function(1))
+            .field("Command System", &self.function(2))
+            .field("Driver Strength", &self.function(3))
+            .field("Power Limit", &self.function(4))
+            .field("Version", &self.version())
             .finish()
     }
 }
 
+/// A command the card can't support
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CapabilityError {
+    /// The card does not implement the command class (CCC) of the command
+    CommandClass(u8),
+    /// The SCR does not list the command in CMD_SUPPORT
+    Command(u8),
+    /// The card does not support 4-bit bus width
+    BusWidth,
+}
+
+/// Checks commands against the capabilities of a card before they are
+/// issued
+///
+/// Catches driver logic bugs, such as CMD23 to a card without CMD23 support
+/// or erase commands to a card without the erase command class.
+#[derive(Debug, Copy, Clone)]
+pub struct CapabilityGuard {
+    classes: u16,
+    scr: SCR,
+}
+
+impl CapabilityGuard {
+    /// Guard for a card with the given CSD and SCR
+    pub fn new(csd: &CSD<SD>, scr: &SCR) -> Self {
+        Self {
+            classes: csd.card_command_classes(),
+            scr: *scr,
+        }
+    }
+    fn class(&self, class: u8) -> Result<(), CapabilityError> {
+        if self.classes & (1 << class) != 0 {
+            Ok(())
+        } else {
+            Err(CapabilityError::CommandClass(class))
+        }
+    }
+    fn optional(&self, index: u8, supported: bool) -> Result<(), CapabilityError> {
+        if supported {
+            Ok(())
+        } else {
+            Err(CapabilityError::Command(index))
+        }
+    }
+    /// Check a command. Application specific commands must be checked with
+    /// [`CapabilityGuard::check_app`]
+    ///
+    /// Ref PLSS_v7_10 Table 4-21 Card Command Classes
+    pub fn check<R: Resp>(&self, cmd: &Cmd<R>) -> Result<(), CapabilityError> {
+        match cmd.cmd {
+            17 | 18 => self.class(2),
+            24 | 25 | 27 => self.class(4),
+            32 | 33 | 38 => self.class(5),
+            28..=30 => self.class(6),
+            42 => self.class(7),
+            55 | 56 => self.class(8),
+            6 | 34..=37 | 50 | 57 => self.class(10),
+            20 => self.optional(20, self.scr.cmd20_support()),
+            23 => self.optional(23, self.scr.cmd23_support()),
+            48 | 49 => self
+                .class(11)
+                .and_then(|_| self.optional(cmd.cmd, self.scr.cmd48_49_support())),
+            58 | 59 => self
+                .class(11)
+                .and_then(|_| self.optional(cmd.cmd, self.scr.cmd58_59_support())),
+            _ => Ok(()),
+        }
+    }
+    /// Check an application specific command, sent after CMD55
+    pub fn check_app<R: Resp>(&self, cmd: &Cmd<R>) -> Result<(), CapabilityError> {
+        self.class(8)?;
+        match cmd.cmd {
+            6 if cmd.arg & 0b11 == 0b10 && !self.scr.bus_width_four() => {
+                Err(CapabilityError::BusWidth)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Card interface condition (R7)
 #[derive(Copy, Clone, Default)]
 pub struct CIC(u32);
@@ -402,14 +1006,78 @@ impl From<u32> for CIC {
     }
 }
 impl CIC {
+    /// Raw R7 response
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+    /// Response word, as accepted by `From<u32>`
+    pub fn as_words(&self) -> [u32; 1] {
+        [self.0]
+    }
     /// The voltage range the card accepts
     pub fn voltage_accepted(&self) -> u8 {
-        (self.0 >> 8) as u8
+        (self.0 >> 8) as u8 & 0xF
     }
     /// Echo-back check pattern
     pub fn pattern(&self) -> u8 {
         self.0 as u8
     }
+    /// PCIe response. The card supports SD Express and the host indicated
+    /// PCIe availability in CMD8
+    pub fn pcie(&self) -> bool {
+        self.0 & 0x1000 != 0
+    }
+    /// PCIe 1.2V support. The card supports the 1.2V PCIe supply (VDD3)
+    pub fn pcie_1v2(&self) -> bool {
+        self.0 & 0x2000 != 0
+    }
+    /// Decide how to continue initialization after CMD8
+    ///
+    /// * `host_pcie` - The host is able to switch to PCIe mode. A host that
+    ///   only supports SD mode may still set PCIe availability in CMD8 to
+    ///   detect SD Express cards
+    pub fn express_decision(&self, host_pcie: bool) -> ExpressDecision {
+        match (self.pcie(), host_pcie) {
+            (false, _) => ExpressDecision::SdMode,
+            (true, false) => ExpressDecision::SdModeExpressCard,
+            (true, true) => ExpressDecision::SwitchToPcie {
+                v1_2: self.pcie_1v2(),
+            },
+        }
+    }
+}
+
+impl fmt::Debug for CIC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Ref PLSS_v7_10 Table 4-18 Format of CMD8
+        let voltage = match self.voltage_accepted() {
+            0b0000 => "Not Defined",
+            0b0001 => "2.7 - 3.6V",
+            0b0010 => "Low Voltage Range",
+            _ => "Reserved",
+        };
+        f.debug_struct("CIC: Card Interface Condition")
+            .field("Voltage Accepted", &voltage)
+            .field("Check Pattern", &self.pattern())
+            .field("PCIe Response", &self.pcie())
+            .field("PCIe 1.2V Support", &self.pcie_1v2())
+            .finish()
+    }
+}
+
+/// Outcome of SD Express detection
+///
+/// Ref PLSS_v7_10 Section 3.13 SD Express
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExpressDecision {
+    /// Card has no PCIe interface. Continue with ACMD41
+    SdMode,
+    /// Card is an SD Express card, but the host can't use PCIe. Continue
+    /// with ACMD41; the card operates in SD mode
+    SdModeExpressCard,
+    /// Stop SD initialization and hand the card over to the PCIe/NVMe
+    /// stack. `v1_2` indicates that the card accepts a 1.2V VDD3 supply
+    SwitchToPcie { v1_2: bool },
 }
 
 impl RCA<SD> {
@@ -417,4 +1085,319 @@ impl RCA<SD> {
     pub fn status(&self) -> u16 {
         self.0 as u16
     }
+    /// Status bits at their position in the card status
+    pub fn card_status(&self) -> CardStatus<SD> {
+        self.r6_status()
+    }
+}
+
+impl CSD<SD> {
+    /// Compact single line description of the card, eg. `SanDisk SU08G
+    /// 7.4GiB SDHC C4 4-bit @25MHz`
+    ///
+    /// The speed class and bus are only included when provided with
+    /// [Summary::status] and [Summary::bus]
+    pub fn summary<'a>(&'a self, cid: &'a CID<SD>) -> Summary<'a> {
+        Summary {
+            cid,
+            csd: self,
+            status: None,
+            bus: None,
+        }
+    }
+}
+
+impl CSD<SD> {
+    /// Worst case power consumption estimate (mW)
+    ///
+    /// Uses the highest voltage of the OCR window and either the current
+    /// of the functions selected with CMD6 (`switch`) or the default speed
+    /// current. Only version 1.0 CSDs report the maximum read/write
+    /// currents, later versions are assumed to draw the 100 mA allowed in
+    /// default speed mode.
+    ///
+    /// Ref PLSS_v7_10 Section 4.3.10.4 and Table 5-4
+    pub fn max_power_mw(&self, ocr: &OCR<SD>, switch: Option<&SwitchStatus>) -> u32 {
+        let max_mv = ocr.voltage_window_mv().map(|(_, max)| max).unwrap_or(3_600);
+        let current_ma = match switch.map(|s| s.max_current()) {
+            Some(ma) if ma > 0 => u32::from(ma),
+            _ if self.version() == CsdStructureVersion::V1 => core::cmp::max(
+                u32::from(&self.read_current_maximum_vdd()),
+                u32::from(&self.write_current_maximum_vdd()),
+            ),
+            _ => 100,
+        };
+        u32::from(max_mv) * current_ma / 1000
+    }
+}
+
+/// One line card description, see [CSD::summary]
+#[derive(Copy, Clone)]
+pub struct Summary<'a> {
+    cid: &'a CID<SD>,
+    csd: &'a CSD<SD>,
+    status: Option<&'a SDStatus>,
+    bus: Option<(BusWidth, u32)>,
+}
+
+impl<'a> Summary<'a> {
+    /// Include the speed class and UHS speed grade
+    pub fn status(mut self, status: &'a SDStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+    /// Include the bus width and clock frequency
+    pub fn bus(mut self, width: BusWidth, clock_hz: u32) -> Self {
+        self.bus = Some((width, clock_hz));
+        self
+    }
+}
+
+/// Manufacturer names for common manufacturer IDs
+fn manufacturer_name(mid: u8) -> Option<&'static str> {
+    Some(match mid {
+        0x01 => "Panasonic",
+        0x02 => "Toshiba",
+        0x03 => "SanDisk",
+        0x1B => "Samsung",
+        0x1D => "ADATA",
+        0x27 => "Phison",
+        0x28 => "Lexar",
+        0x41 => "Kingston",
+        0x74 => "Transcend",
+        0x76 => "Patriot",
+        0x82 => "Sony",
+        _ => return None,
+    })
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mid = self.cid.manufacturer_id();
+        match manufacturer_name(mid) {
+            Some(name) => write!(f, "{}", name)?,
+            None => write!(f, "MID {:#04x}", mid)?,
+        }
+        write!(f, " {}", self.cid.product_name())?;
+
+        // Capacity with one decimal
+        let size = self.csd.card_size();
+        if size >= 1 << 30 {
+            let tenths = (size * 10) >> 30;
+            write!(f, " {}.{}GiB", tenths / 10, tenths % 10)?;
+        } else {
+            write!(f, " {}MiB", size >> 20)?;
+        }
+
+        let capacity = match self.csd.version() {
+            CsdStructureVersion::V1 => "SDSC",
+            CsdStructureVersion::V2 if size < 32 << 30 => "SDHC",
+            CsdStructureVersion::V2 => "SDXC",
+            CsdStructureVersion::V3 => "SDUC",
+            CsdStructureVersion::Unknown => "SD",
+        };
+        write!(f, " {}", capacity)?;
+
+        if let Some(status) = self.status {
+            match status.speed_class_mb_per_s() {
+                0 => {}
+                class => write!(f, " C{}", class)?,
+            }
+            match status.uhs_speed_grade() {
+                0 => {}
+                grade => write!(f, " U{}", grade)?,
+            }
+        }
+
+        if let Some((width, clock_hz)) = self.bus {
+            match width {
+                BusWidth::Unknown => {}
+                width => write!(f, " {}-bit", width as u8)?,
+            }
+            if clock_hz % 1_000_000 == 0 {
+                write!(f, " @{}MHz", clock_hz / 1_000_000)?;
+            } else {
+                write!(f, " @{}kHz", clock_hz / 1_000)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Ref PLSS_v7_10 Table 5-2
+static CID_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("MID", 127, 120),
+    FieldDesc::ascii("OID", 119, 104),
+    FieldDesc::ascii("PNM", 103, 64),
+    FieldDesc::new("PRV", 63, 56),
+    FieldDesc::new("PSN", 55, 24),
+    FieldDesc::new("MDT", 19, 8),
+    FieldDesc::new("CRC", 7, 1),
+];
+
+// Ref PLSS_v7_10 Table 5-4, 5-16 and 5-18. C_SIZE and the fields next to
+// it depend on the CSD version and are listed separately
+static CSD_HEAD_FIELDS: &[FieldDesc] = &[
+    FieldDesc::names("CSD_STRUCTURE", 127, 126, &["1.0", "2.0", "3.0"]),
+    FieldDesc::new("TAAC", 119, 112),
+    FieldDesc::new("NSAC", 111, 104),
+    FieldDesc::new("TRAN_SPEED", 103, 96),
+    FieldDesc::new("CCC", 95, 84),
+    FieldDesc::new("READ_BL_LEN", 83, 80),
+    FieldDesc::new("READ_BL_PARTIAL", 79, 79),
+    FieldDesc::new("WRITE_BLK_MISALIGN", 78, 78),
+    FieldDesc::new("READ_BLK_MISALIGN", 77, 77),
+    FieldDesc::new("DSR_IMP", 76, 76),
+];
+static CSD_TAIL_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("ERASE_BLK_EN", 46, 46),
+    FieldDesc::new("SECTOR_SIZE", 45, 39),
+    FieldDesc::new("WP_GRP_SIZE", 38, 32),
+    FieldDesc::new("WP_GRP_ENABLE", 31, 31),
+    FieldDesc::new("R2W_FACTOR", 28, 26),
+    FieldDesc::new("WRITE_BL_LEN", 25, 22),
+    FieldDesc::new("WRITE_BL_PARTIAL", 21, 21),
+    FieldDesc::new("FILE_FORMAT_GRP", 15, 15),
+    FieldDesc::new("COPY", 14, 14),
+    FieldDesc::new("PERM_WRITE_PROTECT", 13, 13),
+    FieldDesc::new("TMP_WRITE_PROTECT", 12, 12),
+    FieldDesc::names(
+        "FILE_FORMAT",
+        11,
+        10,
+        &["Hard disk-like", "DOS FAT", "Universal", "Others"],
+    ),
+    FieldDesc::new("WP_UPC", 9, 9),
+    FieldDesc::new("CRC", 7, 1),
+];
+static CSD_V1_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("C_SIZE", 73, 62),
+    FieldDesc::new("VDD_R_CURR_MIN", 61, 59),
+    FieldDesc::new("VDD_R_CURR_MAX", 58, 56),
+    FieldDesc::new("VDD_W_CURR_MIN", 55, 53),
+    FieldDesc::new("VDD_W_CURR_MAX", 52, 50),
+    FieldDesc::new("C_SIZE_MULT", 49, 47),
+];
+static CSD_V2_FIELDS: &[FieldDesc] = &[FieldDesc::new("C_SIZE", 69, 48)];
+static CSD_V3_FIELDS: &[FieldDesc] = &[FieldDesc::new("C_SIZE", 75, 48)];
+
+// Ref PLSS_v7_10 Table 5-1
+static OCR_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("BUSY", 31, 31),
+    FieldDesc::new("CCS", 30, 30),
+    FieldDesc::new("UHS2", 29, 29),
+    FieldDesc::new("CO2T", 27, 27),
+    FieldDesc::new("S18A", 24, 24),
+    FieldDesc::new("VDD_WINDOW", 23, 15),
+];
+
+// Ref PLSS_v7_10 Table 5-17
+static SCR_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("SCR_STRUCTURE", 63, 60),
+    FieldDesc::new("SD_SPEC", 59, 56),
+    FieldDesc::new("DATA_STAT_AFTER_ERASE", 55, 55),
+    FieldDesc::names(
+        "SD_SECURITY",
+        54,
+        52,
+        &["None", "Not used", "SDSC", "SDHC", "SDXC"],
+    ),
+    FieldDesc::new("SD_BUS_WIDTHS", 51, 48),
+    FieldDesc::new("SD_SPEC3", 47, 47),
+    FieldDesc::new("EX_SECURITY", 46, 43),
+    FieldDesc::new("SD_SPEC4", 42, 42),
+    FieldDesc::new("SD_SPECX", 41, 38),
+    FieldDesc::new("CMD_SUPPORT", 35, 32),
+];
+
+// Ref PLSS_v7_10 Table 4-44
+static SD_STATUS_FIELDS: &[FieldDesc] = &[
+    FieldDesc::names("DAT_BUS_WIDTH", 511, 510, &["1 bit", "", "4 bit"]),
+    FieldDesc::new("SECURED_MODE", 509, 509),
+    FieldDesc::new("SD_CARD_TYPE", 495, 480),
+    FieldDesc::new("SIZE_OF_PROTECTED_AREA", 479, 448),
+    FieldDesc::new("SPEED_CLASS", 447, 440),
+    FieldDesc::new("PERFORMANCE_MOVE", 439, 432),
+    FieldDesc::new("AU_SIZE", 431, 428),
+    FieldDesc::new("ERASE_SIZE", 423, 408),
+    FieldDesc::new("ERASE_TIMEOUT", 407, 402),
+    FieldDesc::new("ERASE_OFFSET", 401, 400),
+    FieldDesc::new("UHS_SPEED_GRADE", 399, 396),
+    FieldDesc::new("UHS_AU_SIZE", 395, 392),
+    FieldDesc::new("VIDEO_SPEED_CLASS", 391, 384),
+    FieldDesc::new("VSC_AU_SIZE", 377, 368),
+    FieldDesc::new("SUS_ADDR", 367, 346),
+    FieldDesc::new("APP_PERF_CLASS", 339, 336),
+    FieldDesc::new("PERFORMANCE_ENHANCE", 335, 328),
+    FieldDesc::new("DISCARD_SUPPORT", 313, 313),
+    FieldDesc::new("FULE_SUPPORT", 312, 312),
+];
+
+// Ref PLSS_v7_10 Table 4-42
+static CARD_STATUS_FIELDS: &[FieldDesc] = &[
+    FieldDesc::new("OUT_OF_RANGE", 31, 31),
+    FieldDesc::new("ADDRESS_ERROR", 30, 30),
+    FieldDesc::new("BLOCK_LEN_ERROR", 29, 29),
+    FieldDesc::new("ERASE_SEQ_ERROR", 28, 28),
+    FieldDesc::new("ERASE_PARAM", 27, 27),
+    FieldDesc::new("WP_VIOLATION", 26, 26),
+    FieldDesc::new("CARD_IS_LOCKED", 25, 25),
+    FieldDesc::new("LOCK_UNLOCK_FAILED", 24, 24),
+    FieldDesc::new("COM_CRC_ERROR", 23, 23),
+    FieldDesc::new("ILLEGAL_COMMAND", 22, 22),
+    FieldDesc::new("CARD_ECC_FAILED", 21, 21),
+    FieldDesc::new("CC_ERROR", 20, 20),
+    FieldDesc::new("ERROR", 19, 19),
+    FieldDesc::new("CSD_OVERWRITE", 16, 16),
+    FieldDesc::new("WP_ERASE_SKIP", 15, 15),
+    FieldDesc::new("CARD_ECC_DISABLED", 14, 14),
+    FieldDesc::new("ERASE_RESET", 13, 13),
+    FieldDesc::names("CURRENT_STATE", 12, 9, CURRENT_STATE_NAMES),
+    FieldDesc::new("READY_FOR_DATA", 8, 8),
+    FieldDesc::new("FX_EVENT", 6, 6),
+    FieldDesc::new("APP_CMD", 5, 5),
+    FieldDesc::new("AKE_SEQ_ERROR", 3, 3),
+];
+
+impl Register for CID<SD> {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(CID_FIELDS, &self.as_words())
+    }
+}
+impl Register for OCR<SD> {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(OCR_FIELDS, &self.as_words())
+    }
+}
+impl Register for SCR {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(SCR_FIELDS, &self.as_words())
+    }
+}
+impl Register for SDStatus {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(SD_STATUS_FIELDS, self.raw())
+    }
+}
+impl Register for CardStatus<SD> {
+    fn fields(&self) -> Fields<'_> {
+        Fields::new(CARD_STATUS_FIELDS, &self.as_words())
+    }
+}
+
+impl Register for CSD<SD> {
+    /// The common fields before the capacity, the capacity fields of this
+    /// CSD version and the common fields after the capacity
+    fn fields(&self) -> Fields<'_> {
+        let capacity = match self.version() {
+            CsdStructureVersion::V1 => CSD_V1_FIELDS,
+            CsdStructureVersion::V2 => CSD_V2_FIELDS,
+            CsdStructureVersion::V3 => CSD_V3_FIELDS,
+            CsdStructureVersion::Unknown => &[],
+        };
+        Fields::chain(
+            [CSD_HEAD_FIELDS, capacity, CSD_TAIL_FIELDS],
+            &self.as_words(),
+        )
+    }
 }
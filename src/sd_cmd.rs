@@ -1,10 +1,16 @@
 //! SD-specific command definitions.
 
-use crate::common_cmd::{cmd, Cmd, Resp, R1, R3};
+use crate::common_cmd::{
+    cmd, read_single_block, set_block_length, write_single_block, Cmd, EraseSequence, Resp,
+    SwitchError, UnsupportedOperation, R1, R3,
+};
+use crate::sd::{BlockSize, SDStatus, SwitchStatus, VoltageError, CSD, SD};
 
 /// R6: Published RCA response
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub struct R6;
 /// R7: Card interface condition
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub struct R7;
 
 impl Resp for R6 {}
@@ -20,12 +26,75 @@ pub fn cmd6(arg: u32) -> Cmd<R1> {
     cmd(6, arg)
 }
 
+/// Mode of a CMD6 switch function command
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SwitchMode {
+    /// Query the supported functions without switching
+    Check = 0,
+    /// Switch to the requested functions
+    Switch = 1,
+}
+
+/// A CMD6 switch function command, and the check the host must do
+/// afterwards
+///
+/// The card returns the switch function status on the data lines. The host
+/// must read it and pass it to [`SdSwitch::verify`].
+#[must_use = "the result of a switch must be verified with the switch status"]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SdSwitch {
+    arg: u32,
+}
+
+impl SdSwitch {
+    /// CMD6 to send
+    pub fn cmd(&self) -> Cmd<R1> {
+        cmd6(self.arg)
+    }
+    /// Check the 512-bit status read after the command. In check mode this
+    /// verifies that the functions are supported.
+    pub fn verify(&self, status: &SwitchStatus) -> Result<(), SwitchError> {
+        for group in 1..=6u8 {
+            let function = (self.arg >> (4 * (group - 1))) as u8 & 0xF;
+            if function != 0xF && status.function(group) != function {
+                return Err(SwitchError::FunctionGroup(group));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// CMD6: Switch function command
+///
+/// `functions` holds the function to select for groups 1 - 6. 0xF keeps
+/// the current function of a group.
+pub fn switch_function(mode: SwitchMode, functions: [u8; 6]) -> SdSwitch {
+    let mut arg = (mode as u32) << 31;
+    for (group, function) in functions.iter().enumerate() {
+        arg |= u32::from(function & 0xF) << (4 * group);
+    }
+    SdSwitch { arg }
+}
+
 /// CMD8: Sends memory card interface conditions
 pub fn send_if_cond(voltage: u8, checkpattern: u8) -> Cmd<R7> {
     let arg = u32::from(voltage & 0xF) << 8 | u32::from(checkpattern);
     cmd(8, arg)
 }
 
+/// CMD8: Sends memory card interface conditions, indicating SD Express
+/// support of the host
+///
+/// * `pcie` - PCIe availability. The host supports SD Express
+/// * `pcie_1v2` - The host can supply 1.2V to VDD3
+pub fn send_if_cond_express(voltage: u8, checkpattern: u8, pcie: bool, pcie_1v2: bool) -> Cmd<R7> {
+    let arg = u32::from(pcie_1v2) << 13
+        | u32::from(pcie) << 12
+        | u32::from(voltage & 0xF) << 8
+        | u32::from(checkpattern);
+    cmd(8, arg)
+}
+
 /// CMD11: Switch to 1.8V bus signaling level
 pub fn voltage_switch() -> Cmd<R1> {
     cmd(11, 0)
@@ -41,6 +110,46 @@ pub fn speed_class_control(arg: u32) -> Cmd<R1> {
     cmd(20, arg)
 }
 
+/// Speed Class Control (SCC) function of CMD20
+///
+/// Ref PLSS_v7_10 Section 4.13.2.8
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SpeedClassControl {
+    /// Start recording
+    StartRecording = 0b0000,
+    /// Create a directory entry
+    CreateDir = 0b0001,
+    /// Update the continuous information
+    UpdateCi = 0b0100,
+    /// Suspend writing the current AU. The card reports the suspended AU in
+    /// the SUS_ADDR field of the SD Status
+    SuspendAu = 0b0101,
+    /// Resume writing the AU at SUS_ADDR
+    ResumeAu = 0b0110,
+    /// Set the free AU
+    SetFreeAu = 0b0111,
+    /// Release a directory entry
+    ReleaseDir = 0b1000,
+}
+
+/// CMD20: Speed class control with a typed control function
+///
+/// * `value` - Function specific argument bits \[27:0\]
+pub fn speed_class_control_fn(scc: SpeedClassControl, value: u32) -> Cmd<R1> {
+    speed_class_control((scc as u32) << 28 | (value & 0x0FFF_FFFF))
+}
+
+/// CMD20: Suspend the AU currently being recorded
+pub fn suspend_au() -> Cmd<R1> {
+    speed_class_control_fn(SpeedClassControl::SuspendAu, 0)
+}
+
+/// CMD20: Resume recording at the AU reported by
+/// [`SDStatus::sus_addr`](crate::sd::SDStatus::sus_addr)
+pub fn resume_au() -> Cmd<R1> {
+    speed_class_control_fn(SpeedClassControl::ResumeAu, 0)
+}
+
 /// CMD22: Address extension
 pub fn address_extension(arg: u32) -> Cmd<R1> {
     cmd(22, arg)
@@ -54,13 +163,13 @@ pub fn set_block_count(blockcount: u32) -> Cmd<R1> {
 
 /// CMD32: Sets the address of the first write block to be erased
 pub fn erase_wr_blk_start_addr(address: u32) -> Cmd<R1> {
-    cmd(35, address)
+    cmd(32, address)
 }
 
 /// CMD33: Sets the address of the last write block of the continuous range to
 /// be erased
 pub fn erase_wr_blk_end_addr(address: u32) -> Cmd<R1> {
-    cmd(35, address)
+    cmd(33, address)
 }
 
 /// CMD36: Sets the address of the last erase group within a continuous range to
@@ -71,6 +180,167 @@ pub fn erase_group_end(address: u32) -> Cmd<R1> {
     cmd(36, address)
 }
 
+/// Erase function selected by the argument of CMD38
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EraseFunction {
+    /// Erase the selected blocks
+    Erase = 0,
+    /// Discard the selected blocks. Their contents become undefined
+    Discard = 1,
+    /// Full User Area Logical Erase
+    Fule = 2,
+}
+
+/// CMD38: Erase with the given erase function
+pub fn erase_function(function: EraseFunction) -> Cmd<R1> {
+    cmd(38, function as u32)
+}
+
+/// Discard the blocks from `start` to `end`, inclusive
+///
+/// Fails if the card doesn't report discard support in its SD Status.
+pub fn discard(
+    status: &SDStatus,
+    start: u32,
+    end: u32,
+) -> Result<EraseSequence, UnsupportedOperation> {
+    if !status.discard_support() {
+        return Err(UnsupportedOperation);
+    }
+    Ok(EraseSequence {
+        start: erase_wr_blk_start_addr(start),
+        end: erase_wr_blk_end_addr(end),
+        erase: erase_function(EraseFunction::Discard),
+    })
+}
+
+/// CMD38: Erase the whole user area with a Full User Area Logical Erase
+///
+/// Fails if the card doesn't report FULE support in its SD Status.
+pub fn full_user_area_logical_erase(status: &SDStatus) -> Result<Cmd<R1>, UnsupportedOperation> {
+    if status.fule_support() {
+        Ok(erase_function(EraseFunction::Fule))
+    } else {
+        Err(UnsupportedOperation)
+    }
+}
+
+/// Argument of CMD48 and CMD49
+fn extr_single_arg(fno: u8, address: u32, len: u16) -> u32 {
+    u32::from(fno & 0xF) << 27 | (address & 0x1_FFFF) << 9 | u32::from(len.max(1).min(512) - 1)
+}
+
+/// CMD48: Read `len` bytes (1 ..= 512) of an extension register at the
+/// 17-bit `address` of function `fno`
+pub fn read_extr_single(fno: u8, address: u32, len: u16) -> Cmd<R1> {
+    cmd(48, extr_single_arg(fno, address, len))
+}
+
+/// CMD49: Write `len` bytes (1 ..= 512) of an extension register at the
+/// 17-bit `address` of function `fno`
+pub fn write_extr_single(fno: u8, address: u32, len: u16) -> Cmd<R1> {
+    cmd(49, extr_single_arg(fno, address, len))
+}
+
+/// A single byte write to an extension register with CMD49
+///
+/// Send [`ExtRegWrite::cmd`], then write [`ExtRegWrite::block`] as the
+/// data block.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ExtRegWrite {
+    fno: u8,
+    address: u32,
+    value: u8,
+}
+
+impl ExtRegWrite {
+    /// CMD49 to send
+    pub fn cmd(&self) -> Cmd<R1> {
+        write_extr_single(self.fno, self.address, 1)
+    }
+    /// Data block to write after the command
+    pub fn block(&self) -> [u8; 512] {
+        let mut block = [0; 512];
+        block[0] = self.value;
+        block
+    }
+}
+
+/// Performance Enhancement extension register set
+///
+/// The location of the register set is listed in the General Information
+/// read with CMD48 from page 0 of function 0. The card must set
+/// [`SCR::cmd48_49_support`]. The cache writes also need
+/// [`SDStatus::cache_support`], the self-maintenance writes
+/// [`SDStatus::host_maintenance_support`] or
+/// [`SDStatus::card_maintenance_support`].
+///
+/// [`SCR::cmd48_49_support`]: crate::sd::SCR::cmd48_49_support
+///
+/// Ref PLSS_v7_10 Section 5.7.2.7
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PerformanceEnhancement {
+    fno: u8,
+    address: u32,
+}
+
+impl PerformanceEnhancement {
+    const MAINTENANCE_ENABLE: u32 = 257;
+    const MAINTENANCE_START: u32 = 258;
+    const MAINTENANCE_STOP: u32 = 259;
+    const CACHE_ENABLE: u32 = 260;
+    const CACHE_FLUSH: u32 = 261;
+
+    /// Register set of function `fno` starting at the 17-bit `address`
+    pub fn new(fno: u8, address: u32) -> Self {
+        Self { fno, address }
+    }
+    /// Enable or disable card initiated and host initiated self-maintenance
+    pub fn set_maintenance(&self, card: bool, host: bool) -> ExtRegWrite {
+        self.write(
+            Self::MAINTENANCE_ENABLE,
+            u8::from(host) << 1 | u8::from(card),
+        )
+    }
+    /// Start host initiated self-maintenance. Poll with
+    /// [`maintenance_status_cmd`] until the start bit reads back as zero
+    ///
+    /// [`maintenance_status_cmd`]: PerformanceEnhancement::maintenance_status_cmd
+    pub fn start_maintenance(&self) -> ExtRegWrite {
+        self.write(Self::MAINTENANCE_START, 1)
+    }
+    /// Stop host initiated self-maintenance before it has finished
+    pub fn stop_maintenance(&self) -> ExtRegWrite {
+        self.write(Self::MAINTENANCE_STOP, 1)
+    }
+    /// CMD48 reading the self-maintenance start register
+    pub fn maintenance_status_cmd(&self) -> Cmd<R1> {
+        read_extr_single(self.fno, self.address + Self::MAINTENANCE_START, 1)
+    }
+    /// Enable or disable the cache
+    pub fn set_cache(&self, enable: bool) -> ExtRegWrite {
+        self.write(Self::CACHE_ENABLE, u8::from(enable))
+    }
+    /// Start flushing the cache. Poll with [`flush_status_cmd`] until the
+    /// flush bit reads back as zero
+    ///
+    /// [`flush_status_cmd`]: PerformanceEnhancement::flush_status_cmd
+    pub fn flush_cache(&self) -> ExtRegWrite {
+        self.write(Self::CACHE_FLUSH, 1)
+    }
+    /// CMD48 reading the cache flush register
+    pub fn flush_status_cmd(&self) -> Cmd<R1> {
+        read_extr_single(self.fno, self.address + Self::CACHE_FLUSH, 1)
+    }
+    fn write(&self, offset: u32, value: u8) -> ExtRegWrite {
+        ExtRegWrite {
+            fno: self.fno,
+            address: self.address + offset,
+            value,
+        }
+    }
+}
+
 /// ACMD6: Bus Width
 /// * `bw4bit` - Enable 4 bit bus width
 pub fn set_bus_width(bw4bit: bool) -> Cmd<R1> {
@@ -83,14 +353,97 @@ pub fn sd_status() -> Cmd<R1> {
     cmd(13, 0)
 }
 
+/// ACMD22: Number of well written blocks of the last multiple block write
+///
+/// The card returns the count as a 4 byte big endian data block
+pub fn send_num_wr_blocks() -> Cmd<R1> {
+    cmd(22, 0)
+}
+
+/// ACMD23: Number of blocks to pre-erase before the following multiple
+/// block write
+///
+/// Only the 23 least significant bits of `blockcount` are used
+pub fn set_wr_blk_erase_count(blockcount: u32) -> Cmd<R1> {
+    cmd(23, blockcount & 0x7F_FFFF)
+}
+
+/// Power the host can supply to an SDXC or SDUC card, which decides the
+/// SDXC Power Control (XPC) bit of ACMD41
+///
+/// Ref PLSS_v7_10 Section 4.2.3.1 and Table 4-33
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HostPower {
+    /// Less than 0.54W. The card stays in power saving mode, limited to
+    /// 0.36W (100 mA at 3.6V). Speed Class 10 and UHS speed grades may not
+    /// be met
+    PowerSaving,
+    /// At least 0.54W (150 mA at 3.6V). The card may use maximum
+    /// performance mode
+    MaximumPerformance,
+}
+
+impl HostPower {
+    /// Power mode from the power (mW) the host can supply to the card
+    pub fn from_mw(mw: u32) -> Self {
+        if mw >= 540 {
+            HostPower::MaximumPerformance
+        } else {
+            HostPower::PowerSaving
+        }
+    }
+    /// Value of the XPC bit, the `sdxc_power_control` argument of
+    /// [sd_send_op_cond]. Ignored by SDSC and SDHC cards
+    pub fn xpc(&self) -> bool {
+        *self == HostPower::MaximumPerformance
+    }
+}
+
+/// ACMD41 voltage window: 2.7 - 3.6V, every range defined by the OCR
+pub const VOLTAGE_2V7_TO_3V6: u16 = 0x1FF;
+/// ACMD41 voltage window: 3.2 - 3.4V, a 3.3V supply
+pub const VOLTAGE_3V2_TO_3V4: u16 = 0x060;
+/// ACMD41 voltage window: 3.0 - 3.6V
+pub const VOLTAGE_3V0_TO_3V6: u16 = 0x1F8;
+/// ACMD41 voltage window: 2.7 - 3.6V
+pub const FULL_3V3_RANGE: u16 = VOLTAGE_2V7_TO_3V6;
+
+/// ACMD41 voltage window covering the supply range `min_mv` ..= `max_mv`
+///
+/// Selects every 100 mV OCR range within the supply range. Returns an
+/// error if it doesn't cover any of them.
+///
+/// ```
+/// # use sdio_host::sd_cmd::{voltage_window, VOLTAGE_3V2_TO_3V4};
+/// assert_eq!(voltage_window(3200, 3400), Ok(VOLTAGE_3V2_TO_3V4));
+/// ```
+///
+/// Ref PLSS_v7_10 Table 5-1 OCR Register Definition
+pub fn voltage_window(min_mv: u16, max_mv: u16) -> Result<u16, VoltageError> {
+    let window = (0..9).fold(0, |window, bit| {
+        let low = 2_700 + 100 * bit;
+        if low >= min_mv && low + 100 <= max_mv {
+            window | 1 << bit
+        } else {
+            window
+        }
+    });
+    if window == 0 {
+        Err(VoltageError::NoOverlap)
+    } else {
+        Ok(window)
+    }
+}
+
 /// ACMD41: App Op Command
 ///
 /// * `host_high_capacity_support` - Host supports high capacity cards
-/// * `sdxc_power_control` - Controls the maximum power and default speed mode of SDXC and SDUC cards
+/// * `sdxc_power_control` - Controls the maximum power and default speed mode of SDXC and SDUC cards,
+///   see [HostPower::xpc]
 /// * `switch_to_1_8v_request` - Switch to 1.8V signaling
 /// * `voltage_window` - 9-bit bitfield that represents the voltage window
-/// supported by the host. Use 0x1FF to indicate support for the full range of
-/// voltages
+///   supported by the host. Use [VOLTAGE_2V7_TO_3V6] to indicate support for
+///   the full range of voltages, or [voltage_window] for a supply range
 pub fn sd_send_op_cond(
     host_high_capacity_support: bool,
     sdxc_power_control: bool,
@@ -108,3 +461,119 @@ pub fn sd_send_op_cond(
 pub fn send_scr() -> Cmd<R1> {
     cmd(51, 0)
 }
+
+/// A partial block access is not possible
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PartialError {
+    /// The card doesn't allow partial block access in this direction
+    Unsupported,
+    /// The length is zero or longer than the block length
+    InvalidLength,
+    /// The access crosses a physical block boundary, and the card doesn't
+    /// allow misaligned access
+    CrossesBlock,
+    /// The access is not within the card
+    OutOfRange,
+}
+
+/// Commands of a partial block access
+///
+/// After the transfer, the host restores the block length with
+/// `set_block_length(512)`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PartialCmds {
+    /// CMD16 setting the block length to the access length
+    pub set_block_length: Cmd<R1>,
+    /// CMD17 or CMD24
+    pub transfer: Cmd<R1>,
+}
+
+/// Partial block reads and writes of an SDSC card
+///
+/// Cards with READ_BL_PARTIAL or WRITE_BL_PARTIAL set in their CSD accept
+/// transfers of 1 byte up to the block length, which lets hosts without a
+/// 512 byte buffer access them. The access must stay within one physical
+/// block, unless READ_BLK_MISALIGN or WRITE_BLK_MISALIGN is set. SDHC and
+/// SDXC cards never allow partial block access.
+///
+/// ```
+/// # use sdio_host::sd::{CSD, SD};
+/// # use sdio_host::sd_cmd::{PartialAccess, PartialError};
+/// // 1GB SDSC card with 512 byte blocks, READ_BL_PARTIAL set
+/// let csd = CSD::<SD>::from([0, 0x0003_8000, 0x0009_83C0, 0]);
+/// let access = PartialAccess::new(&csd);
+/// let read = access.read(0x210, 16).unwrap();
+/// assert_eq!((read.set_block_length.arg, read.transfer.arg), (16, 0x210));
+/// assert_eq!(access.read(0x1F8, 16).err(), Some(PartialError::CrossesBlock));
+/// assert_eq!(access.write(0x210, 16).err(), Some(PartialError::Unsupported));
+/// ```
+///
+/// Ref PLSS_v7_10 Section 4.3.3 and Section 5.3.2
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PartialAccess {
+    read: bool,
+    write: bool,
+    read_misalign: bool,
+    write_misalign: bool,
+    block_len: u32,
+    card_size: u64,
+}
+
+impl PartialAccess {
+    /// Partial access as allowed by the CSD of the card
+    pub fn new(csd: &CSD<SD>) -> Self {
+        let block_len = match csd.block_length() {
+            BlockSize::Unknown => 0,
+            size => 1 << size as u32,
+        };
+        Self {
+            read: csd.read_block_partial(),
+            write: csd.write_block_partial(),
+            read_misalign: csd.read_block_misalign(),
+            write_misalign: csd.write_block_misalign(),
+            block_len,
+            card_size: csd.card_size(),
+        }
+    }
+    /// Physical block length in bytes
+    pub fn block_len(&self) -> u32 {
+        self.block_len
+    }
+    /// Read `len` bytes at byte address `addr`
+    pub fn read(&self, addr: u32, len: u32) -> Result<PartialCmds, PartialError> {
+        self.check(self.read, self.read_misalign, addr, len)?;
+        Ok(PartialCmds {
+            set_block_length: set_block_length(len),
+            transfer: read_single_block(addr),
+        })
+    }
+    /// Write `len` bytes at byte address `addr`
+    pub fn write(&self, addr: u32, len: u32) -> Result<PartialCmds, PartialError> {
+        self.check(self.write, self.write_misalign, addr, len)?;
+        Ok(PartialCmds {
+            set_block_length: set_block_length(len),
+            transfer: write_single_block(addr),
+        })
+    }
+    fn check(
+        &self,
+        allowed: bool,
+        misalign: bool,
+        addr: u32,
+        len: u32,
+    ) -> Result<(), PartialError> {
+        if !allowed || self.block_len == 0 {
+            return Err(PartialError::Unsupported);
+        }
+        if len == 0 || len > self.block_len {
+            return Err(PartialError::InvalidLength);
+        }
+        if u64::from(addr) + u64::from(len) > self.card_size {
+            return Err(PartialError::OutOfRange);
+        }
+        if !misalign && addr % self.block_len + len > self.block_len {
+            return Err(PartialError::CrossesBlock);
+        }
+        Ok(())
+    }
+}
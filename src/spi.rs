@@ -0,0 +1,728 @@
+//! SPI mode
+//!
+//! Command framing, responses and the initialization flow of SD cards
+//! operated over SPI. Unlike the SD bus, every command is sent as a 6 byte
+//! frame and answered with an R1 status byte, optionally followed by more
+//! response bytes.
+//!
+//! Ref PLSS_v7_10 Section 7
+
+use crate::common::StatusError;
+use crate::common_cmd::{self, app_cmd, cmd, set_block_length, Cmd, Resp, R1, R3};
+use crate::crc::{self, CrcBackend, Software};
+use crate::sd_cmd::{sd_send_op_cond, send_if_cond};
+
+/// CMD0 frame, including the CRC the card checks before it enters SPI mode
+pub const GO_IDLE_FRAME: [u8; 6] = [0x40, 0, 0, 0, 0, 0x95];
+
+/// Response format of a command in SPI mode
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SpiResponse {
+    /// R1 status byte
+    R1,
+    /// R1 followed by busy signaling
+    R1b,
+    /// R1 followed by a second status byte
+    R2,
+    /// R1 followed by the OCR
+    R3,
+    /// R1 followed by the interface condition
+    R7,
+}
+
+impl SpiResponse {
+    /// Length of the response in bytes, excluding busy signaling
+    pub fn byte_len(&self) -> usize {
+        match self {
+            SpiResponse::R1 | SpiResponse::R1b => 1,
+            SpiResponse::R2 => 2,
+            SpiResponse::R3 | SpiResponse::R7 => 5,
+        }
+    }
+}
+
+/// A command framed for SPI mode
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SpiCmd {
+    /// Bytes to send on MOSI
+    pub frame: [u8; 6],
+    /// Response to expect
+    pub response: SpiResponse,
+}
+
+impl SpiCmd {
+    /// Frame `cmd` with its CRC7 and end bit
+    pub fn new<R: Resp>(cmd: &Cmd<R>, response: SpiResponse) -> Self {
+        let arg = cmd.arg.to_be_bytes();
+        let mut frame = [0x40 | (cmd.cmd & 0x3F), arg[0], arg[1], arg[2], arg[3], 0];
+        frame[5] = crc::crc7(&frame[..5]) << 1 | 1;
+        Self { frame, response }
+    }
+    /// Command index
+    pub fn index(&self) -> u8 {
+        self.frame[0] & 0x3F
+    }
+    /// Bytes to discard before looking for the response
+    ///
+    /// The card sends a stuff byte after CMD12, which may look like a
+    /// valid R1 while a multiple block read is stopped.
+    pub fn stuff_bytes(&self) -> usize {
+        match self.index() {
+            12 => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// CMD0: Reset the card and enter SPI mode. Must be sent with CS asserted
+pub fn go_idle() -> SpiCmd {
+    SpiCmd {
+        frame: GO_IDLE_FRAME,
+        response: SpiResponse::R1,
+    }
+}
+
+/// CMD12: Stop a multiple block read. See [`SpiCmd::stuff_bytes`]
+pub fn stop_transmission() -> SpiCmd {
+    SpiCmd::new(&common_cmd::stop_transmission(), SpiResponse::R1b)
+}
+
+/// CMD59: Turn CRC checking on or off. Track the result in a [`CrcMode`]
+pub fn crc_on_off(enable: bool) -> SpiCmd {
+    SpiCmd::new(&cmd::<R1>(59, u32::from(enable)), SpiResponse::R1)
+}
+
+/// CMD13: Read the card status as an [`SpiR2`]
+pub fn send_status() -> SpiCmd {
+    SpiCmd::new(&common_cmd::card_status(0, false), SpiResponse::R2)
+}
+
+/// CMD9: Read the CSD as a data block. See [`register_from_block`]
+pub fn send_csd() -> SpiCmd {
+    SpiCmd::new(&common_cmd::send_csd(0), SpiResponse::R1)
+}
+
+/// CMD10: Read the CID as a data block. See [`register_from_block`]
+pub fn send_cid() -> SpiCmd {
+    SpiCmd::new(&common_cmd::send_cid(0), SpiResponse::R1)
+}
+
+/// CID or CSD from the 16 byte data block returned by CMD9 / CMD10
+///
+/// The CRC16 following the block is always checked, independent of the
+/// [`CrcMode`].
+///
+/// ```
+/// # use sdio_host::spi::register_from_block;
+/// # use sdio_host::sd::{CID, SD};
+/// let block = [
+///     0x03, 0x53, 0x44, 0x53, 0x43, 0x31, 0x36, 0x47,
+///     0x80, 0x12, 0x34, 0x56, 0x78, 0x01, 0x29, 0x00,
+/// ];
+/// let crc = sdio_host::crc::crc16(&block);
+/// let cid: CID<SD> = register_from_block(block, crc).unwrap();
+/// assert_eq!(cid.product_name(), "SC16G");
+/// assert!(register_from_block::<CID<SD>>(block, !crc).is_err());
+/// ```
+pub fn register_from_block<T: From<[u8; 16]>>(
+    block: [u8; 16],
+    crc: u16,
+) -> Result<T, DataCrcError> {
+    CrcMode::On.check_block(&block, crc)?;
+    Ok(T::from(block))
+}
+
+/// CMD58: Read the OCR
+pub fn read_ocr() -> SpiCmd {
+    SpiCmd::new(&cmd::<R3>(58, 0), SpiResponse::R3)
+}
+
+/// A data block was received with a wrong CRC16
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DataCrcError;
+
+/// CRC checking mode of the SPI interface
+///
+/// CRC checking is off after reset, and turned on with [`crc_on_off`].
+/// Command frames always carry a valid CRC7. While CRC checking is on,
+/// data blocks carry a CRC16 which is checked in both directions.
+///
+/// ```
+/// # use sdio_host::spi::{CrcMode, DataCrcError};
+/// let block = [0xFF; 512];
+/// assert_eq!(CrcMode::On.block_crc(&block), 0x7FA1);
+/// assert_eq!(CrcMode::On.check_block(&block, 0x7FA1), Ok(()));
+/// assert_eq!(CrcMode::On.check_block(&block, 0xFFFF), Err(DataCrcError));
+/// assert_eq!(CrcMode::Off.check_block(&block, 0xFFFF), Ok(()));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CrcMode {
+    /// CRCs are ignored
+    Off,
+    /// CRCs are checked
+    On,
+}
+
+impl Default for CrcMode {
+    fn default() -> Self {
+        CrcMode::Off
+    }
+}
+
+impl CrcMode {
+    /// CRC16 to send after a data block. A dummy value if CRC checking is
+    /// off
+    pub fn block_crc(&self, data: &[u8]) -> u16 {
+        self.block_crc_with(&mut Software, data)
+    }
+    /// Check the CRC16 received after a data block
+    pub fn check_block(&self, data: &[u8], crc: u16) -> Result<(), DataCrcError> {
+        self.check_block_with(&mut Software, data, crc)
+    }
+    /// [`block_crc`](CrcMode::block_crc) computed by `backend`
+    pub fn block_crc_with<B: CrcBackend>(&self, mut backend: B, data: &[u8]) -> u16 {
+        match self {
+            CrcMode::Off => 0xFFFF,
+            CrcMode::On => backend.crc16(data),
+        }
+    }
+    /// [`check_block`](CrcMode::check_block) computed by `backend`
+    pub fn check_block_with<B: CrcBackend>(
+        &self,
+        mut backend: B,
+        data: &[u8],
+        crc: u16,
+    ) -> Result<(), DataCrcError> {
+        match self {
+            CrcMode::On if backend.crc16(data) != crc => Err(DataCrcError),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// R1 response
+///
+/// Ref PLSS_v7_10 Section 7.3.2.1
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SpiR1(pub u8);
+
+impl SpiR1 {
+    /// The card is in the idle state, running the initialization process
+    pub fn idle(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+    /// An erase sequence was cleared before executing
+    pub fn erase_reset(&self) -> bool {
+        self.0 & 0x02 != 0
+    }
+    /// Illegal command code
+    pub fn illegal_command(&self) -> bool {
+        self.0 & 0x04 != 0
+    }
+    /// The CRC check of the last command failed
+    pub fn com_crc_error(&self) -> bool {
+        self.0 & 0x08 != 0
+    }
+    /// Error in the sequence of erase commands
+    pub fn erase_sequence_error(&self) -> bool {
+        self.0 & 0x10 != 0
+    }
+    /// Misaligned address
+    pub fn address_error(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+    /// The command argument was outside the allowed range
+    pub fn parameter_error(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+    /// Bit 7 is always zero in a valid response. 0xFF means that the card
+    /// has not responded yet
+    pub fn is_valid(&self) -> bool {
+        self.0 & 0x80 == 0
+    }
+    /// Any error bit is set
+    pub fn is_error(&self) -> bool {
+        self.0 & 0x7E != 0
+    }
+    /// The first error reported, if any
+    pub fn check(&self) -> Result<(), StatusError> {
+        if self.parameter_error() {
+            Err(StatusError::OutOfRange)
+        } else if self.address_error() {
+            Err(StatusError::AddressError)
+        } else if self.erase_sequence_error() {
+            Err(StatusError::EraseSeqError)
+        } else if self.com_crc_error() {
+            Err(StatusError::ComCrcError)
+        } else if self.illegal_command() {
+            Err(StatusError::IllegalCommand)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// R2 response: R1 followed by a second status byte. Returned by CMD13
+///
+/// Ref PLSS_v7_10 Section 7.3.2.3
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SpiR2(pub [u8; 2]);
+
+impl SpiR2 {
+    /// The R1 part
+    pub fn r1(&self) -> SpiR1 {
+        SpiR1(self.0[0])
+    }
+    /// The card is locked
+    pub fn card_is_locked(&self) -> bool {
+        self.0[1] & 0x01 != 0
+    }
+    /// Write protect erase skip, or a lock/unlock command failed
+    pub fn wp_erase_skip_lock_unlock_failed(&self) -> bool {
+        self.0[1] & 0x02 != 0
+    }
+    /// General or unknown error
+    pub fn error(&self) -> bool {
+        self.0[1] & 0x04 != 0
+    }
+    /// Internal card controller error
+    pub fn cc_error(&self) -> bool {
+        self.0[1] & 0x08 != 0
+    }
+    /// Card internal ECC failed to correct the data
+    pub fn card_ecc_failed(&self) -> bool {
+        self.0[1] & 0x10 != 0
+    }
+    /// Attempt to write a write protected block
+    pub fn wp_violation(&self) -> bool {
+        self.0[1] & 0x20 != 0
+    }
+    /// Invalid selection of blocks for erase
+    pub fn erase_param(&self) -> bool {
+        self.0[1] & 0x40 != 0
+    }
+    /// Out of range, or attempt to overwrite read-only CSD fields
+    pub fn out_of_range_csd_overwrite(&self) -> bool {
+        self.0[1] & 0x80 != 0
+    }
+    /// The first error reported, if any. Errors in the R1 part are
+    /// reported first
+    pub fn check(&self) -> Result<(), StatusError> {
+        self.r1().check()?;
+        if self.out_of_range_csd_overwrite() {
+            Err(StatusError::OutOfRange)
+        } else if self.erase_param() {
+            Err(StatusError::EraseParam)
+        } else if self.wp_violation() {
+            Err(StatusError::WpViolation)
+        } else if self.card_ecc_failed() {
+            Err(StatusError::CardEccFailed)
+        } else if self.cc_error() {
+            Err(StatusError::CcError)
+        } else if self.error() {
+            Err(StatusError::Error)
+        } else if self.wp_erase_skip_lock_unlock_failed() {
+            Err(StatusError::LockUnlockFailed)
+        } else if self.card_is_locked() {
+            Err(StatusError::CardIsLocked)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Start block token, sent before a data block
+pub const START_BLOCK: u8 = 0xFE;
+
+/// Data error token, sent by the card instead of a data block after a read
+/// command failed
+///
+/// Ref PLSS_v7_10 Section 7.3.3.3
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DataErrorToken(pub u8);
+
+impl DataErrorToken {
+    /// General or unknown error
+    pub fn error(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+    /// Internal card controller error
+    pub fn cc_error(&self) -> bool {
+        self.0 & 0x02 != 0
+    }
+    /// Card internal ECC failed to correct the data
+    pub fn card_ecc_failed(&self) -> bool {
+        self.0 & 0x04 != 0
+    }
+    /// The address is out of range
+    pub fn out_of_range(&self) -> bool {
+        self.0 & 0x08 != 0
+    }
+    /// The card is locked
+    pub fn card_is_locked(&self) -> bool {
+        self.0 & 0x10 != 0
+    }
+    /// The reported error
+    pub fn status_error(&self) -> StatusError {
+        if self.out_of_range() {
+            StatusError::OutOfRange
+        } else if self.card_is_locked() {
+            StatusError::CardIsLocked
+        } else if self.card_ecc_failed() {
+            StatusError::CardEccFailed
+        } else if self.cc_error() {
+            StatusError::CcError
+        } else {
+            StatusError::Error
+        }
+    }
+}
+
+/// Byte read while waiting for a data block
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DataToken {
+    /// The data block follows
+    Start,
+    /// The read failed
+    Error(DataErrorToken),
+    /// No token yet
+    Waiting,
+}
+
+impl From<u8> for DataToken {
+    fn from(byte: u8) -> Self {
+        match byte {
+            START_BLOCK => DataToken::Start,
+            0x01..=0x1F => DataToken::Error(DataErrorToken(byte)),
+            _ => DataToken::Waiting,
+        }
+    }
+}
+
+/// Start block token of each block of a multiple block write
+pub const START_BLOCK_MULTI: u8 = 0xFC;
+
+/// Stop transmission token, ends a multiple block write
+pub const STOP_TRAN: u8 = 0xFD;
+
+/// Data response token, sent by the card after each written data block
+///
+/// Ref PLSS_v7_10 Section 7.3.3.1
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DataResponse {
+    /// The data was accepted
+    Accepted,
+    /// The data was rejected due to a CRC error
+    CrcError,
+    /// The data was rejected due to a write error
+    WriteError,
+    /// Not a valid data response token
+    Invalid(u8),
+}
+
+impl From<u8> for DataResponse {
+    fn from(byte: u8) -> Self {
+        match byte & 0x1F {
+            0b0_0101 => DataResponse::Accepted,
+            0b0_1011 => DataResponse::CrcError,
+            0b0_1101 => DataResponse::WriteError,
+            _ => DataResponse::Invalid(byte),
+        }
+    }
+}
+
+/// A block of a multiple block write that was rejected
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct WriteFailure {
+    /// Index of the block within the write, starting at zero
+    pub block: u32,
+    /// The data response token of the block
+    pub response: DataResponse,
+}
+
+/// Data response tracking of a multiple block write
+///
+/// After a failure the host ends the write with [`STOP_TRAN`] and reads the
+/// number of well written blocks with ACMD22
+/// ([`send_num_wr_blocks`](crate::sd_cmd::send_num_wr_blocks)). Blocks the
+/// card accepted are not necessarily programmed, so the write resumes at
+/// [`MultiBlockWrite::resume_block`].
+///
+/// ```
+/// # use sdio_host::spi::{DataResponse, MultiBlockWrite, WriteFailure};
+/// let mut write = MultiBlockWrite::new(4);
+/// assert_eq!(write.record(0xE5), Ok(()));
+/// assert_eq!(write.record(0xEB), Err(WriteFailure { block: 1, response: DataResponse::CrcError }));
+/// // STOP_TRAN, then ACMD22 returns [0, 0, 0, 1]
+/// assert_eq!(write.resume_block(u32::from_be_bytes([0, 0, 0, 1])), 1);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct MultiBlockWrite {
+    blocks: u32,
+    accepted: u32,
+    failure: Option<WriteFailure>,
+}
+
+impl MultiBlockWrite {
+    /// Write of `blocks` blocks
+    pub fn new(blocks: u32) -> Self {
+        Self {
+            blocks,
+            accepted: 0,
+            failure: None,
+        }
+    }
+    /// Record the data response token read after the next block
+    ///
+    /// Once a block failed, the first failure is returned for all
+    /// following tokens.
+    pub fn record(&mut self, token: u8) -> Result<(), WriteFailure> {
+        if let Some(failure) = self.failure {
+            return Err(failure);
+        }
+        match DataResponse::from(token) {
+            DataResponse::Accepted => {
+                self.accepted += 1;
+                Ok(())
+            }
+            response => {
+                let failure = WriteFailure {
+                    block: self.accepted,
+                    response,
+                };
+                self.failure = Some(failure);
+                Err(failure)
+            }
+        }
+    }
+    /// Number of blocks accepted by the card
+    pub fn accepted(&self) -> u32 {
+        self.accepted
+    }
+    /// All blocks were accepted
+    pub fn is_complete(&self) -> bool {
+        self.accepted >= self.blocks
+    }
+    /// The first rejected block, if any
+    pub fn failure(&self) -> Option<WriteFailure> {
+        self.failure
+    }
+    /// Index of the first block to write again, given the number of well
+    /// written blocks reported by ACMD22
+    pub fn resume_block(&self, well_written: u32) -> u32 {
+        well_written.min(self.accepted)
+    }
+}
+
+/// Busy state of the card, classified from a byte read on DO
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Busy {
+    /// The card holds DO low
+    Busy,
+    /// The card released DO
+    Ready,
+    /// The card stayed busy for longer than allowed
+    Timeout,
+}
+
+/// Busy wait after an R1b response, a data block write, CMD38 or CMD28
+///
+/// ```
+/// # use sdio_host::spi::{Busy, BusyWait};
+/// let mut wait = BusyWait::new(100);
+/// assert_eq!(wait.poll(0x00), Busy::Busy);
+/// assert_eq!(wait.poll(0x07), Busy::Ready);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct BusyWait {
+    polls: u32,
+    max_polls: u32,
+}
+
+impl BusyWait {
+    /// Busy wait giving up after `max_polls` bytes
+    pub fn new(max_polls: u32) -> Self {
+        Self {
+            polls: 0,
+            max_polls,
+        }
+    }
+    /// Classify the next byte read with MOSI held high
+    ///
+    /// Any non-zero byte means that DO was released, possibly during the
+    /// byte.
+    pub fn poll(&mut self, byte: u8) -> Busy {
+        if byte != 0 {
+            return Busy::Ready;
+        }
+        self.polls = self.polls.saturating_add(1);
+        if self.polls >= self.max_polls {
+            Busy::Timeout
+        } else {
+            Busy::Busy
+        }
+    }
+}
+
+/// Failure of the SPI initialization flow
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SpiInitError {
+    /// No card entered the idle state after CMD0
+    NoCard,
+    /// The card does not accept the supply voltage, or echoed a wrong check
+    /// pattern
+    Unusable,
+    /// The card did not finish initialization in time
+    Timeout,
+    /// The card rejected a command
+    Rejected(SpiR1),
+}
+
+/// A command to send during [`SpiInit`], or its outcome
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SpiInitStep {
+    /// Send the command and pass its response to [`SpiInit::update`]
+    Send(SpiCmd),
+    /// The card is ready for data transfer
+    Done {
+        /// Block addressed (SDHC / SDXC) card
+        high_capacity: bool,
+    },
+    /// Initialization failed
+    Failed(SpiInitError),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum SpiInitState {
+    GoIdle,
+    IfCond,
+    AppCmd,
+    OpCond,
+    ReadOcr,
+    SetBlockLength,
+    Done,
+    Failed(SpiInitError),
+}
+
+const CHECK_PATTERN: u8 = 0xAA;
+
+/// Initialization flow of an SD card in SPI mode
+///
+/// CMD0, CMD8, ACMD41 until the card leaves the idle state, CMD58 to read
+/// the capacity and CMD16 for standard capacity cards. Before the first
+/// step, the host must send at least 74 clocks with CS deasserted.
+///
+/// ```
+/// # use sdio_host::spi::{SpiInit, SpiInitStep, SpiR1};
+/// let mut init = SpiInit::new(100);
+/// let responses = [
+///     (0x01, 0),           // CMD0
+///     (0x01, 0x0000_01AA), // CMD8
+///     (0x01, 0),           // CMD55
+///     (0x00, 0),           // ACMD41
+///     (0x00, 0xC0FF_8000), // CMD58
+/// ];
+/// for &(r1, payload) in responses.iter() {
+///     if let SpiInitStep::Send(cmd) = init.step() {
+///         // Send cmd.frame, read cmd.response.byte_len() bytes
+///     }
+///     init.update(SpiR1(r1), payload);
+/// }
+/// assert_eq!(init.step(), SpiInitStep::Done { high_capacity: true });
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct SpiInit {
+    state: SpiInitState,
+    attempts: u32,
+    max_attempts: u32,
+    v2: bool,
+    high_capacity: bool,
+}
+
+impl SpiInit {
+    /// Initialization giving up after `max_attempts` CMD0 or ACMD41
+    /// attempts
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            state: SpiInitState::GoIdle,
+            attempts: 0,
+            max_attempts,
+            v2: false,
+            high_capacity: false,
+        }
+    }
+    /// The current step
+    pub fn step(&self) -> SpiInitStep {
+        let cmd = match self.state {
+            SpiInitState::GoIdle => go_idle(),
+            SpiInitState::IfCond => SpiCmd::new(&send_if_cond(1, CHECK_PATTERN), SpiResponse::R7),
+            SpiInitState::AppCmd => SpiCmd::new(&app_cmd(0), SpiResponse::R1),
+            SpiInitState::OpCond => {
+                SpiCmd::new(&sd_send_op_cond(self.v2, false, false, 0), SpiResponse::R1)
+            }
+            SpiInitState::ReadOcr => read_ocr(),
+            SpiInitState::SetBlockLength => SpiCmd::new(&set_block_length(512), SpiResponse::R1),
+            SpiInitState::Done => {
+                return SpiInitStep::Done {
+                    high_capacity: self.high_capacity,
+                }
+            }
+            SpiInitState::Failed(e) => return SpiInitStep::Failed(e),
+        };
+        SpiInitStep::Send(cmd)
+    }
+    /// Pass the response to the last command and get the next step
+    ///
+    /// * `r1` - R1 status byte. 0xFF if the card did not respond
+    /// * `payload` - Bytes following R1 of an R3 or R7 response, most
+    ///   significant first. Ignored for other responses
+    pub fn update(&mut self, r1: SpiR1, payload: u32) -> SpiInitStep {
+        self.state = match self.state {
+            SpiInitState::GoIdle if r1 == SpiR1(0x01) => SpiInitState::IfCond,
+            SpiInitState::GoIdle => self.retry(SpiInitState::GoIdle, SpiInitError::NoCard),
+            SpiInitState::IfCond if r1.is_valid() && r1.illegal_command() => {
+                // Version 1.x card
+                SpiInitState::AppCmd
+            }
+            SpiInitState::IfCond if !r1.is_valid() || r1.is_error() => {
+                SpiInitState::Failed(SpiInitError::Rejected(r1))
+            }
+            SpiInitState::IfCond if payload & 0xFFF == 0x100 | u32::from(CHECK_PATTERN) => {
+                self.v2 = true;
+                SpiInitState::AppCmd
+            }
+            SpiInitState::IfCond => SpiInitState::Failed(SpiInitError::Unusable),
+            SpiInitState::AppCmd | SpiInitState::ReadOcr | SpiInitState::SetBlockLength
+                if !r1.is_valid() || r1.is_error() =>
+            {
+                SpiInitState::Failed(SpiInitError::Rejected(r1))
+            }
+            SpiInitState::AppCmd => SpiInitState::OpCond,
+            SpiInitState::OpCond if r1 == SpiR1(0x00) && self.v2 => SpiInitState::ReadOcr,
+            SpiInitState::OpCond if r1 == SpiR1(0x00) => SpiInitState::SetBlockLength,
+            SpiInitState::OpCond if r1 == SpiR1(0x01) => {
+                self.retry(SpiInitState::AppCmd, SpiInitError::Timeout)
+            }
+            SpiInitState::OpCond => SpiInitState::Failed(SpiInitError::Rejected(r1)),
+            SpiInitState::ReadOcr => {
+                self.high_capacity = payload & 0x4000_0000 != 0;
+                if self.high_capacity {
+                    SpiInitState::Done
+                } else {
+                    SpiInitState::SetBlockLength
+                }
+            }
+            SpiInitState::SetBlockLength => SpiInitState::Done,
+            state => state,
+        };
+        self.step()
+    }
+    fn retry(&mut self, state: SpiInitState, error: SpiInitError) -> SpiInitState {
+        self.attempts += 1;
+        if self.attempts >= self.max_attempts {
+            SpiInitState::Failed(error)
+        } else {
+            state
+        }
+    }
+}
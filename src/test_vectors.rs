@@ -0,0 +1,480 @@
+//! SD card and eMMC register dumps with their expected decoded values
+//!
+//! Only available with the `test-vectors` feature. Registers are stored in
+//! the same little endian word order the register types are created from,
+//! the EXT_CSD as the block read from the device.
+//!
+//! ```
+//! use sdio_host::emmc::{ExtCSD, EMMC};
+//! use sdio_host::sd::{CID, CSD, SD};
+//! use sdio_host::test_vectors::{EMMC_PARTS, SD_CARDS};
+//!
+//! for card in SD_CARDS {
+//!     let cid: CID<SD> = card.cid.into();
+//!     let csd: CSD<SD> = card.csd.into();
+//!     assert_eq!(cid.product_name(), card.expected.product_name);
+//!     assert_eq!(csd.block_count(), card.expected.block_count);
+//! }
+//! for part in EMMC_PARTS {
+//!     let cid: CID<EMMC> = part.cid.into();
+//!     let ext_csd: ExtCSD = part.ext_csd.into();
+//!     assert_eq!(cid.product_name(), part.expected.product_name);
+//!     assert_eq!(ext_csd.sector_count(), part.expected.sector_count);
+//! }
+//! ```
+
+use crate::emmc::{DeviceType, EmmcVersion, HsTiming};
+use crate::sd::{BusWidth, CsdStructureVersion, CurrentConsumption, SDSpecVersion};
+use core::fmt;
+
+/// Register dump of an SD card
+#[derive(Debug, Clone)]
+pub struct SdCard {
+    /// Short description of the card
+    pub name: &'static str,
+    /// CID register
+    pub cid: [u32; 4],
+    /// CSD register
+    pub csd: [u32; 4],
+    /// OCR register, as returned by the final ACMD41
+    pub ocr: u32,
+    /// SCR register
+    pub scr: [u32; 2],
+    /// SD Status, as returned by ACMD13
+    pub sd_status: [u32; 16],
+    /// Expected decoded values
+    pub expected: SdExpected,
+}
+
+/// Decoded values of an [SdCard] dump
+#[derive(Debug, Clone)]
+pub struct SdExpected {
+    /// CID manufacturer ID
+    pub manufacturer_id: u8,
+    /// CID OEM/Application ID
+    pub oem_id: &'static str,
+    /// CID product name
+    pub product_name: &'static str,
+    /// CID product revision
+    pub product_revision: u8,
+    /// CID product serial number
+    pub serial: u32,
+    /// CID manufacturing date (month, year)
+    pub manufacturing_date: (u8, u16),
+    /// CSD structure version
+    pub csd_version: CsdStructureVersion,
+    /// CSD maximum data transfer rate
+    pub transfer_rate: u8,
+    /// CSD number of 512 byte blocks
+    pub block_count: u64,
+    /// CSD card size in bytes
+    pub card_size: u64,
+    /// CSD maximum read current at the minimum VDD
+    pub read_current_minimum_vdd: CurrentConsumption,
+    /// CSD maximum write current at the minimum VDD
+    pub write_current_minimum_vdd: CurrentConsumption,
+    /// CSD maximum read current at the maximum VDD
+    pub read_current_maximum_vdd: CurrentConsumption,
+    /// CSD maximum write current at the maximum VDD
+    pub write_current_maximum_vdd: CurrentConsumption,
+    /// CSD erase size in blocks
+    pub erase_size_blocks: u32,
+    /// OCR voltage window (mV)
+    pub voltage_window_mv: (u16, u16),
+    /// OCR switching to 1.8V accepted
+    pub v18_allowed: bool,
+    /// OCR over 2TB support status
+    pub over_2tb: bool,
+    /// OCR UHS-II card status
+    pub uhs2_card_status: bool,
+    /// OCR card capacity status
+    pub high_capacity: bool,
+    /// OCR power up status, the card is not busy
+    pub powered: bool,
+    /// SCR physical layer specification version
+    pub spec_version: SDSpecVersion,
+    /// SCR supported bus widths
+    pub bus_widths: u8,
+    /// SD Status bus width
+    pub bus_width: BusWidth,
+    /// SD Status secured mode
+    pub secure_mode: bool,
+    /// SD Status card type
+    pub sd_card_type: u16,
+    /// SD Status size of the protected area
+    pub protected_area_size: u32,
+    /// SD Status speed class
+    pub speed_class: u8,
+    /// SD Status video speed class
+    pub video_speed_class: u8,
+    /// SD Status application performance class
+    pub app_perf_class: u8,
+    /// SD Status move performance (MB/s)
+    pub move_performance: u8,
+    /// SD Status allocation unit size
+    pub allocation_unit_size: u8,
+    /// SD Status erase size
+    pub erase_size: u16,
+    /// SD Status erase timeout
+    pub erase_timeout: u8,
+    /// SD Status discard support
+    pub discard_support: bool,
+    /// SD Status UHS speed grade
+    pub uhs_speed_grade: u8,
+    /// Guaranteed write performance (MB/s) and the AU size it applies to
+    pub write_pacing: (u8, u32),
+}
+
+/// SD card register dumps, captured from the cards
+pub static SD_CARDS: &[SdCard] = &[
+    SdCard {
+        name: "Panasonic 8GB Class 4",
+        cid: [4093715758, 333095359, 808993095, 22036825],
+        csd: [171966712, 968064896, 1532559360, 1074659378],
+        ocr: 3237969920,
+        scr: [16777216, 37060608],
+        sd_status: [
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 134676480, 33722368, 50331648, 2147483648,
+        ],
+        expected: SdExpected {
+            manufacturer_id: 1,
+            oem_id: "PA",
+            product_name: "Y08AG",
+            product_revision: 19,
+            serial: 3668033524,
+            manufacturing_date: (5, 2018),
+            csd_version: CsdStructureVersion::V2,
+            transfer_rate: 50,
+            block_count: 15126528,
+            card_size: 7744782336,
+            read_current_minimum_vdd: CurrentConsumption::I_100mA,
+            write_current_minimum_vdd: CurrentConsumption::I_1mA,
+            read_current_maximum_vdd: CurrentConsumption::I_45mA,
+            write_current_maximum_vdd: CurrentConsumption::I_35mA,
+            erase_size_blocks: 1,
+            voltage_window_mv: (2700, 3600),
+            v18_allowed: false,
+            over_2tb: false,
+            uhs2_card_status: false,
+            high_capacity: true,
+            powered: true,
+            spec_version: SDSpecVersion::V3,
+            bus_widths: 5,
+            bus_width: BusWidth::Four,
+            secure_mode: false,
+            sd_card_type: 0,
+            protected_area_size: 50331648,
+            speed_class: 2,
+            video_speed_class: 0,
+            app_perf_class: 0,
+            move_performance: 2,
+            allocation_unit_size: 9,
+            erase_size: 8,
+            erase_timeout: 1,
+            discard_support: false,
+            uhs_speed_grade: 0,
+            write_pacing: (4, 4 * 1024 * 1024),
+        },
+    },
+    SdCard {
+        name: "Sandisk 8GB Class 4",
+        cid: [2197869198, 2149469225, 1429223495, 55788627],
+        csd: [171983022, 993492864, 1532559360, 1074659378],
+        ocr: 3237969920,
+        scr: [0, 37060609],
+        sd_status: [
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 184877056, 33722368, 50331648, 2147483648,
+        ],
+        expected: SdExpected {
+            manufacturer_id: 3,
+            oem_id: "SD",
+            product_name: "SU08G",
+            product_revision: 128,
+            serial: 508307843,
+            manufacturing_date: (2, 2013),
+            csd_version: CsdStructureVersion::V2,
+            transfer_rate: 50,
+            block_count: 15523840,
+            card_size: 7948206080,
+            read_current_minimum_vdd: CurrentConsumption::I_100mA,
+            write_current_minimum_vdd: CurrentConsumption::I_10mA,
+            read_current_maximum_vdd: CurrentConsumption::I_5mA,
+            write_current_maximum_vdd: CurrentConsumption::I_45mA,
+            erase_size_blocks: 1,
+            voltage_window_mv: (2700, 3600),
+            v18_allowed: false,
+            over_2tb: false,
+            uhs2_card_status: false,
+            high_capacity: true,
+            powered: true,
+            spec_version: SDSpecVersion::V3,
+            bus_widths: 5,
+            bus_width: BusWidth::Four,
+            secure_mode: false,
+            sd_card_type: 0,
+            protected_area_size: 50331648,
+            speed_class: 2,
+            video_speed_class: 0,
+            app_perf_class: 0,
+            move_performance: 2,
+            allocation_unit_size: 9,
+            erase_size: 11,
+            erase_timeout: 1,
+            discard_support: false,
+            uhs_speed_grade: 0,
+            write_pacing: (4, 4 * 1024 * 1024),
+        },
+    },
+    SdCard {
+        name: "Sandisk Extreme 32GB Class 10",
+        cid: [0xc000e344, 0x80f1086b, 0x45333247, 0x03534453],
+        csd: [0x0a4040c2, 0xedc87f80, 0x5b590000, 0x400e0032],
+        ocr: 3254747136,
+        scr: [0x00000000, 0x02358001],
+        sd_status: [
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 251992576, 67145728, 83886080, 2147483648,
+        ],
+        expected: SdExpected {
+            manufacturer_id: 3,
+            oem_id: "SD",
+            product_name: "SE32G",
+            product_revision: 128,
+            serial: 4043860928,
+            manufacturing_date: (3, 2014),
+            csd_version: CsdStructureVersion::V2,
+            transfer_rate: 50,
+            block_count: 62333952,
+            card_size: 31914983424,
+            read_current_minimum_vdd: CurrentConsumption::I_35mA,
+            write_current_minimum_vdd: CurrentConsumption::I_35mA,
+            read_current_maximum_vdd: CurrentConsumption::I_80mA,
+            write_current_maximum_vdd: CurrentConsumption::I_10mA,
+            erase_size_blocks: 1,
+            voltage_window_mv: (2700, 3600),
+            v18_allowed: true,
+            over_2tb: false,
+            uhs2_card_status: false,
+            high_capacity: true,
+            powered: true,
+            spec_version: SDSpecVersion::V3,
+            bus_widths: 5,
+            bus_width: BusWidth::Four,
+            secure_mode: false,
+            sd_card_type: 0,
+            protected_area_size: 83886080,
+            speed_class: 4,
+            video_speed_class: 0,
+            app_perf_class: 0,
+            move_performance: 0,
+            allocation_unit_size: 9,
+            erase_size: 15,
+            erase_timeout: 1,
+            discard_support: false,
+            uhs_speed_grade: 1,
+            write_pacing: (10, 8 * 1024 * 1024),
+        },
+    },
+];
+
+/// Register dump of an eMMC device
+#[derive(Clone)]
+pub struct EmmcPart {
+    /// Short description of the device
+    pub name: &'static str,
+    /// CID register
+    pub cid: [u32; 4],
+    /// CSD register
+    pub csd: [u32; 4],
+    /// OCR register, as returned by the final CMD1
+    pub ocr: u32,
+    /// EXT_CSD register, as the 512 byte block returned by CMD8
+    pub ext_csd: [u8; 512],
+    /// Expected decoded values
+    pub expected: EmmcExpected,
+}
+
+impl fmt::Debug for EmmcPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmmcPart")
+            .field("name", &self.name)
+            .field("cid", &self.cid)
+            .field("csd", &self.csd)
+            .field("ocr", &self.ocr)
+            .field("ext_csd", &&self.ext_csd[..])
+            .field("expected", &self.expected)
+            .finish()
+    }
+}
+
+/// Decoded values of an [EmmcPart] dump
+#[derive(Debug, Clone)]
+pub struct EmmcExpected {
+    /// CID manufacturer ID
+    pub manufacturer_id: u8,
+    /// CID device type
+    pub device_type: DeviceType,
+    /// CID product name
+    pub product_name: &'static str,
+    /// CID product revision (major, minor)
+    pub product_revision: (u8, u8),
+    /// CID product serial number
+    pub serial: u32,
+    /// CID manufacturing date (month, year code)
+    pub manufacturing_date: (u8, u8),
+    /// Manufacturing year, resolved with EXT_CSD_REV
+    pub manufacturing_year: u16,
+    /// CSD structure version
+    pub csd_version: u8,
+    /// OCR access mode is sector mode
+    pub sector_mode: bool,
+    /// EXT_CSD specification version
+    pub version: EmmcVersion,
+    /// EXT_CSD number of 512 byte sectors
+    pub sector_count: u32,
+    /// EXT_CSD fastest supported timing
+    pub fastest_timing: HsTiming,
+    /// EXT_CSD size of each boot partition in bytes
+    pub boot_partition_bytes: u32,
+    /// EXT_CSD size of the RPMB partition in bytes
+    pub rpmb_partition_bytes: u32,
+    /// EXT_CSD erase group size in 512 byte blocks
+    pub erase_group_blocks: u32,
+    /// EXT_CSD volatile cache size in KiB
+    pub cache_size_kib: u32,
+    /// EXT_CSD command queue depth
+    pub cmdq_depth: u8,
+}
+
+/// eMMC register dumps
+///
+/// Rebuilt from the datasheet values of each part, with valid CRCs.
+pub static EMMC_PARTS: &[EmmcPart] = &[
+    EmmcPart {
+        name: "Samsung KLMAG1JETD 16GB eMMC 5.1",
+        cid: [0xa9c164e5, 0x52057ef2, 0x4a544434, 0x15010141],
+        csd: [0x96400023, 0xffffffef, 0x8f5903ff, 0xd0270132],
+        ocr: 0xc0ff8080,
+        ext_csd: [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x02, 0x00,
+            0x57, 0x1F, 0x01, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0xA0, 0xD5, 0x01, 0x00, 0x11, 0x07, 0x07, 0x07, 0x10, 0x01, 0x11,
+            0x01, 0x07, 0x20, 0x00, 0x07, 0x11, 0xF7, 0x55, 0x11, 0x11, 0x0A, 0x00, 0xAA, 0x00,
+            0x00, 0x00, 0x00, 0x0A, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x05, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x02,
+            0x02, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x05, 0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x03,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ],
+        expected: EmmcExpected {
+            manufacturer_id: 0x15,
+            device_type: DeviceType::BGA,
+            product_name: "AJTD4R",
+            product_revision: (0, 5),
+            serial: 2129832385,
+            manufacturing_date: (6, 4),
+            manufacturing_year: 2017,
+            csd_version: 3,
+            sector_mode: true,
+            version: EmmcVersion::V5_1,
+            sector_count: 30777344,
+            fastest_timing: HsTiming::HS400,
+            boot_partition_bytes: 4194304,
+            rpmb_partition_bytes: 4194304,
+            erase_group_blocks: 1024,
+            cache_size_kib: 65536,
+            cmdq_depth: 32,
+        },
+    },
+    EmmcPart {
+        name: "SanDisk SDINBDG4-8G 8GB iNAND 7232",
+        cid: [0x5e1933f5, 0x38012c7d, 0x47343030, 0x45010144],
+        csd: [0x8a400017, 0xffffffef, 0x8f5903ff, 0xd00e0032],
+        ocr: 0xc0ff8080,
+        ext_csd: [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x02, 0x00,
+            0x57, 0x1F, 0x01, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0xE9, 0x00, 0x00, 0x11, 0x07, 0x07, 0x07, 0x10, 0x01, 0x01,
+            0x01, 0x06, 0x20, 0x00, 0x07, 0x01, 0xF7, 0x55, 0x11, 0x11, 0x0A, 0x00, 0x88, 0x88,
+            0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x05, 0x00, 0x02, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+            0x01, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0F,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x05, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x03,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ],
+        expected: EmmcExpected {
+            manufacturer_id: 0x45,
+            device_type: DeviceType::BGA,
+            product_name: "DG4008",
+            product_revision: (0, 1),
+            serial: 746413593,
+            manufacturing_date: (3, 3),
+            manufacturing_year: 2016,
+            csd_version: 3,
+            sector_mode: true,
+            version: EmmcVersion::V5_1,
+            sector_count: 15269888,
+            fastest_timing: HsTiming::HS400,
+            boot_partition_bytes: 4194304,
+            rpmb_partition_bytes: 524288,
+            erase_group_blocks: 1024,
+            cache_size_kib: 512,
+            cmdq_depth: 16,
+        },
+    },
+];
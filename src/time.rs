@@ -0,0 +1,89 @@
+//! Time source for timeouts
+//!
+//! The state machines in this crate don't measure time themselves. Hosts
+//! implement [`Clock`] on top of their timer (eg. a SysTick counter, an
+//! embedded-hal timer or Embassy's `Instant::now().as_millis()`) and use a
+//! [`Deadline`] to get real millisecond timeouts.
+//!
+//! ```
+//! # use core::cell::Cell;
+//! # use sdio_host::common_cmd::{OpCondPoll, OpCondState, OP_COND_TIMEOUT_MS};
+//! # use sdio_host::sd::{OCR, SD};
+//! # use sdio_host::time::{Clock, Deadline};
+//! struct Ticks(Cell<u32>);
+//! impl Clock for Ticks {
+//!     fn now_ms(&self) -> u32 {
+//!         self.0.get()
+//!     }
+//! }
+//!
+//! let clock = Ticks(Cell::new(5_000));
+//! let deadline = Deadline::new(&clock, OP_COND_TIMEOUT_MS);
+//! let mut poll = OpCondPoll::new(1000).timeout_ms(deadline.timeout_ms());
+//! let busy = OCR::<SD>::from(0x00FF_8000);
+//! assert_eq!(poll.poll(&busy, deadline.elapsed_ms(&clock)), OpCondState::KeepPolling);
+//! clock.0.set(6_000);
+//! assert!(deadline.expired(&clock));
+//! assert_eq!(poll.poll(&busy, deadline.elapsed_ms(&clock)), OpCondState::TimedOut);
+//! ```
+
+/// Monotonic millisecond time source
+///
+/// The counter may wrap around.
+pub trait Clock {
+    /// Current time in milliseconds
+    fn now_ms(&self) -> u32;
+}
+
+/// Blocking delay
+///
+/// Used between polls, eg. when waiting for ACMD41 or CMD1 to complete.
+pub trait Delay {
+    /// Wait at least `ms` milliseconds
+    fn delay_ms(&mut self, ms: u32);
+}
+
+impl<C: Clock> Clock for &C {
+    fn now_ms(&self) -> u32 {
+        (**self).now_ms()
+    }
+}
+
+impl<D: Delay> Delay for &mut D {
+    fn delay_ms(&mut self, ms: u32) {
+        (**self).delay_ms(ms)
+    }
+}
+
+/// A point in time `timeout_ms` after it was created
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Deadline {
+    start_ms: u32,
+    timeout_ms: u32,
+}
+
+impl Deadline {
+    /// Deadline `timeout_ms` milliseconds from now
+    pub fn new<C: Clock>(clock: &C, timeout_ms: u32) -> Self {
+        Self {
+            start_ms: clock.now_ms(),
+            timeout_ms,
+        }
+    }
+    /// The timeout the deadline was created with
+    pub fn timeout_ms(&self) -> u32 {
+        self.timeout_ms
+    }
+    /// Milliseconds since the deadline was created
+    pub fn elapsed_ms<C: Clock>(&self, clock: &C) -> u32 {
+        clock.now_ms().wrapping_sub(self.start_ms)
+    }
+    /// The timeout has elapsed
+    pub fn expired<C: Clock>(&self, clock: &C) -> bool {
+        self.elapsed_ms(clock) >= self.timeout_ms
+    }
+    /// Milliseconds left until the deadline, zero if expired
+    pub fn remaining_ms<C: Clock>(&self, clock: &C) -> u32 {
+        self.timeout_ms.saturating_sub(self.elapsed_ms(clock))
+    }
+}
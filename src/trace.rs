@@ -0,0 +1,56 @@
+//! Command and response tracing
+//!
+//! Only available with the `trace` feature. Hosts call a [`Tracer`] for
+//! every command they send and every response they receive, so protocol
+//! traces are captured the same way on every HAL.
+//!
+//! ```
+//! # use sdio_host::common_cmd::{card_status, Cmd, Resp};
+//! # use sdio_host::trace::Tracer;
+//! #[derive(Default)]
+//! struct Count(u32);
+//!
+//! impl Tracer for Count {
+//!     fn on_cmd<R: Resp>(&mut self, _cmd: &Cmd<R>) {
+//!         self.0 += 1;
+//!     }
+//! }
+//!
+//! fn send<T: Tracer>(tracer: &mut T) {
+//!     let cmd = card_status(0x1234, false);
+//!     tracer.on_cmd(&cmd);
+//!     // Send cmd and wait for the response
+//!     tracer.on_resp(&[0x900]);
+//! }
+//!
+//! let mut count = Count::default();
+//! send(&mut count);
+//! assert_eq!(count.0, 1);
+//! ```
+
+use crate::common_cmd::{Cmd, Resp, Stats};
+
+/// Receives the commands and responses on the bus
+pub trait Tracer {
+    /// A command is sent
+    fn on_cmd<R: Resp>(&mut self, cmd: &Cmd<R>);
+    /// A response was received. One word for short responses, four words,
+    /// least significant first, for R2
+    fn on_resp(&mut self, _resp: &[u32]) {}
+}
+
+impl<T: Tracer> Tracer for &mut T {
+    fn on_cmd<R: Resp>(&mut self, cmd: &Cmd<R>) {
+        (**self).on_cmd(cmd)
+    }
+    fn on_resp(&mut self, resp: &[u32]) {
+        (**self).on_resp(resp)
+    }
+}
+
+/// Counts the commands sent
+impl Tracer for Stats {
+    fn on_cmd<R: Resp>(&mut self, _cmd: &Cmd<R>) {
+        self.record_command();
+    }
+}
@@ -0,0 +1,131 @@
+//! Sampling point tuning for SDR104 / SDR50 and HS200
+//!
+//! Sans-IO description of the tuning procedure. The host sets each sampling
+//! phase it wants to test, sends the tuning command and reports whether the
+//! tuning block was received correctly. [`Tuning`] then selects the phase in
+//! the middle of the widest passing window.
+//!
+//! ```
+//! # use sdio_host::tuning::Tuning;
+//! let mut tuning = Tuning::sd(16);
+//! while let Some((phase, cmd)) = tuning.next() {
+//!     // Set sampling phase, send cmd and compare the tuning block
+//!     let pass = (4..=10).contains(&phase);
+//!     tuning.record(pass);
+//! }
+//! assert_eq!(tuning.result(), Ok(7));
+//! ```
+//!
+//! Ref PLSS_v7_10 Section 4.2.4.5, JESD84-B51 Section 6.6.5.1
+
+use crate::common_cmd::{Cmd, R1};
+use crate::emmc_cmd::send_tuning_block_hs200;
+use crate::sd_cmd::send_tuning_block;
+
+/// Maximum number of tuning commands the host may send
+pub const MAX_TUNING_ITERATIONS: u8 = 40;
+/// Maximum number of phases tracked by [`Tuning`]
+pub const MAX_PHASES: u8 = 64;
+/// Size in bytes of the tuning block on a 4-bit bus
+pub const TUNING_BLOCK_4BIT: usize = 64;
+/// Size in bytes of the tuning block on an 8-bit bus (eMMC only)
+pub const TUNING_BLOCK_8BIT: usize = 128;
+
+/// Tuning did not find a usable sampling point
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TuningError {
+    /// Not all phases have been tested yet
+    Incomplete,
+    /// No tested phase received the tuning block correctly
+    NoPassingPhase,
+}
+
+/// Sampling point tuning procedure
+#[derive(Debug, Copy, Clone)]
+pub struct Tuning {
+    emmc: bool,
+    phases: u8,
+    step: u8,
+    phase: u8,
+    passed: u64,
+}
+
+impl Tuning {
+    fn new(emmc: bool, phases: u8) -> Self {
+        let phases = phases.max(1).min(MAX_PHASES);
+        Self {
+            emmc,
+            phases,
+            step: (phases + MAX_TUNING_ITERATIONS - 1) / MAX_TUNING_ITERATIONS,
+            phase: 0,
+            passed: 0,
+        }
+    }
+    /// Tuning of an SD card using CMD19, over `phases` sampling phases
+    ///
+    /// If the host has more phases than [`MAX_TUNING_ITERATIONS`], only
+    /// evenly spaced phases are tested.
+    pub fn sd(phases: u8) -> Self {
+        Self::new(false, phases)
+    }
+    /// Tuning of an eMMC device using CMD21, over `phases` sampling phases
+    pub fn emmc(phases: u8) -> Self {
+        Self::new(true, phases)
+    }
+    /// The next phase to test and the command to send, or `None` if tuning
+    /// is complete
+    pub fn next(&self) -> Option<(u8, Cmd<R1>)> {
+        if self.phase >= self.phases {
+            return None;
+        }
+        let cmd = if self.emmc {
+            send_tuning_block_hs200()
+        } else {
+            send_tuning_block(0)
+        };
+        Some((self.phase, cmd))
+    }
+    /// Record whether the tuning block was received correctly with the
+    /// current phase
+    pub fn record(&mut self, pass: bool) {
+        if self.phase >= self.phases {
+            return;
+        }
+        if pass {
+            self.passed |= 1 << self.phase;
+        }
+        self.phase += self.step;
+    }
+    /// Whether `phase` was tested and passed
+    pub fn passed(&self, phase: u8) -> bool {
+        phase < MAX_PHASES && self.passed & (1 << phase) != 0
+    }
+    /// The phase in the middle of the widest window of passing phases
+    pub fn result(&self) -> Result<u8, TuningError> {
+        if self.phase < self.phases {
+            return Err(TuningError::Incomplete);
+        }
+        let mut best: Option<(u8, u8)> = None;
+        let mut start = None;
+        let mut phase = 0;
+        while phase < self.phases + self.step {
+            let pass = phase < self.phases && self.passed(phase);
+            match (pass, start) {
+                (true, None) => start = Some(phase),
+                (false, Some(first)) => {
+                    let last = phase - self.step;
+                    if best.map_or(true, |(a, b)| last - first > b - a) {
+                        best = Some((first, last));
+                    }
+                    start = None;
+                }
+                _ => {}
+            }
+            phase += self.step;
+        }
+        match best {
+            Some((first, last)) => Ok(first + (last - first) / self.step / 2 * self.step),
+            None => Err(TuningError::NoPassingPhase),
+        }
+    }
+}
@@ -1,323 +1,114 @@
-use sdio_host::sd::{BusWidth, CID, CSD, CurrentConsumption, OCR, SD, SDSpecVersion, SDStatus, SCR};
-
-struct TestCard {
-    cid: [u32; 4],
-    cidr: CidRes,
-    csd: [u32; 4],
-    csdr: CsdRes,
-    ocr: u32,
-    ocrr: OcrRes,
-    status: [u32; 16],
-    statusr: StatusRes,
-    scr: [u32; 2],
-    scrr: ScrRes,
-}
-
-struct CidRes {
-    mid: u8,
-    serial: u32,
-    name: &'static str,
-    oem: &'static str,
-    revision: u8,
-    m_month: u8,
-    m_year: u16,
-}
-
-struct CsdRes {
-    version: u8,
-    transfer_rate: u8,
-    blocks: u64,
-    size_bytes: u64,
-    read_current_minimum_vdd: CurrentConsumption,
-    write_current_minimum_vdd: CurrentConsumption,
-    read_current_maximum_vdd: CurrentConsumption,
-    write_current_maximum_vdd: CurrentConsumption,
-    erase_size_blocks: u32,
-}
-
-struct OcrRes {
-    voltage_window_mv: (u16, u16),
-    v18_allowed: bool,
-    over_2tb: bool,
-    uhs2_card_status: bool,
-    high_capacity: bool,
-    powered: bool,
-}
-
-struct StatusRes {
-    bus_width: BusWidth,
-    secure_mode: bool,
-    sd_card_type: u16,
-    protected_area_size: u32,
-    speed_class: u8,
-    video_speed_class: u8,
-    app_perf_class: u8,
-    move_performance: u8,
-    allocation_unit_size: u8,
-    erase_size: u16,
-    erase_timeout: u8,
-    discard_support: bool,
-}
-
-struct ScrRes {
-    bus_widths: u8,
-
-    version: SDSpecVersion,
-}
-
-static CARDS: &[TestCard] = &[
-    // Panasonic 8 Gb Class 4
-    TestCard {
-        cid: [4093715758, 333095359, 808993095, 22036825],
-        cidr: CidRes {
-            mid: 1,
-            serial: 3668033524,
-            name: "Y08AG",
-            oem: "PA",
-            revision: 19,
-            m_month: 5,
-            m_year: 2018,
-        },
-        csd: [171966712, 968064896, 1532559360, 1074659378],
-        csdr: CsdRes {
-            version: 1,
-            transfer_rate: 50,
-            blocks: 15126528,
-            size_bytes: 7744782336,
-            read_current_minimum_vdd: CurrentConsumption::I_100mA,
-            write_current_minimum_vdd: CurrentConsumption::I_1mA,
-            read_current_maximum_vdd: CurrentConsumption::I_45mA,
-            write_current_maximum_vdd: CurrentConsumption::I_35mA,
-            erase_size_blocks: 1,
-        },
-        ocr: 3237969920,
-        ocrr: OcrRes {
-            voltage_window_mv: (2700, 3600),
-            v18_allowed: false,
-            over_2tb: false,
-            uhs2_card_status: false,
-            high_capacity: true,
-            powered: true,
-        },
-
-        status: [
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 134676480, 33722368, 50331648, 2147483648,
-        ],
-        statusr: StatusRes {
-            bus_width: BusWidth::Four,
-            secure_mode: false,
-            sd_card_type: 0,
-            protected_area_size: 50331648,
-            speed_class: 2, // Class 4
-            video_speed_class: 0,
-            app_perf_class: 0,
-            move_performance: 2, // MB/s
-            allocation_unit_size: 9,
-            erase_size: 8,
-            erase_timeout: 1,
-            discard_support: false,
-        },
-        scr: [16777216, 37060608],
-        scrr: ScrRes {
-            bus_widths: 5,
-            version: SDSpecVersion::V3,
-        },
-    },
-    // Sandisk 8 Gb Class 4
-    TestCard {
-        cid: [2197869198, 2149469225, 1429223495, 55788627],
-        cidr: CidRes {
-            mid: 3,
-            serial: 508307843,
-            name: "SU08G",
-            oem: "SD",
-            revision: 128,
-            m_month: 2,
-            m_year: 2013,
-        },
-        csd: [171983022, 993492864, 1532559360, 1074659378],
-        csdr: CsdRes {
-            version: 1,
-            transfer_rate: 50,
-            size_bytes: 7948206080,
-            blocks: 15523840,
-            read_current_minimum_vdd: CurrentConsumption::I_100mA,
-            write_current_minimum_vdd: CurrentConsumption::I_10mA,
-            read_current_maximum_vdd: CurrentConsumption::I_5mA,
-            write_current_maximum_vdd: CurrentConsumption::I_45mA,
-            erase_size_blocks: 1,
-        },
-
-        ocr: 3237969920,
-        ocrr: OcrRes {
-            voltage_window_mv: (2700, 3600),
-            v18_allowed: false,
-            over_2tb: false,
-            uhs2_card_status: false,
-            high_capacity: true,
-            powered: true,
-        },
-
-        status: [
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 184877056, 33722368, 50331648, 2147483648,
-        ],
-        statusr: StatusRes {
-            bus_width: BusWidth::Four,
-            secure_mode: false,
-            sd_card_type: 0,
-            protected_area_size: 50331648,
-            speed_class: 2, // Class 4
-            video_speed_class: 0,
-            app_perf_class: 0,
-            move_performance: 2, // MB/s
-            allocation_unit_size: 9,
-            erase_size: 11,
-            erase_timeout: 1,
-            discard_support: false,
-        },
-
-        scr: [0, 37060609],
-        scrr: ScrRes {
-            bus_widths: 5,
-            version: SDSpecVersion::V3,
-        },
-    },
-    // Sandisk extreme 32Gb Class 10
-    TestCard {
-        cid: [0xc000e344, 0x80f1086b, 0x45333247, 0x03534453],
-        cidr: CidRes {
-            mid: 3,
-            serial: 4043860928,
-            name: "SE32G",
-            oem: "SD",
-            revision: 128,
-            m_month: 3,
-            m_year: 2014,
-        },
-        csd: [0x0a4040c2, 0xedc87f80, 0x5b590000, 0x400e0032],
-        csdr: CsdRes {
-            version: 1,
-            transfer_rate: 50,
-            size_bytes: 31914983424,
-            blocks: 62333952,
-            read_current_minimum_vdd: CurrentConsumption::I_35mA,
-            write_current_minimum_vdd: CurrentConsumption::I_35mA,
-            read_current_maximum_vdd: CurrentConsumption::I_80mA,
-            write_current_maximum_vdd: CurrentConsumption::I_10mA,
-            erase_size_blocks: 1,
-        },
-
-        ocr: 3254747136,
-        ocrr: OcrRes {
-            voltage_window_mv: (2700, 3600),
-            v18_allowed: true,
-            over_2tb: false,
-            uhs2_card_status: false,
-            high_capacity: true,
-            powered: true,
-        },
-
-        status: [
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 251992576, 67145728, 83886080, 2147483648,
-        ],
-        statusr: StatusRes {
-            bus_width: BusWidth::Four,
-            secure_mode: false,
-            sd_card_type: 0,
-            protected_area_size: 83886080,
-            speed_class: 4, // Class 10
-            video_speed_class: 0,
-            app_perf_class: 0,
-            move_performance: 0, // Ignore for class 10
-            allocation_unit_size: 9,
-            erase_size: 15,
-            erase_timeout: 1,
-            discard_support: false,
-        },
-
-        scr: [0x00000000, 0x02358001],
-        scrr: ScrRes {
-            bus_widths: 5,
-            version: SDSpecVersion::V3,
-        },
-    },
-];
+use sdio_host::common_cmd::{BlockAddress, OutOfRange};
+use sdio_host::sd::{
+    r2_from_registers, BusWidth, CrcIncluded, SDSpecVersion, SDStatus, VoltageError, WordOrder,
+    CID, CSD, OCR, SCR, SD,
+};
+use sdio_host::test_vectors::SD_CARDS;
 
 #[test]
 fn test_cid() {
-    for card in CARDS {
+    for card in SD_CARDS {
         let cid: CID<SD> = card.cid.into();
         println!("{:?}", cid);
 
-        assert_eq!(cid.serial(), card.cidr.serial);
-        assert_eq!(cid.manufacturer_id(), card.cidr.mid);
-        assert_eq!(cid.product_revision(), card.cidr.revision);
+        assert_eq!(cid.serial(), card.expected.serial);
+        assert_eq!(cid.manufacturer_id(), card.expected.manufacturer_id);
+        assert_eq!(cid.product_revision(), card.expected.product_revision);
+
+        assert_eq!(cid.product_name(), card.expected.product_name);
+        assert_eq!(cid.oem_id(), card.expected.oem_id);
 
-        assert_eq!(cid.product_name(), card.cidr.name);
-        assert_eq!(cid.oem_id(), card.cidr.oem);
+        assert_eq!(cid.manufacturing_date(), card.expected.manufacturing_date);
 
-        assert_eq!(cid.manufacturing_date().0, card.cidr.m_month);
-        assert_eq!(cid.manufacturing_date().1, card.cidr.m_year);
+        assert!(cid.is_plausible());
+        let mut garbled = card.cid;
+        garbled[2] ^= 0x100;
+        assert!(!CID::<SD>::from(garbled).is_plausible());
     }
 }
 
 #[test]
 fn test_csd() {
-    for card in CARDS {
+    // SDSC with 1024 byte READ_BL_LEN
+    let sdsc = CSD::<SD>::from(0xA_u128 << 80);
+    assert_eq!(sdsc.block_length_cmd().map(|cmd| cmd.arg), Some(512));
+    assert_eq!(CSD::<SD>::from(0x9_u128 << 80).block_length_cmd(), None);
+
+    for card in SD_CARDS {
         let csd: CSD<SD> = card.csd.into();
         println!("{:?}", csd);
 
-        assert_eq!(csd.version(), card.csdr.version);
-        assert_eq!(csd.transfer_rate(), card.csdr.transfer_rate);
+        assert_eq!(csd.version(), card.expected.csd_version);
+        assert_eq!(csd.transfer_rate(), card.expected.transfer_rate);
 
-        assert_eq!(csd.block_count(), card.csdr.blocks);
-        assert_eq!(csd.card_size(), card.csdr.size_bytes);
+        assert_eq!(csd.block_count(), card.expected.block_count);
+        assert_eq!(csd.card_size(), card.expected.card_size);
+        assert!(csd.block_length_cmd().is_none());
 
         assert_eq!(
             csd.read_current_minimum_vdd(),
-            card.csdr.read_current_minimum_vdd
+            card.expected.read_current_minimum_vdd
         );
         assert_eq!(
             csd.write_current_minimum_vdd(),
-            card.csdr.write_current_minimum_vdd
+            card.expected.write_current_minimum_vdd
         );
         assert_eq!(
             csd.read_current_maximum_vdd(),
-            card.csdr.read_current_maximum_vdd
+            card.expected.read_current_maximum_vdd
         );
         assert_eq!(
             csd.write_current_maximum_vdd(),
-            card.csdr.write_current_maximum_vdd
+            card.expected.write_current_maximum_vdd
         );
-        assert_eq!(csd.erase_size_blocks(), card.csdr.erase_size_blocks);
+        assert_eq!(csd.erase_size_blocks(), card.expected.erase_size_blocks);
+
+        assert!(csd.is_plausible());
+
+        let blocks = (card.expected.card_size / 512) as u32;
+        assert_eq!(csd.check_range(BlockAddress(blocks - 8), 8), Ok(()));
+        assert_eq!(
+            csd.check_range(BlockAddress(blocks - 8), 9),
+            Err(OutOfRange)
+        );
+        let mut garbled = card.csd;
+        garbled[3] ^= 0x8000;
+        assert!(!CSD::<SD>::from(garbled).is_plausible());
     }
 }
 
 #[test]
 fn test_ocr() {
-    for card in CARDS {
+    for card in SD_CARDS {
         let ocr: OCR<SD> = card.ocr.into();
         println!("{:?}", ocr);
 
         assert_eq!(
             ocr.voltage_window_mv().unwrap(),
-            card.ocrr.voltage_window_mv
+            card.expected.voltage_window_mv
+        );
+        assert_eq!(ocr.v18_allowed(), card.expected.v18_allowed);
+        assert_eq!(ocr.over_2tb(), card.expected.over_2tb);
+        assert_eq!(ocr.uhs2_card_status(), card.expected.uhs2_card_status);
+        assert_eq!(ocr.high_capacity(), card.expected.high_capacity);
+        assert_eq!(ocr.is_busy(), !card.expected.powered);
+
+        assert_eq!(ocr.usable_voltage_mv((3300, 3300)), Ok((3300, 3300)));
+        assert_eq!(ocr.usable_voltage_mv((1800, 3000)), Ok((2700, 3000)));
+        assert_eq!(
+            ocr.usable_voltage_mv((1700, 1950)),
+            Err(VoltageError::NoOverlap)
         );
-        assert_eq!(ocr.v18_allowed(), card.ocrr.v18_allowed);
-        assert_eq!(ocr.over_2tb(), card.ocrr.over_2tb);
-        assert_eq!(ocr.uhs2_card_status(), card.ocrr.uhs2_card_status);
-        assert_eq!(ocr.high_capacity(), card.ocrr.high_capacity);
-        assert_eq!(ocr.is_busy(), !card.ocrr.powered);
     }
 }
 
 #[test]
 fn test_sdstatus() {
-    for card in CARDS {
-        let status: SDStatus = card.status.into();
+    for card in SD_CARDS {
+        let status: SDStatus = card.sd_status.into();
         println!("{:?}", status);
 
-        let r = &card.statusr;
+        let r = &card.expected;
         assert_eq!(status.bus_width(), r.bus_width);
         assert_eq!(status.secure_mode(), r.secure_mode);
         assert_eq!(status.sd_memory_card_type(), r.sd_card_type);
@@ -330,17 +121,1944 @@ fn test_sdstatus() {
         assert_eq!(status.erase_size(), r.erase_size);
         assert_eq!(status.erase_timeout(), r.erase_timeout);
         assert_eq!(status.discard_support(), r.discard_support);
+
+        assert_eq!(status.uhs_speed_grade(), r.uhs_speed_grade);
+
+        let pacing = status.write_pacing();
+        assert_eq!(
+            (pacing.min_write_mb_per_s, pacing.au_size_bytes),
+            r.write_pacing
+        );
     }
 }
 
+#[test]
+fn test_sdstatus_offsets() {
+    // SanDisk Extreme dump with VIDEO_SPEED_CLASS [391:384] set to V30 and
+    // SUS_ADDR bits set in the word below it
+    let mut words = SD_CARDS[2].sd_status;
+    words[12] |= 30;
+    words[11] |= 0xAB;
+    let status: SDStatus = words.into();
+    assert_eq!(status.video_speed_class(), 30);
+    let status: SDStatus = SD_CARDS[2].sd_status.into();
+    assert_eq!(status.video_speed_class(), 0);
+
+    // DISCARD_SUPPORT [313]
+    let mut words = SD_CARDS[2].sd_status;
+    words[9] |= 0x0200_0000;
+    assert!(SDStatus::from(words).discard_support());
+    let mut words = SD_CARDS[2].sd_status;
+    words[8] |= 0x0200_0000;
+    assert!(!SDStatus::from(words).discard_support());
+
+    // APP_PERF_CLASS [339:336], next to PERFORMANCE_ENHANCE
+    let mut words = SD_CARDS[2].sd_status;
+    words[10] |= 2 << 16;
+    words[9] |= 0xF << 16;
+    assert_eq!(SDStatus::from(words).app_perf_class(), 2);
+}
+
+#[test]
+fn test_sdstatus_security() {
+    // SECURITY_FUNCTIONS [508:502], below SECURED_MODE [509]
+    let mut words = SD_CARDS[0].sd_status;
+    words[15] |= 0x2000_0000 | (0x55 << 22) | 0x0020_0000;
+    let status: SDStatus = words.into();
+    assert_eq!(status.security_functions(), 0x55);
+    assert!(status.secure_mode());
+    words[15] = 0x1FC0_0000;
+    assert_eq!(SDStatus::from(words).security_functions(), 0x7F);
+    assert!(!SDStatus::from(words).secure_mode());
+    let status: SDStatus = SD_CARDS[0].sd_status.into();
+    assert_eq!(status.security_functions(), 0);
+
+    // SDHC: SIZE_OF_PROTECTED_AREA is in bytes
+    let card = &SD_CARDS[2];
+    let status: SDStatus = card.sd_status.into();
+    assert_eq!(
+        status.protected_area_bytes(&card.csd.into()),
+        u64::from(card.expected.protected_area_size)
+    );
+
+    // SDSC: in units of MULT * BLOCK_LEN, C_SIZE_MULT 7 and READ_BL_LEN 9
+    let sdsc = CSD::<SD>::from(9u128 << 80 | 7 << 47);
+    let mut words = [0; 16];
+    words[14] = 3;
+    let status: SDStatus = words.into();
+    assert_eq!(status.protected_area_bytes(&sdsc), 3 << 18);
+    assert_eq!(status.protected_area_size(), 3);
+}
+
+#[test]
+fn test_video_speed_class() {
+    use sdio_host::sd_cmd::{resume_au, speed_class_control_fn, suspend_au, SpeedClassControl};
+
+    // VSC_AU_SIZE [377:368] and SUS_ADDR [367:346], with the neighbouring
+    // bits set
+    let mut words = SD_CARDS[2].sd_status;
+    words[12] |= 0xFF;
+    words[11] = 0xFC00_0000 | (0x200 << 16) | 0xABCD;
+    words[10] |= 0x2C00_0000 | 0x03FF_FFFF;
+    let status: SDStatus = words.into();
+    assert_eq!(status.vsc_au_size(), 0x200);
+    assert_eq!(status.sus_addr(), 0xABCD << 6 | 0x0B);
+    let status: SDStatus = SD_CARDS[2].sd_status.into();
+    assert_eq!((status.vsc_au_size(), status.sus_addr()), (0, 0));
+
+    let cmd = speed_class_control_fn(SpeedClassControl::SetFreeAu, 0xFFFF_1234);
+    assert_eq!((cmd.cmd, cmd.arg), (20, 0x7FFF_1234));
+    let cmd = speed_class_control_fn(SpeedClassControl::ReleaseDir, 3);
+    assert_eq!((cmd.cmd, cmd.arg), (20, 0x8000_0003));
+    assert_eq!((suspend_au().cmd, suspend_au().arg), (20, 0x5000_0000));
+    assert_eq!((resume_au().cmd, resume_au().arg), (20, 0x6000_0000));
+}
+
+#[test]
+fn test_sd_express() {
+    use sdio_host::sd::{ExpressDecision, CIC};
+    use sdio_host::sd_cmd::send_if_cond_express;
+
+    let cmd = send_if_cond_express(1, 0xAA, true, true);
+    assert_eq!((cmd.cmd, cmd.arg), (8, 0x31AA));
+    let cmd = send_if_cond_express(0xF1, 0xAA, true, false);
+    assert_eq!(cmd.arg, 0x11AA);
+    let cmd = send_if_cond_express(1, 0x55, false, false);
+    assert_eq!(cmd.arg, 0x0155);
+
+    let legacy = CIC::from(0x01AA);
+    assert!(!legacy.pcie() && !legacy.pcie_1v2());
+    assert_eq!(legacy.express_decision(true), ExpressDecision::SdMode);
+    assert_eq!(legacy.express_decision(false), ExpressDecision::SdMode);
+
+    let express = CIC::from(0x11AA);
+    assert!(express.pcie() && !express.pcie_1v2());
+    assert_eq!(
+        express.express_decision(false),
+        ExpressDecision::SdModeExpressCard
+    );
+    assert_eq!(
+        express.express_decision(true),
+        ExpressDecision::SwitchToPcie { v1_2: false }
+    );
+    assert_eq!(
+        CIC::from(0x31AA).express_decision(true),
+        ExpressDecision::SwitchToPcie { v1_2: true }
+    );
+}
+
+#[test]
+fn test_erase_cmds() {
+    use sdio_host::sd_cmd::{erase_wr_blk_end_addr, erase_wr_blk_start_addr};
+
+    let start = erase_wr_blk_start_addr(0x100);
+    let end = erase_wr_blk_end_addr(0x1FF);
+    assert_eq!((start.cmd, start.arg), (32, 0x100));
+    assert_eq!((end.cmd, end.arg), (33, 0x1FF));
+}
+
+#[test]
+fn test_uhs_capabilities() {
+    use sdio_host::sd::UhsCapabilities;
+
+    let card = &SD_CARDS[2];
+    let mut words = card.sd_status;
+    assert_eq!(words[12] & 0xFF00, 0x1A00);
+    // UHS_SPEED_GRADE 3, UHS_AU_SIZE 9
+    words[12] = words[12] & !0xFF00 | 0x3900;
+    let caps = UhsCapabilities::new(&card.ocr.into(), &words.into());
+    assert_eq!(
+        caps,
+        UhsCapabilities {
+            uhs1: true,
+            uhs2: false,
+            speed_grade: 3,
+            allocation_unit_size: 9,
+        }
+    );
+    let caps = UhsCapabilities::new(&card.ocr.into(), &card.sd_status.into());
+    assert_eq!((caps.speed_grade, caps.allocation_unit_size), (1, 10));
+    let caps = UhsCapabilities::new(&SD_CARDS[0].ocr.into(), &SD_CARDS[0].sd_status.into());
+    assert!(!caps.uhs1 && !caps.uhs2);
+}
+
 #[test]
 fn test_scr() {
-    for card in CARDS {
+    for card in SD_CARDS {
         let scr: SCR = card.scr.into();
         println!("{:?}", scr);
 
-        let r = &card.scrr;
+        let r = &card.expected;
         assert_eq!(scr.bus_widths(), r.bus_widths);
-        assert_eq!(scr.version(), r.version);
+        assert_eq!(scr.version(), r.spec_version);
+    }
+}
+
+#[test]
+fn test_r2_from_registers() {
+    for card in SD_CARDS {
+        let w = card.cid;
+        let msf = [w[3], w[2], w[1], w[0]];
+        assert_eq!(
+            r2_from_registers(msf, WordOrder::MostSignificantFirst, CrcIncluded::Yes),
+            card.cid
+        );
+
+        let shifted = [
+            (w[0] >> 8) | (w[1] << 24),
+            (w[1] >> 8) | (w[2] << 24),
+            (w[2] >> 8) | (w[3] << 24),
+            w[3] >> 8,
+        ];
+        let cid: CID<SD> =
+            r2_from_registers(shifted, WordOrder::LeastSignificantFirst, CrcIncluded::No).into();
+        assert_eq!(cid.serial(), card.expected.serial);
+        assert_eq!(cid.product_name(), card.expected.product_name);
+    }
+}
+
+#[test]
+fn test_retry_policy() {
+    use sdio_host::common_cmd::{
+        read_single_block, write_single_block, RetryPolicy, TransientError,
+    };
+
+    let policy = RetryPolicy::default();
+    let read = read_single_block(0);
+    assert!(policy.should_retry(&read, TransientError::Crc, 3));
+    assert!(!policy.should_retry(&read, TransientError::Crc, 4));
+    assert!(!policy.should_retry(&write_single_block(0), TransientError::Timeout, 1));
+    assert!(!RetryPolicy::none().should_retry(&read, TransientError::Crc, 1));
+}
+
+#[test]
+fn test_stats() {
+    use sdio_host::common_cmd::{Stats, TransientError};
+
+    let mut stats = Stats::default();
+    stats.record_command();
+    stats.record_error(TransientError::Crc);
+    stats.record_retry();
+    stats.record_command();
+    stats.record_error(TransientError::Timeout);
+    stats.record_error(TransientError::Timeout);
+    stats.record_read(512);
+    stats.record_read(1024);
+    stats.record_write(512);
+    assert_eq!(
+        stats,
+        Stats {
+            commands: 2,
+            crc_errors: 1,
+            timeouts: 2,
+            retries: 1,
+            bytes_read: 1536,
+            bytes_written: 512,
+        }
+    );
+
+    // Counters saturate
+    stats.commands = u32::max_value();
+    stats.bytes_written = u64::max_value();
+    stats.record_command();
+    stats.record_write(512);
+    assert_eq!(stats.commands, u32::max_value());
+    assert_eq!(stats.bytes_written, u64::max_value());
+
+    stats.reset();
+    assert_eq!(stats, Stats::default());
+}
+
+#[test]
+fn test_power_up() {
+    use sdio_host::power::{PowerUp, PowerUpStep};
+
+    let steps: Vec<PowerUpStep> = PowerUp::new(400_000).ramp_up_ms(5).steps().collect();
+    assert_eq!(
+        steps,
+        [
+            PowerUpStep::PowerOff { ms: 1 },
+            PowerUpStep::PowerOn { ms: 5 },
+            PowerUpStep::Wait { ms: 1 },
+            PowerUpStep::InitClocks {
+                hz: 400_000,
+                cycles: 74,
+                us: 185
+            },
+        ]
+    );
+    assert_eq!(
+        PowerUp::new(400_000).without_power_cycle().steps().count(),
+        3
+    );
+
+    // Resumed one step at a time
+    let mut sequence = PowerUp::new(400_000).ramp_up_ms(5).steps();
+    let mut resumed = vec![sequence.step().unwrap()];
+    while let Some(step) = sequence.advance() {
+        resumed.push(step);
+    }
+    assert_eq!(resumed, steps);
+    assert_eq!(sequence.step(), None);
+    assert_eq!(sequence.advance(), None);
+}
+
+#[test]
+fn test_cmd_eq() {
+    use sdio_host::common_cmd::{app_cmd, card_status, deselect_card, select_card};
+
+    assert_eq!(select_card(0x1234), select_card(0x1234));
+    assert_ne!(card_status(0x1234, false), card_status(0x1234, true));
+    assert_eq!(
+        format!("{:?}", select_card(0x1234)),
+        "Cmd { cmd: 7, arg: 0x12340000 }"
+    );
+
+    assert_eq!(select_card(0x1234).rca(), Some(0x1234));
+    assert_eq!(card_status(0xBEEF, true).rca(), Some(0xBEEF));
+    assert_eq!(app_cmd(0xAAAA).rca(), Some(0xAAAA));
+    assert_eq!(deselect_card().rca(), Some(0));
+    assert_eq!(
+        sdio_host::common_cmd::read_single_block(0x1234_0000).rca(),
+        None
+    );
+}
+
+#[test]
+fn test_discard() {
+    use sdio_host::common_cmd::UnsupportedOperation;
+    use sdio_host::sd_cmd::{discard, full_user_area_logical_erase};
+
+    let status: SDStatus = SD_CARDS[0].sd_status.into();
+    assert!(discard(&status, 0, 7).is_err());
+
+    let mut words = SD_CARDS[0].sd_status;
+    words[9] |= 0x0200_0000;
+    let status: SDStatus = words.into();
+    let seq = discard(&status, 0, 7).unwrap();
+    assert_eq!((seq.start.cmd, seq.end.cmd, seq.end.arg), (32, 33, 7));
+    assert_eq!((seq.erase.cmd, seq.erase.arg), (38, 1));
+    assert_eq!(
+        full_user_area_logical_erase(&status).err(),
+        Some(UnsupportedOperation)
+    );
+}
+
+/// EXT_CSD with the given bytes set
+fn ext_csd(bytes: &[(usize, u8)]) -> sdio_host::emmc::ExtCSD {
+    let mut block = [0u8; 512];
+    for &(index, value) in bytes {
+        block[index] = value;
+    }
+    block.into()
+}
+
+#[test]
+fn test_ext_csd_bytes() {
+    let ext_csd = ext_csd(&[(185, 2), (192, 8), (504, 1)]);
+    assert_eq!(ext_csd.as_words()[46], 0x0002_0000);
+    assert_eq!(ext_csd.extended_csd_revision(), 8);
+    assert_eq!(ext_csd.hs_timing(), Some(sdio_host::emmc::HsTiming::HS200));
+    let bytes = ext_csd.to_bytes();
+    assert_eq!((bytes[185], bytes[192], bytes[504]), (2, 8, 1));
+    let roundtrip = sdio_host::emmc::ExtCSD::from(bytes);
+    assert_eq!(roundtrip.as_words()[..], ext_csd.as_words()[..]);
+}
+
+#[test]
+fn test_ext_csd_capacity() {
+    // 8GB device: SEC_COUNT 0x00E9_0000
+    let ext_csd = ext_csd(&[(212, 0x00), (213, 0x00), (214, 0xE9), (215, 0x00)]);
+    assert_eq!(ext_csd.sector_count(), 0x00E9_0000);
+    assert_eq!(ext_csd.device_capacity_bytes(), 0x00E9_0000 * 512);
+    assert_eq!(
+        sdio_host::emmc::Geometry::emmc(&ext_csd).block_count,
+        0x00E9_0000
+    );
+}
+
+#[test]
+fn test_ext_csd_optimal_sizes() {
+    let sizes = ext_csd(&[(264, 1), (265, 2), (266, 3), (495, 7)]);
+    assert_eq!(sizes.optimal_trim_unit_size_bytes(), Some(4096));
+    assert_eq!(sizes.optimal_write_size_bytes(), 8192);
+    assert_eq!(sizes.optimal_read_size_bytes(), 12288);
+    assert_eq!(sizes.large_unit_size_bytes(), 8 << 20);
+
+    let trim = |n| ext_csd(&[(264, n)]).optimal_trim_unit_size_bytes();
+    assert_eq!(trim(0), None);
+    assert_eq!(trim(21), Some(4 << 30));
+    assert_eq!(trim(52), Some(1 << 63));
+    assert_eq!(trim(53), None);
+    assert_eq!(trim(0xFF), None);
+}
+
+#[test]
+fn test_ext_csd_health() {
+    use sdio_host::emmc::{LifeTimeEstimate, PreEolInfo};
+
+    let aged = ext_csd(&[(267, 0x02), (268, 0x01), (269, 0x0A)]);
+    assert_eq!(aged.pre_eol_info(), PreEolInfo::Warning);
+    assert_eq!(aged.life_time_est_a(), LifeTimeEstimate::Used(10));
+    assert_eq!(aged.life_time_est_b(), LifeTimeEstimate::Used(100));
+
+    let worn = ext_csd(&[(267, 0x04), (268, 0x0B), (269, 0x0C)]);
+    assert_eq!(worn.pre_eol_info(), PreEolInfo::Reserved(4));
+    assert_eq!(worn.life_time_est_a(), LifeTimeEstimate::Exceeded);
+    assert_eq!(worn.life_time_est_b(), LifeTimeEstimate::Reserved(0x0C));
+    assert_eq!(
+        sdio_host::emmc::ExtCSD::default().life_time_est_a(),
+        LifeTimeEstimate::NotDefined
+    );
+}
+
+#[test]
+fn test_ext_csd_bkops() {
+    use sdio_host::emmc::BkopsStatus;
+
+    let status = |value| ext_csd(&[(246, value)]).bkops_status();
+    assert_eq!(status(0), BkopsStatus::NotRequired);
+    assert_eq!(status(1), BkopsStatus::Outstanding);
+    assert_eq!(status(2), BkopsStatus::PerformanceImpacted);
+    assert_eq!(status(3), BkopsStatus::Critical);
+    // Reserved bits [7:2] are ignored
+    assert_eq!(status(0xFC), BkopsStatus::NotRequired);
+
+    let bkops = ext_csd(&[(163, 0x02), (502, 0x01)]);
+    assert!(bkops.bkops_support() && bkops.bkops_auto_enabled());
+    assert!(!bkops.bkops_manual_enabled());
+
+    // 512KiB erase groups, 16 erase groups per write protect group
+    let groups = ext_csd(&[(58, 3), (221, 0x10), (224, 0x01)]);
+    assert_eq!(groups.wp_group_blocks(), 16 * 1024);
+    assert_eq!(groups.dyncap_release_blocks(), 3 * 16 * 1024);
+    assert_eq!(ext_csd(&[(221, 0x10)]).wp_group_blocks(), 0);
+    assert_eq!(
+        ext_csd(&[(221, 0xFF), (224, 0xFF)]).wp_group_blocks(),
+        255 * 255 * 1024
+    );
+}
+
+#[test]
+fn test_ext_csd_boot_config() {
+    use sdio_host::emmc::{BootMode, BootPartition, PartitionAccess, PartitionConfig};
+    use sdio_host::emmc_cmd::set_partition_config;
+
+    let boot = ext_csd(&[(177, 0x0E), (178, 0x01), (179, 0x48), (226, 32)]);
+    assert_eq!(boot.boot_partition_bytes(), 4 * 1024 * 1024);
+    let config = boot.partition_config();
+    assert!(config.boot_ack());
+    assert_eq!(config.boot_partition(), Some(BootPartition::Boot1));
+    assert_eq!(config.partition_access(), PartitionAccess::User);
+    let conditions = boot.boot_bus_conditions();
+    assert_eq!(conditions.boot_mode(), Some(BootMode::SdrHighSpeed));
+    assert!(conditions.retain_after_boot());
+    assert_eq!(conditions.bus_width(), BusWidth::Eight);
+    assert!(boot.boot_config_pwr_protected());
+    assert!(!boot.boot_config_perm_protected());
+
+    let rpmb = PartitionConfig::new(true, BootPartition::Boot1, PartitionAccess::Rpmb).unwrap();
+    assert_eq!(rpmb, PartitionConfig(0x4B));
+    let gp4 =
+        PartitionConfig::new(false, BootPartition::User, PartitionAccess::General(4)).unwrap();
+    assert_eq!(gp4.partition_access(), PartitionAccess::General(4));
+    assert_eq!(gp4.boot_partition(), Some(BootPartition::User));
+    assert!(
+        PartitionConfig::new(false, BootPartition::User, PartitionAccess::General(5)).is_none()
+    );
+    assert_eq!(set_partition_config(rpmb).cmd().arg, 0x03B3_4B00);
+}
+
+#[test]
+fn test_ext_csd_cache() {
+    use sdio_host::emmc_cmd::{cache_barrier, flush_cache, set_cache};
+
+    let cached = ext_csd(&[(33, 1), (240, 1), (249, 0x00), (250, 0x10), (486, 1)]);
+    assert_eq!(cached.cache_size_kib(), 4096);
+    assert_eq!(cached.cache_size_bytes(), 4 * 1024 * 1024);
+    assert!(cached.cache_enabled());
+    assert!(cached.cache_flush_fifo());
+    assert!(cached.barrier_support());
+    assert!(!cached.barrier_enabled());
+
+    assert_eq!(set_cache(true).cmd().arg, 0x0321_0100);
+    assert_eq!(flush_cache().cmd().arg, 0x0320_0100);
+    assert_eq!(cache_barrier().cmd().arg, 0x0320_0200);
+}
+
+#[test]
+fn test_ext_csd_rpmb() {
+    let legacy = ext_csd(&[(166, 0x01), (168, 4), (222, 1)]);
+    assert_eq!(legacy.rpmb_partition_bytes(), 512 * 1024);
+    assert!(legacy.wr_rel_set_supported());
+    assert!(!legacy.enhanced_reliable_write());
+    assert!(!legacy.rpmb_reliable_write_8k());
+    assert_eq!(legacy.reliable_write_sectors(), 1);
+
+    let enhanced = ext_csd(&[(166, 0x15), (168, 32), (222, 8)]);
+    assert_eq!(enhanced.rpmb_size_mult(), 32);
+    assert_eq!(enhanced.rel_wr_sec_c(), 8);
+    assert!(enhanced.enhanced_reliable_write());
+    assert!(enhanced.rpmb_reliable_write_8k());
+    assert_eq!(enhanced.reliable_write_sectors(), 1);
+    let sectors = ext_csd(&[(222, 8)]);
+    assert_eq!(sectors.reliable_write_sectors(), 8);
+}
+
+#[test]
+fn test_ext_csd_device_timings() {
+    use sdio_host::emmc::{DeviceTimings, HsTiming};
+
+    let hs200 = ext_csd(&[(196, 0x17)]).device_timings();
+    assert!(hs200.hs52() && hs200.ddr52() && hs200.hs200() && !hs200.hs400());
+    assert_eq!(hs200.fastest(), HsTiming::HS200);
+    assert_eq!(hs200.max_clock_hz(HsTiming::HighSpeed), Some(52_000_000));
+    assert_eq!(hs200.max_clock_hz(HsTiming::HS200), Some(200_000_000));
+    assert_eq!(hs200.max_clock_hz(HsTiming::HS400), None);
+
+    let hs26 = DeviceTimings(DeviceTimings::HS26);
+    assert_eq!(hs26.fastest(), HsTiming::HighSpeed);
+    assert_eq!(hs26.max_clock_hz(HsTiming::HighSpeed), Some(26_000_000));
+    assert!(DeviceTimings(DeviceTimings::HS400_1V2).supports(HsTiming::HS400));
+    assert_eq!(DeviceTimings(0).fastest(), HsTiming::Legacy);
+}
+
+#[test]
+fn test_ext_csd_power_class() {
+    use sdio_host::emmc::{DataPhase, HsTiming, PowerClassVoltage};
+    use sdio_host::emmc_cmd::set_power_class;
+    use HsTiming::*;
+    use PowerClassVoltage::*;
+
+    let classes = ext_csd(&[
+        (187, 0x02),
+        (200, 0x21),
+        (201, 0x43),
+        (202, 0x65),
+        (203, 0x87),
+        (236, 0xA9),
+        (237, 0xCB),
+        (238, 0xED),
+        (239, 0x1F),
+        (253, 0x30),
+    ]);
+    assert_eq!(classes.power_class(), 2);
+    assert_eq!(classes.pwr_cl_200_195(), 0xA9);
+    assert_eq!(classes.pwr_cl_200_360(), 0xCB);
+
+    let one = DataPhase::sdr(BusWidth::One);
+    let four = DataPhase::sdr(BusWidth::Four);
+    let eight = DataPhase::sdr(BusWidth::Eight);
+    let ddr4 = DataPhase::ddr(BusWidth::Four);
+    let ddr8 = DataPhase::ddr(BusWidth::Eight);
+    let cases = [
+        // timing, phase, VCC, VCCQ, class
+        (Legacy, four, V1_8, V1_8, Some(0x3)),
+        (Legacy, eight, V1_8, V1_8, Some(0x4)),
+        (Legacy, four, V3_3, V3_3, Some(0x7)),
+        (Legacy, eight, V3_3, V3_3, Some(0x8)),
+        (HighSpeed, four, V1_8, V1_8, Some(0x1)),
+        (HighSpeed, eight, V1_8, V1_8, Some(0x2)),
+        (HighSpeed, four, V3_3, V3_3, Some(0x5)),
+        (HighSpeed, eight, V3_3, V1_8, Some(0x6)),
+        (HighSpeed, ddr4, V1_8, V1_8, Some(0xD)),
+        (HighSpeed, ddr8, V1_8, V1_8, Some(0xE)),
+        (HighSpeed, ddr4, V3_3, V3_3, Some(0xF)),
+        (HighSpeed, ddr8, V3_3, V1_8, Some(0x1)),
+        (HS200, four, V1_8, V1_8, Some(0x9)),
+        (HS200, eight, V1_8, V1_2, Some(0xA)),
+        (HS200, four, V3_3, V1_8, Some(0xB)),
+        (HS200, eight, V3_3, V1_2, Some(0xC)),
+        (HS400, ddr8, V1_8, V1_8, Some(0xA)),
+        (HS400, ddr8, V3_3, V1_8, Some(0x3)),
+        (HS400, ddr8, V3_3, V1_2, Some(0x3)),
+        // HS200 and HS400 need a 1.8V or 1.2V VCCQ
+        (HS200, eight, V3_3, V3_3, None),
+        (HS400, ddr8, V3_3, V3_3, None),
+        // HS400 is 8 bit only, DDR needs high speed timing
+        (HS400, ddr4, V3_3, V1_8, None),
+        (Legacy, ddr8, V3_3, V3_3, None),
+        // There is no VCC of 1.2V
+        (HighSpeed, eight, V1_2, V1_2, None),
+        // 1 bit buses always use class 0
+        (HS200, one, V3_3, V3_3, Some(0)),
+    ];
+    for &(timing, phase, vcc, vccq, class) in cases.iter() {
+        assert_eq!(
+            classes.power_class_for(timing, phase, vcc, vccq),
+            class,
+            "{:?} {:?} VCC {:?} VCCQ {:?}",
+            timing,
+            phase,
+            vcc,
+            vccq
+        );
+    }
+    assert_eq!(set_power_class(6).cmd().arg, 0x03BB_0600);
+}
+
+#[test]
+fn test_ext_csd_erase_timeout() {
+    use sdio_host::emmc_cmd::EraseFunction;
+
+    // ERASE_TIMEOUT_MULT, HC_ERASE_GRP_SIZE, SEC_TRIM_MULT, SEC_ERASE_MULT, TRIM_MULT
+    let ext_csd = ext_csd(&[(223, 2), (224, 1), (229, 3), (230, 4), (232, 5)]);
+
+    assert_eq!(ext_csd.erase_group_blocks(), 1024);
+    assert_eq!(ext_csd.erase_timeout_ms(EraseFunction::Erase, 10), 6_000);
+    assert_eq!(ext_csd.erase_timeout_ms(EraseFunction::Trim, 1), 1_500);
+    assert_eq!(
+        ext_csd.erase_timeout_ms(EraseFunction::SecureErase, 1),
+        2_400
+    );
+    assert_eq!(
+        ext_csd.erase_timeout_ms(EraseFunction::SecureTrimStep1, 1),
+        1_800
+    );
+}
+
+#[test]
+fn test_switch_verify() {
+    use sdio_host::common_cmd::SwitchError;
+    use sdio_host::sd::SwitchStatus;
+    use sdio_host::sd_cmd::{switch_function, SwitchMode};
+
+    // High speed in group 1, keep the other groups
+    let switch = switch_function(SwitchMode::Switch, [1, 0xF, 0xF, 0xF, 0xF, 0xF]);
+    assert_eq!(switch.cmd().arg, 0x80FF_FFF1);
+
+    // Group 1 result at [379:376]
+    let mut words = [0u32; 16];
+    words[11] = 0xF1FF_FFFF;
+    assert_eq!(switch.verify(&SwitchStatus::from(words)), Ok(()));
+    words[11] = 0xFFFF_FFFF;
+    assert_eq!(
+        switch.verify(&SwitchStatus::from(words)),
+        Err(SwitchError::FunctionGroup(1))
+    );
+}
+
+#[test]
+fn test_emmc_switch() {
+    use sdio_host::common_cmd::SwitchError;
+    use sdio_host::emmc::{CardStatus, EMMC};
+    use sdio_host::emmc_cmd::{modify_ext_csd, switch, AccessMode};
+
+    // Clear bit 0 of BKOPS_EN
+    let clear = switch(AccessMode::ClearBits, 163, 0x01);
+    assert_eq!(
+        clear.cmd(),
+        modify_ext_csd(AccessMode::ClearBits, 163, 0x01)
+    );
+    assert_eq!(clear.cmd().arg, 0x02A3_0100);
+    assert_eq!(clear.status_cmd(0x1234).arg, 0x1234_0000);
+    assert_eq!(clear.verify(&CardStatus::<EMMC>::from(0x900)), Ok(()));
+    assert_eq!(
+        clear.verify(&CardStatus::<EMMC>::from(0x980)),
+        Err(SwitchError::Rejected)
+    );
+}
+
+#[test]
+fn test_set_hs_timing() {
+    use sdio_host::emmc::{DriverStrength, HsTiming};
+    use sdio_host::emmc_cmd::set_hs_timing;
+
+    // HS_TIMING [185]: driver strength in [7:4], timing interface in [3:0]
+    for &(timing, strength, value) in &[
+        (HsTiming::Legacy, DriverStrength::Type0, 0x00),
+        (HsTiming::HighSpeed, DriverStrength::Type0, 0x01),
+        (HsTiming::HS200, DriverStrength::Type1, 0x12),
+        (HsTiming::HS200, DriverStrength::Type2, 0x22),
+        (HsTiming::HS400, DriverStrength::Type3, 0x33),
+        (HsTiming::HS400, DriverStrength::Type4, 0x43),
+    ] {
+        let switch = set_hs_timing(timing, strength);
+        assert_eq!(switch.cmd().cmd, 6);
+        assert_eq!(switch.cmd().arg, 0x03B9_0000 | u32::from(value) << 8);
+        assert_eq!(ext_csd(&[(185, value)]).hs_timing(), Some(timing));
+    }
+
+    // DRIVER_STRENGTH [197]: Type 0, 1 and 4
+    let registers = ext_csd(&[(197, 0x13)]);
+    assert!(registers.supports_driver_strength(DriverStrength::Type0));
+    assert!(registers.supports_driver_strength(DriverStrength::Type1));
+    assert!(!registers.supports_driver_strength(DriverStrength::Type2));
+    assert!(!registers.supports_driver_strength(DriverStrength::Type3));
+    assert!(registers.supports_driver_strength(DriverStrength::Type4));
+}
+
+#[test]
+fn test_block_count_arg() {
+    use sdio_host::emmc_cmd::{set_block_count_with, BlockCountArg};
+
+    let arg = |arg: BlockCountArg| set_block_count_with(arg).arg;
+    let count = BlockCountArg {
+        count: 0xFFFF,
+        ..Default::default()
+    };
+    assert_eq!(set_block_count_with(count).cmd, 23);
+    assert_eq!(arg(count), 0x0000_FFFF);
+    let reliable = BlockCountArg {
+        count: 8,
+        reliable_write: true,
+        ..Default::default()
+    };
+    assert_eq!(arg(reliable), 0x8000_0008);
+    let packed = BlockCountArg {
+        count: 3,
+        packed: true,
+        ..Default::default()
+    };
+    assert_eq!(arg(packed), 0x4000_0003);
+    let tagged = BlockCountArg {
+        count: 1,
+        tag_request: true,
+        context_id: 0x1F,
+        forced_programming: true,
+        ..Default::default()
+    };
+    // Context ID is masked to [28:25]
+    assert_eq!(arg(tagged), 0x3F00_0001);
+    let all = BlockCountArg {
+        count: 0x1234,
+        reliable_write: true,
+        packed: true,
+        tag_request: true,
+        context_id: 0x5,
+        forced_programming: true,
+    };
+    assert_eq!(arg(all), 0xEB00_1234);
+
+    let tags = ext_csd(&[(499, 0x01), (498, 3), (497, 0x12)]);
+    assert!(tags.data_tag_support());
+    assert_eq!((tags.tag_unit_size(), tags.tag_resources_size()), (3, 0x12));
+    assert!(!ext_csd(&[(497, 0x01)]).data_tag_support());
+}
+
+#[test]
+fn test_exception_events() {
+    use sdio_host::emmc::ExceptionEvents;
+    use sdio_host::emmc_cmd::set_exception_events_ctrl;
+
+    // URGENT_BKOPS and SYSPOOL_EXHAUSTED in EXCEPTION_EVENTS_STATUS, with
+    // a reserved bit in the upper byte
+    let registers = ext_csd(&[(54, 0x05), (55, 0x80), (56, 0x1A)]);
+    let status = registers.exception_events_status();
+    assert_eq!(status, ExceptionEvents(0x8005));
+    assert!(status.urgent_bkops() && status.syspool_exhausted());
+    assert!(!status.dyncap_needed() && !status.packed_failure());
+    assert!(!status.extended_security_failure());
+
+    let ctrl = registers.exception_events_ctrl();
+    assert!(ctrl.dyncap_needed() && ctrl.packed_failure() && ctrl.extended_security_failure());
+    assert!(!ctrl.urgent_bkops() && !ctrl.syspool_exhausted());
+    assert!(format!("{:?}", ctrl).contains("Packed Failure: true"));
+
+    // Enable: URGENT_BKOPS can't be controlled and is left out
+    let set = set_exception_events_ctrl(ExceptionEvents(
+        ExceptionEvents::URGENT_BKOPS
+            | ExceptionEvents::DYNCAP_NEEDED
+            | ExceptionEvents::PACKED_FAILURE,
+    ));
+    assert_eq!((set.cmd().cmd, set.cmd().arg), (6, 0x0338_0A00));
+    // Disable all
+    let clear = set_exception_events_ctrl(ExceptionEvents::default());
+    assert_eq!(clear.cmd().arg, 0x0338_0000);
+}
+
+/// Parsing must not panic for any register contents, including reserved
+/// and garbled values
+#[test]
+fn test_no_panic_on_arbitrary_registers() {
+    use sdio_host::emmc::{self, EMMC};
+    use sdio_host::sd::{CardStatus, SwitchStatus, CIC};
+
+    for &word in &[0, !0, 0xA5A5_A5A5, 0x5A5A_5A5A, 0x8000_0001] {
+        let words = [word; 4];
+        let _ = format!("{:?}", CID::<SD>::from(words));
+        let _ = format!("{:?}", CID::<EMMC>::from(words));
+        let csd = CSD::<SD>::from(words);
+        let _ = format!("{:?} {} {}", csd, csd.card_size(), csd.erase_size_blocks());
+        let _ = csd.check_range(BlockAddress(word), word);
+        let _ = format!("{:?}", CSD::<EMMC>::from(words));
+        let _ = format!("{:?}", OCR::<SD>::from(word));
+        let _ = format!("{:?}", OCR::<EMMC>::from(word));
+        let _ = format!("{:?}", CardStatus::<SD>::from(word));
+        let _ = format!("{:?}", CardStatus::<EMMC>::from(word));
+        let _ = format!("{:?}", CIC::from(word));
+        let _ = format!("{:?}", SCR::from([word; 2]));
+
+        let status = SDStatus::from([word; 16]);
+        let _ = format!("{:?} {:?}", status, status.write_pacing());
+        let switch = SwitchStatus::from([word; 16]);
+        for group in 0..=7 {
+            let _ = (switch.supported(group), switch.function(group));
+        }
+        let _ = format!("{:?}", switch);
+
+        let ext_csd = emmc::ExtCSD::from([word; 128]);
+        let _ = format!("{:?}", ext_csd);
+        let _ = (
+            ext_csd.dyncap_release_blocks(),
+            ext_csd.large_unit_size_bytes(),
+            ext_csd.optimal_trim_unit_size_bytes(),
+            ext_csd.erase_timeout_ms(sdio_host::emmc_cmd::EraseFunction::SecureErase, !0),
+        );
+    }
+}
+
+#[test]
+fn test_emmc_ocr() {
+    use sdio_host::common_cmd::{OpCondPoll, OpCondState};
+    use sdio_host::emmc::EMMC;
+
+    let ocr = OCR::<EMMC>::from(0xC0FF_8080);
+    assert!(!ocr.is_busy());
+    assert!(ocr.is_high_voltage_card());
+    assert!(ocr.is_dual_voltage_card());
+    assert_eq!(ocr.access_mode(), 0b10);
+    assert_eq!(OCR::<EMMC>::from(0x80FF_8000).access_mode(), 0b00);
+
+    let busy = OCR::<EMMC>::from(0x00FF_8080);
+    let mut poll = OpCondPoll::new(3);
+    assert_eq!(poll.poll(&busy, 0), OpCondState::KeepPolling);
+    assert_eq!(poll.poll(&busy, 0), OpCondState::KeepPolling);
+    assert_eq!(poll.poll(&busy, 0), OpCondState::TimedOut);
+    assert_eq!(poll.attempts(), 3);
+    let mut poll = OpCondPoll::new(100).timeout_ms(1000);
+    assert_eq!(poll.poll(&busy, 999), OpCondState::KeepPolling);
+    assert_eq!(poll.poll(&busy, 1000), OpCondState::TimedOut);
+    assert_eq!(poll.poll(&ocr, 1000), OpCondState::Ready);
+}
+
+#[test]
+fn test_emmc_data_phase() {
+    use sdio_host::emmc::DataPhase;
+    use sdio_host::emmc_cmd::set_bus_width;
+
+    let ddr52 = DataPhase::ddr(BusWidth::Eight);
+    assert_eq!(set_bus_width(ddr52).unwrap().cmd().arg, 0x03B7_0600);
+    assert_eq!(ddr52.crc_bytes_per_block(), 32);
+    assert_eq!(DataPhase::sdr(BusWidth::Four).crc_bytes_per_block(), 8);
+    assert!(set_bus_width(DataPhase::ddr(BusWidth::One)).is_err());
+
+    let ext_csd = ext_csd(&[(183, 0x86)]);
+    let hs400 = ext_csd.data_phase().unwrap();
+    assert!(hs400.ddr && hs400.enhanced_strobe);
+    assert_eq!(hs400.bus_width, BusWidth::Eight);
+    assert_eq!(DataPhase::from_bus_width(3), None);
+}
+
+#[test]
+fn test_emmc_irq_mode() {
+    use sdio_host::emmc_cmd::{IrqMode, IrqStep};
+
+    let mut irq = IrqMode::new();
+    assert_eq!(irq.abort(), IrqStep::Deselect);
+    assert_eq!(irq.deselect_cmd().arg, 0);
+    assert_eq!(irq.deselected(), IrqStep::Wait);
+    assert_eq!(irq.irq_cmd().cmd, 40);
+    assert_eq!(irq.abort(), IrqStep::Abort(0));
+    // Our own response
+    assert_eq!(irq.response(0), IrqStep::Aborted);
+
+    // A device wins the arbitration against the abort
+    let mut irq = IrqMode::new();
+    irq.deselected();
+    irq.abort();
+    match irq.response(0x0003_00AA) {
+        IrqStep::Interrupt(response) => {
+            assert_eq!(response.rca(), 3);
+            assert_eq!(response.data(), 0xAA);
+        }
+        step => panic!("{:?}", step),
+    }
+}
+
+#[test]
+fn test_emmc_cid() {
+    use sdio_host::emmc::{DeviceType, EMMC};
+
+    // MID 0x15, CBX 0b01, OID 0x01, PNM "8GTF4R", PRV 0.7, MDT 9/2
+    let cid = CID::<EMMC>::from(0x1501_0138_4754_4634_5207_1234_5678_9200);
+    assert_eq!(cid.manufacturer_id(), 0x15);
+    assert_eq!(cid.device_type(), DeviceType::BGA);
+    assert_eq!(cid.product_name(), "8GTF4R");
+    assert_eq!(cid.product_revision(), (0, 7));
+    assert_eq!(cid.serial(), 0x1234_5678);
+    assert_eq!(cid.manufacturing_date(), (9, 2));
+    assert_eq!(cid.manufacturing_year(8), 2015);
+    assert_eq!(cid.manufacturing_year(3), 1999);
+    let year = |code: u128| CID::<EMMC>::from(code << 8).manufacturing_year(8);
+    assert_eq!(year(12), 2025);
+    assert_eq!(year(13), 2010);
+    assert_eq!(year(15), 2012);
+
+    let reserved = CID::<EMMC>::from(0x1503_0000_0000_0000_0000_0000_0000_0000);
+    assert_eq!(reserved.device_type(), DeviceType::Unknown);
+}
+
+#[test]
+fn test_cid_filter() {
+    use sdio_host::emmc::EMMC;
+    use sdio_host::sd::{CardIdentity, CidFilter, CidRule};
+
+    static RULES: &[CidRule<'static>] = &[
+        CidRule {
+            manufacturer_id: Some(0x03),
+            oem_id: Some(0x5344), // "SD"
+            product_prefix: "SE",
+            min_revision: 0x80,
+        },
+        CidRule {
+            manufacturer_id: Some(0x15),
+            oem_id: None,
+            product_prefix: "8GTF",
+            min_revision: 0x07,
+        },
+    ];
+    let sandisk_extreme = CID::<SD>::from([0xc000e344, 0x80f1086b, 0x45333247, 0x03534453]);
+    let sandisk = CID::<SD>::from([2197869198, 2149469225, 1429223495, 55788627]);
+    let emmc = CID::<EMMC>::from(0x1501_0138_4754_4634_5207_1234_5678_9200);
+    assert_eq!(
+        sandisk_extreme.identity(),
+        CardIdentity {
+            manufacturer_id: 3,
+            oem_id: 0x5344,
+            product_name: "SE32G",
+            revision: 0x80,
+        }
+    );
+
+    let allow = CidFilter::allow(RULES);
+    assert!(allow.permits(&sandisk_extreme.identity()));
+    assert!(!allow.permits(&sandisk.identity()));
+    assert!(allow.permits(&emmc.identity()));
+    let deny = CidFilter::deny(RULES);
+    assert!(!deny.permits(&sandisk_extreme.identity()));
+    assert!(deny.permits(&sandisk.identity()));
+
+    // Revision too old
+    let old = CID::<EMMC>::from(0x1501_0138_4754_4634_5206_1234_5678_9200);
+    assert!(!allow.permits(&old.identity()));
+}
+
+#[test]
+fn test_card_version() {
+    use sdio_host::emmc::{CardVersion, EmmcVersion};
+
+    assert_eq!(ext_csd(&[(192, 8)]).version(), EmmcVersion::V5_1);
+    assert_eq!(ext_csd(&[(192, 4)]).version(), EmmcVersion::Unknown);
+    assert_eq!(
+        CardVersion::from(ext_csd(&[(192, 5)]).version()),
+        CardVersion::Emmc(EmmcVersion::V4_41)
+    );
+    assert_eq!(
+        CardVersion::from(SCR::from([0, 0x0285_8000]).version()),
+        CardVersion::Sd(SDSpecVersion::V3)
+    );
+}
+
+#[test]
+fn test_capability_guard() {
+    use sdio_host::common_cmd::read_single_block;
+    use sdio_host::sd::{CapabilityError, CapabilityGuard};
+    use sdio_host::sd_cmd::{set_block_count, set_bus_width, speed_class_control};
+
+    for card in SD_CARDS {
+        let csd: CSD<SD> = card.csd.into();
+        let scr: SCR = card.scr.into();
+        let guard = CapabilityGuard::new(&csd, &scr);
+
+        assert_eq!(guard.check(&read_single_block(0)), Ok(()));
+        assert_eq!(guard.check_app(&set_bus_width(true)), Ok(()));
+        assert_eq!(
+            guard.check(&speed_class_control(0)).is_ok(),
+            scr.cmd20_support()
+        );
+        assert_eq!(
+            guard.check(&set_block_count(8)),
+            Err(CapabilityError::Command(23))
+        );
+
+        // 1-bit only card
+        let guard = CapabilityGuard::new(&csd, &SCR::from([0, card.scr[1] & !0x0004_0000]));
+        assert_eq!(
+            guard.check_app(&set_bus_width(true)),
+            Err(CapabilityError::BusWidth)
+        );
+        assert_eq!(guard.check_app(&set_bus_width(false)), Ok(()));
+    }
+
+    // No erase command class
+    let guard = CapabilityGuard::new(&CSD::from(0u128), &SCR::from([0, 0]));
+    assert_eq!(
+        guard.check(&sdio_host::sd_cmd::erase_wr_blk_start_addr(0)),
+        Err(CapabilityError::CommandClass(5))
+    );
+}
+
+#[test]
+fn test_read_planner() {
+    use sdio_host::common_cmd::{DataCmdBuilder, ReadCmds, ReadPlanner, ReadRun};
+    use sdio_host::sd::CardCapacity;
+
+    let mut planner = ReadPlanner::new(4);
+    let mut runs: Vec<ReadRun> = (10..16)
+        .filter_map(|block| planner.push(BlockAddress(block)))
+        .collect();
+    runs.extend(planner.finish());
+    assert_eq!(
+        runs,
+        [
+            ReadRun {
+                start: BlockAddress(10),
+                count: 4
+            },
+            ReadRun {
+                start: BlockAddress(14),
+                count: 2
+            }
+        ]
+    );
+
+    let builder = DataCmdBuilder::new(CardCapacity::HighCapacity, 1024).with_set_block_count(true);
+    match builder.read_run(runs[0]).unwrap() {
+        ReadCmds::Multiple {
+            set_block_count,
+            read,
+        } => {
+            assert_eq!((set_block_count.cmd, set_block_count.arg), (23, 4));
+            assert_eq!((read.cmd, read.arg), (18, 10));
+        }
+        cmds => panic!("{:?}", cmds),
+    }
+    // Without CMD23 the read is stopped with CMD12
+    let open_ended = DataCmdBuilder::new(CardCapacity::HighCapacity, 1024);
+    match open_ended.read_run(runs[0]).unwrap() {
+        ReadCmds::OpenEnded { read, stop } => {
+            assert_eq!((read.cmd, read.arg), (18, 10));
+            assert_eq!((stop.cmd, stop.arg), (12, 0));
+        }
+        cmds => panic!("{:?}", cmds),
+    }
+    let single = ReadRun {
+        start: BlockAddress(1023),
+        count: 1,
+    };
+    assert_eq!(
+        builder.read_run(single),
+        Ok(ReadCmds::Single(sdio_host::common_cmd::read_single_block(
+            1023
+        )))
+    );
+    assert_eq!(
+        builder.read_run(ReadRun {
+            start: BlockAddress(1023),
+            count: 2
+        }),
+        Err(OutOfRange)
+    );
+}
+
+#[test]
+fn test_transfer() {
+    use sdio_host::common_cmd::{
+        read_multiple_blocks, stop_transmission, write_multiple_blocks, write_single_block,
+        DataCmdBuilder, ReadRun, TransferEvent, TransferStep, TransientError,
+    };
+    use sdio_host::sd::{CardCapacity, CardStatus, StatusError};
+    use sdio_host::Error;
+
+    let transfer_state = TransferEvent::CommandComplete(CardStatus::<SD>::from(4 << 9));
+    let data = TransferEvent::<SD>::DataComplete;
+    let builder = DataCmdBuilder::new(CardCapacity::HighCapacity, 1024).with_set_block_count(true);
+
+    // CMD23, CMD18 and the data phase
+    let run = ReadRun {
+        start: BlockAddress(16),
+        count: 8,
+    };
+    let mut read = builder.read_transfer(run).unwrap();
+    assert_eq!(read.count(), 8);
+    assert_eq!(
+        read.step(),
+        TransferStep::Send(sdio_host::sd_cmd::set_block_count(8))
+    );
+    assert_eq!(
+        read.advance(transfer_state),
+        TransferStep::Send(read_multiple_blocks(16))
+    );
+    assert_eq!(read.advance(transfer_state), TransferStep::Data);
+    // Events that don't end the step are ignored
+    assert_eq!(read.advance(transfer_state), TransferStep::Data);
+    assert_eq!(read.advance(data), TransferStep::Done);
+    assert_eq!(read.advance(data), TransferStep::Done);
+
+    // A failed multiple block write is stopped with CMD12
+    let mut write = builder.write_transfer(BlockAddress(4), 2).unwrap();
+    write.advance(transfer_state);
+    assert_eq!(write.advance(transfer_state), TransferStep::Data);
+    let crc = TransferEvent::<SD>::Error(TransientError::Crc);
+    assert_eq!(write.advance(crc), TransferStep::Send(stop_transmission()));
+    assert_eq!(
+        write.advance(transfer_state),
+        TransferStep::Failed(Error::Crc)
+    );
+
+    // A single block write rejected by the card
+    let mut write = builder.write_transfer(BlockAddress(4), 1).unwrap();
+    assert_eq!(write.step(), TransferStep::Send(write_single_block(4)));
+    let rejected = TransferEvent::CommandComplete(CardStatus::<SD>::from(0x400_0900));
+    match write.advance(rejected) {
+        TransferStep::Failed(Error::Command(e)) => {
+            assert_eq!((e.cmd, e.error), (24, StatusError::WpViolation))
+        }
+        step => panic!("{:?}", step),
+    }
+
+    // Without CMD23 a multiple block write is ended with CMD12
+    let open_ended = DataCmdBuilder::new(CardCapacity::HighCapacity, 1024);
+    let mut write = open_ended.write_transfer(BlockAddress(4), 2).unwrap();
+    assert_eq!(write.step(), TransferStep::Send(write_multiple_blocks(4)));
+    assert_eq!(write.advance(transfer_state), TransferStep::Data);
+    assert_eq!(write.advance(data), TransferStep::Send(stop_transmission()));
+    let timeout = TransferEvent::<SD>::Error(TransientError::Timeout);
+    assert_eq!(write.advance(timeout), TransferStep::Failed(Error::Timeout));
+
+    assert!(open_ended.write_transfer(BlockAddress(1023), 2).is_err());
+}
+
+#[test]
+fn test_tuning() {
+    use sdio_host::tuning::{Tuning, TuningError, MAX_TUNING_ITERATIONS};
+
+    // More phases than iterations: every second phase is tested
+    let mut tuning = Tuning::emmc(64);
+    let mut iterations = 0;
+    while let Some((phase, cmd)) = tuning.next() {
+        assert_eq!(cmd.cmd, 21);
+        assert_eq!(phase % 2, 0);
+        tuning.record((2..=8).contains(&phase) || (20..=40).contains(&phase));
+        iterations += 1;
+    }
+    assert!(iterations <= MAX_TUNING_ITERATIONS);
+    assert_eq!(tuning.result(), Ok(30));
+
+    let mut tuning = Tuning::sd(8);
+    assert_eq!(tuning.result(), Err(TuningError::Incomplete));
+    while tuning.next().is_some() {
+        tuning.record(false);
+    }
+    assert_eq!(tuning.result(), Err(TuningError::NoPassingPhase));
+}
+
+#[test]
+fn test_spi_init() {
+    use sdio_host::spi::{go_idle, SpiCmd, SpiInit, SpiInitError, SpiInitStep, SpiR1, SpiResponse};
+
+    assert_eq!(
+        SpiCmd::new(&sdio_host::common_cmd::idle(), SpiResponse::R1),
+        go_idle()
+    );
+    let if_cond = SpiCmd::new(&sdio_host::sd_cmd::send_if_cond(1, 0xAA), SpiResponse::R7);
+    assert_eq!(if_cond.frame, [0x48, 0, 0, 0x01, 0xAA, 0x87]);
+    assert_eq!(if_cond.stuff_bytes(), 0);
+    assert_eq!(sdio_host::spi::stop_transmission().stuff_bytes(), 1);
+
+    // Version 1.x SDSC card: CMD8 is illegal, CMD16 sets the block length
+    let mut init = SpiInit::new(2);
+    let responses = [
+        (0x01, 0),
+        (0x05, 0),
+        (0x01, 0),
+        (0x01, 0),
+        (0x01, 0),
+        (0x00, 0),
+    ];
+    let mut sent = Vec::new();
+    for &(r1, payload) in responses.iter() {
+        match init.step() {
+            SpiInitStep::Send(cmd) => sent.push(cmd.index()),
+            step => panic!("{:?}", step),
+        }
+        init.update(SpiR1(r1), payload);
+    }
+    assert_eq!(sent, [0, 8, 55, 41, 55, 41]);
+    assert_eq!(
+        init.step(),
+        SpiInitStep::Send(SpiCmd::new(
+            &sdio_host::common_cmd::set_block_length(512),
+            SpiResponse::R1
+        ))
+    );
+    assert_eq!(
+        init.update(SpiR1(0), 0),
+        SpiInitStep::Done {
+            high_capacity: false
+        }
+    );
+
+    // Card stays idle
+    let mut init = SpiInit::new(2);
+    init.update(SpiR1(0x01), 0);
+    init.update(SpiR1(0x01), 0x1AA);
+    init.update(SpiR1(0x01), 0);
+    init.update(SpiR1(0x01), 0);
+    init.update(SpiR1(0x01), 0);
+    assert_eq!(
+        init.update(SpiR1(0x01), 0),
+        SpiInitStep::Failed(SpiInitError::Timeout)
+    );
+}
+
+#[test]
+fn test_spi_r2() {
+    use sdio_host::sd::StatusError;
+    use sdio_host::spi::SpiR2;
+
+    assert_eq!(SpiR2([0x00, 0x00]).check(), Ok(()));
+    assert_eq!(
+        SpiR2([0x04, 0x20]).check(),
+        Err(StatusError::IllegalCommand)
+    );
+    assert_eq!(SpiR2([0x00, 0x20]).check(), Err(StatusError::WpViolation));
+    let locked = SpiR2([0x00, 0x01]);
+    assert!(locked.card_is_locked());
+    assert_eq!(locked.check(), Err(StatusError::CardIsLocked));
+}
+
+#[test]
+fn test_spi_multi_block_write() {
+    use sdio_host::spi::{DataResponse, MultiBlockWrite, WriteFailure};
+
+    assert_eq!(DataResponse::from(0x05), DataResponse::Accepted);
+    assert_eq!(DataResponse::from(0xEB), DataResponse::CrcError);
+    assert_eq!(DataResponse::from(0x0D), DataResponse::WriteError);
+    assert_eq!(DataResponse::from(0xFF), DataResponse::Invalid(0xFF));
+
+    let mut write = MultiBlockWrite::new(3);
+    for _ in 0..3 {
+        assert_eq!(write.record(0xE5), Ok(()));
+    }
+    assert!(write.is_complete());
+    assert_eq!(write.failure(), None);
+
+    let mut write = MultiBlockWrite::new(8);
+    assert_eq!(write.record(0x05), Ok(()));
+    assert_eq!(write.record(0x05), Ok(()));
+    let failure = WriteFailure {
+        block: 2,
+        response: DataResponse::WriteError,
+    };
+    assert_eq!(write.record(0x0D), Err(failure));
+    assert_eq!(write.record(0x05), Err(failure));
+    assert_eq!((write.accepted(), write.is_complete()), (2, false));
+    // ACMD22: only one block was programmed
+    assert_eq!(write.resume_block(1), 1);
+    assert_eq!(write.resume_block(5), 2);
+    assert_eq!(sdio_host::sd_cmd::send_num_wr_blocks().cmd, 22);
+}
+
+#[test]
+fn test_partial_access() {
+    use sdio_host::sd_cmd::{PartialAccess, PartialError};
+    use sdio_host::Error;
+
+    // READ_BL_LEN 1024, READ_BL_PARTIAL and WRITE_BL_PARTIAL set
+    let csd = CSD::<SD>::from([0x0020_0000, 0x0003_8000, 0x000A_83C0, 0]);
+    assert!(csd.read_block_partial() && csd.write_block_partial());
+    let access = PartialAccess::new(&csd);
+    assert_eq!(access.block_len(), 1024);
+    let write = access.write(0x7F0, 16).unwrap();
+    assert_eq!(write.set_block_length.arg, 16);
+    assert_eq!((write.transfer.cmd, write.transfer.arg), (24, 0x7F0));
+    assert_eq!(
+        access.read(0x7F8, 16).err(),
+        Some(PartialError::CrossesBlock)
+    );
+    assert_eq!(access.read(0, 0).err(), Some(PartialError::InvalidLength));
+    assert_eq!(
+        access.read(0, 1025).err(),
+        Some(PartialError::InvalidLength)
+    );
+    // READ_BLK_MISALIGN set
+    let misalign = CSD::<SD>::from([0x0020_0000, 0x0003_8000, 0x000A_A3C0, 0]);
+    assert!(misalign.read_block_misalign() && !misalign.write_block_misalign());
+    let misaligned = PartialAccess::new(&misalign);
+    assert_eq!(misaligned.read(0x7F8, 16).unwrap().transfer.arg, 0x7F8);
+    assert_eq!(
+        misaligned.write(0x7F8, 16).err(),
+        Some(PartialError::CrossesBlock)
+    );
+    let end = csd.card_size() as u32;
+    assert_eq!(
+        access.read(end - 8, 16).err(),
+        Some(PartialError::OutOfRange)
+    );
+
+    // SDHC cards don't allow partial access
+    let sdhc = CSD::<SD>::from([171966712, 968064896, 1532559360, 1074659378]);
+    let access = PartialAccess::new(&sdhc);
+    assert_eq!(
+        access.read(0, 16).map_err(Error::from).err(),
+        Some(Error::Partial(PartialError::Unsupported))
+    );
+}
+
+#[test]
+fn test_error() {
+    use sdio_host::common_cmd::TransientError;
+    use sdio_host::sd::{CardStatus, StatusError};
+    use sdio_host::spi::{DataErrorToken, DataToken};
+    use sdio_host::Error;
+
+    fn check(status: u32) -> Result<(), Error> {
+        CardStatus::<SD>::from(status).check()?;
+        Ok(())
+    }
+    assert_eq!(check(0x900), Ok(()));
+    assert_eq!(
+        check(0x8000_0900),
+        Err(Error::Status(StatusError::OutOfRange))
+    );
+    assert_eq!(
+        check(0x1_0900),
+        Err(Error::Status(StatusError::CsdOverwrite))
+    );
+    assert_eq!(check(0x908), Err(Error::Status(StatusError::AkeSeqError)));
+    // Bit 3 is reserved in eMMC
+    let emmc = CardStatus::<sdio_host::emmc::EMMC>::from(0x908);
+    assert_eq!(emmc.check(), Ok(()));
+    // ERASE_RESET is informational
+    assert_eq!(check(0x2900), Ok(()));
+
+    let read = sdio_host::common_cmd::read_single_block(0);
+    let error = read
+        .check_response(&CardStatus::<SD>::from(0x4000_0900))
+        .unwrap_err();
+    assert_eq!((error.cmd, error.error), (17, StatusError::AddressError));
+    assert_eq!(Error::from(TransientError::Timeout), Error::Timeout);
+
+    assert_eq!(DataToken::from(0xFE), DataToken::Start);
+    assert_eq!(DataToken::from(0xFF), DataToken::Waiting);
+    assert_eq!(
+        DataToken::from(0x08),
+        DataToken::Error(DataErrorToken(0x08))
+    );
+    assert_eq!(DataErrorToken(0x08).status_error(), StatusError::OutOfRange);
+}
+
+#[test]
+fn test_lock_unlock() {
+    use sdio_host::common_cmd::{InvalidPassword, LockUnlock, LockUnlockData, LockUnlockStep};
+    use sdio_host::sd::{CardStatus, StatusError};
+
+    let data = LockUnlockData::set_password(b"old", b"new!").unwrap();
+    assert_eq!(data.as_bytes(), b"\x01\x07oldnew!");
+    let data = LockUnlockData::set_password_and_lock(b"", b"pw").unwrap();
+    assert_eq!(data.as_bytes(), b"\x05\x02pw");
+    assert_eq!(
+        LockUnlockData::lock(b"pw").unwrap().as_bytes(),
+        b"\x04\x02pw"
+    );
+    assert_eq!(LockUnlockData::force_erase().as_bytes(), [0x08]);
+    assert_eq!(
+        format!("{:?}", LockUnlockData::force_erase()),
+        "LockUnlockData { bytes: [8] }"
+    );
+    assert_eq!(LockUnlockData::lock(b"pw"), LockUnlockData::lock(b"pw"));
+    assert_ne!(LockUnlockData::lock(b"pw"), LockUnlockData::unlock(b"pw"));
+    assert_eq!(LockUnlockData::lock(&[0; 17]), Err(InvalidPassword));
+
+    // Wrong password: LOCK_UNLOCK_FAILED and still locked
+    let mut unlock = LockUnlock::new(0x1234, LockUnlockData::unlock(b"wrong").unwrap());
+    let transfer = CardStatus::<SD>::from(4 << 9);
+    let failed = CardStatus::<SD>::from(0x0100_0000 | 0x0200_0000 | 4 << 9);
+    let mut cmds = Vec::new();
+    while let LockUnlockStep::Send(cmd) = unlock.step() {
+        cmds.push((cmd.cmd, cmd.arg));
+        let status = if cmd.cmd == 13 { &failed } else { &transfer };
+        unlock.update(status);
+    }
+    assert_eq!(cmds, [(16, 7), (42, 0), (13, 0x1234_0000), (16, 512)]);
+    assert_eq!(
+        unlock.step(),
+        LockUnlockStep::Failed(StatusError::LockUnlockFailed)
+    );
+}
+
+#[test]
+fn test_raw_roundtrip() {
+    use sdio_host::sd::{CardStatus, SwitchStatus, CIC, RCA};
+
+    for card in SD_CARDS {
+        let cid = CID::<SD>::from(card.cid);
+        assert_eq!(cid.as_words(), card.cid);
+        assert_eq!(CID::<SD>::from(cid.to_bytes()).raw(), cid.raw());
+        let csd = CSD::<SD>::from(card.csd);
+        assert_eq!(csd.as_words(), card.csd);
+        assert_eq!(CSD::<SD>::from(csd.to_bytes()).raw(), csd.raw());
+        let ocr = OCR::<SD>::from(card.ocr);
+        assert_eq!((ocr.raw(), ocr.as_words()), (card.ocr, [card.ocr]));
+        let status = SDStatus::from(card.sd_status);
+        assert_eq!(status.as_words(), card.sd_status);
+        assert_eq!(status.raw(), &card.sd_status);
+        let scr = SCR::from(card.scr);
+        assert_eq!(scr.as_words(), card.scr);
+        assert_eq!(SCR::from(scr.as_words()).raw(), scr.raw());
+    }
+
+    let status = CardStatus::<SD>::from(0x900);
+    assert_eq!((status.raw(), status.as_words()), (0x900, [0x900]));
+    let rca = RCA::<SD>::from(0x1234_0500);
+    assert_eq!((rca.raw(), rca.as_words()), (0x1234_0500, [0x1234_0500]));
+    let cic = CIC::from(0x1AA);
+    assert_eq!((cic.raw(), cic.as_words()), (0x1AA, [0x1AA]));
+    let mut words = [0; 16];
+    words[15] = 0x8000_0001;
+    let switch = SwitchStatus::from(words);
+    assert_eq!((switch.raw(), switch.as_words()), (&words, words));
+    let ext = ext_csd(&[(192, 8)]);
+    assert_eq!(ext.raw()[48], 0x0800_0000);
+    assert_eq!(ext.raw()[..], ext.as_words()[..]);
+}
+
+#[test]
+fn test_max_power() {
+    use sdio_host::sd::SwitchStatus;
+
+    let card = &SD_CARDS[0];
+    let csd: CSD<SD> = card.csd.into();
+    let ocr: OCR<SD> = card.ocr.into();
+    assert_eq!(csd.max_power_mw(&ocr, None), 360);
+
+    let mut switch = [0; 16];
+    switch[15] = 200 << 16;
+    let switch = SwitchStatus::from(switch);
+    assert_eq!(csd.max_power_mw(&ocr, Some(&switch)), 720);
+
+    // Version 1.0, 80 mA maximum write current
+    let sdsc = CSD::<SD>::from(0x6_u128 << 50);
+    assert_eq!(sdsc.max_power_mw(&ocr, None), 288);
+
+    use sdio_host::sd_cmd::HostPower;
+    assert!(!HostPower::from_mw(360).xpc());
+    assert!(HostPower::from_mw(540).xpc());
+}
+
+#[test]
+fn test_voltage_window() {
+    use sdio_host::sd_cmd::{self, voltage_window};
+
+    assert_eq!(voltage_window(2700, 3600), Ok(sd_cmd::FULL_3V3_RANGE));
+    assert_eq!(voltage_window(3000, 3600), Ok(sd_cmd::VOLTAGE_3V0_TO_3V6));
+    assert_eq!(voltage_window(3150, 3450), Ok(0x060));
+    assert_eq!(voltage_window(1700, 1950), Err(VoltageError::NoOverlap));
+    let ocr = OCR::<SD>::from(u32::from(sd_cmd::VOLTAGE_3V2_TO_3V4) << 15);
+    assert_eq!(ocr.voltage_window_mv(), Some((3200, 3400)));
+}
+
+#[test]
+fn test_verify_bus_width() {
+    use sdio_host::sd::{verify_bus_width, BusWidthFault};
+
+    let card = &SD_CARDS[2];
+    let scr: SCR = card.scr.into();
+    let status: SDStatus = card.sd_status.into();
+    assert_eq!(verify_bus_width(&scr, &scr, &status), Ok(()));
+
+    // DAT2 floating high
+    let floating = SCR(scr.0 | 0x4444_4444_4444_4444);
+    assert_eq!(
+        verify_bus_width(&scr, &floating, &status),
+        Err(BusWidthFault::DataLines(0b0100))
+    );
+    let one_bit = SDStatus::from([0; 16]);
+    assert_eq!(
+        verify_bus_width(&scr, &scr, &one_bit),
+        Err(BusWidthFault::NotApplied)
+    );
+}
+
+#[test]
+fn test_probe_diagnosis() {
+    use sdio_host::detect::{Probe, ProbeDiagnosis, ProbeOutcome};
+
+    let diagnose = |outcomes: &[ProbeOutcome]| {
+        let mut probe = Probe::new();
+        outcomes.iter().for_each(|&o| probe.record(o));
+        probe.diagnosis()
+    };
+    use ProbeOutcome::*;
+    assert_eq!(diagnose(&[]), ProbeDiagnosis::Inconclusive);
+    assert_eq!(diagnose(&[Response, Response]), ProbeDiagnosis::Ok);
+    assert_eq!(diagnose(&[Timeout, Timeout]), ProbeDiagnosis::NoResponse);
+    assert_eq!(diagnose(&[Timeout, Garbled]), ProbeDiagnosis::CmdLineFault);
+    assert_eq!(diagnose(&[Response, Timeout]), ProbeDiagnosis::Intermittent);
+    assert_eq!(
+        diagnose(&[Response, Timeout, Crc]),
+        ProbeDiagnosis::SignalIntegrity
+    );
+}
+
+#[test]
+fn test_strictness() {
+    use sdio_host::power::{PowerUp, PowerUpStep};
+    use sdio_host::sd::{InvalidRegister, Strictness};
+
+    for card in SD_CARDS {
+        let csd: Result<CSD<SD>, _> = Strictness::Strict.parse(card.csd);
+        assert!(csd.is_ok());
+        let mut garbled = card.csd;
+        garbled[3] ^= 0x8000;
+        let csd: Result<CSD<SD>, _> = Strictness::Strict.parse(garbled);
+        assert_eq!(csd.err(), Some(InvalidRegister));
+        let csd: Result<CSD<SD>, _> = Strictness::Lenient.parse(garbled);
+        assert!(csd.is_ok());
+    }
+
+    let relaxed = PowerUp::new(400_000).ramp_up_ms(2).without_power_cycle();
+    assert_eq!(relaxed.steps().next(), Some(PowerUpStep::PowerOn { ms: 2 }));
+    let strict = relaxed.strictness(Strictness::Strict);
+    assert_eq!(strict.steps().nth(1), Some(PowerUpStep::PowerOn { ms: 35 }));
+}
+
+#[test]
+fn test_register_fields() {
+    use sdio_host::sd::Register;
+
+    let card = &SD_CARDS[2];
+    let csd: CSD<SD> = card.csd.into();
+    let c_size = csd.fields().find(|f| f.desc.name == "C_SIZE").unwrap();
+    assert_eq!((c_size.desc.msb, c_size.desc.lsb), (69, 48));
+    assert_eq!((c_size.value + 1) * 1024, csd.block_count());
+    assert_eq!(csd.fields().last().unwrap().desc.name, "CRC");
+
+    let cid: CID<SD> = card.cid.into();
+    let mid = cid.fields().next().unwrap();
+    assert_eq!(mid.value, u64::from(cid.manufacturer_id()));
+
+    let status: SDStatus = card.sd_status.into();
+    let au = status.fields().find(|f| f.desc.name == "AU_SIZE").unwrap();
+    assert_eq!(au.value, u64::from(status.allocation_unit_size()));
+    let width = status.fields().next().unwrap();
+    assert_eq!(width.value, 0b10);
+
+    let scr: SCR = card.scr.into();
+    let widths = scr.fields().find(|f| f.desc.name == "SD_BUS_WIDTHS");
+    assert_eq!(widths.unwrap().value, u64::from(scr.bus_widths()));
+}
+
+#[test]
+fn test_register_fields_decoded() {
+    use sdio_host::emmc::{ExtCSD, EMMC};
+    use sdio_host::sd::{CardStatus, Register, Value};
+
+    fn decoded<R: Register>(register: &R, name: &str) -> Value {
+        let field = register.fields().find(|f| f.desc.name == name);
+        field.unwrap().decoded()
+    }
+
+    let card = &SD_CARDS[1];
+    let cid: CID<SD> = card.cid.into();
+    assert_eq!(decoded(&cid, "PNM").to_string(), cid.product_name());
+    assert_eq!(decoded(&cid, "MID"), Value::Int(0x03));
+    let csd: CSD<SD> = card.csd.into();
+    assert_eq!(decoded(&csd, "CSD_STRUCTURE"), Value::Name("2.0"));
+    assert_eq!(decoded(&csd, "COPY"), Value::Flag(true));
+    let status = CardStatus::<SD>::from(0x900);
+    assert_eq!(decoded(&status, "CURRENT_STATE"), Value::Name("tran"));
+    assert_eq!(decoded(&status, "READY_FOR_DATA"), Value::Flag(true));
+
+    let cid = CID::<EMMC>::from(0x1501_0138_4754_4634_5207_1234_5678_9200);
+    assert_eq!(decoded(&cid, "CBX"), Value::Name("BGA"));
+    assert_eq!(decoded(&cid, "PNM").to_string(), "8GTF4R");
+    assert_eq!(decoded(&cid, "PSN"), Value::Int(0x1234_5678));
+    assert_eq!(cid.fields().last().unwrap().desc.name, "CRC");
+
+    let csd = CSD::<EMMC>::from((3u128 << 126) | (4 << 122) | (9 << 80));
+    assert_eq!(decoded(&csd, "CSD_STRUCTURE"), Value::Name("EXT_CSD"));
+    assert_eq!(decoded(&csd, "SPEC_VERS"), Value::Name("4.x"));
+    assert_eq!(decoded(&csd, "READ_BL_LEN"), Value::Int(9));
+
+    let ocr = OCR::<EMMC>::from(0xC0FF_8080);
+    assert_eq!(decoded(&ocr, "ACCESS_MODE"), Value::Name("Sector"));
+    assert_eq!(decoded(&ocr, "VDD_27_36"), Value::Int(0x1FF));
+    assert_eq!(decoded(&ocr, "VDD_170_195"), Value::Flag(true));
+
+    let status = CardStatus::<EMMC>::from(0x0000_0E80);
+    assert_eq!(decoded(&status, "CURRENT_STATE"), Value::Name("prg"));
+    assert_eq!(decoded(&status, "SWITCH_ERROR"), Value::Flag(true));
+
+    // SEC_COUNT is little endian over bytes 212 - 215
+    let ext: ExtCSD = ext_csd(&[
+        (212, 0x00),
+        (213, 0x00),
+        (214, 0xA4),
+        (215, 0x01),
+        (183, 6),
+        (192, 8),
+        (267, 2),
+        (268, 0x0B),
+    ]);
+    assert_eq!(decoded(&ext, "SEC_COUNT"), Value::Int(0x01A4_0000));
+    assert_eq!(decoded(&ext, "BUS_WIDTH"), Value::Name("8 bit DDR"));
+    assert_eq!(decoded(&ext, "EXT_CSD_REV"), Value::Name("5.1"));
+    assert_eq!(decoded(&ext, "PRE_EOL_INFO"), Value::Name("Warning"));
+    assert_eq!(
+        decoded(&ext, "DEVICE_LIFE_TIME_EST_TYP_A"),
+        Value::Name("Exceeded")
+    );
+    // Reserved values are not named
+    let reserved = ext_csd(&[(183, 3)]);
+    assert_eq!(decoded(&reserved, "BUS_WIDTH"), Value::Int(3));
+    let sec_count = ext.fields().find(|f| f.desc.name == "SEC_COUNT").unwrap();
+    assert_eq!(
+        (sec_count.desc.msb, sec_count.desc.lsb),
+        (215 * 8 + 7, 212 * 8)
+    );
+    assert_eq!(sec_count.value, u64::from(ext.sector_count()));
+}
+
+#[test]
+fn test_performance_enhancement() {
+    use sdio_host::sd_cmd::{read_extr_single, write_extr_single, PerformanceEnhancement};
+
+    let mut words = [0; 16];
+    // Cache, host initiated maintenance, queue depth 32
+    words[10] = 0xFE << 8;
+    let status = SDStatus::from(words);
+    assert!(status.cache_support());
+    assert!(status.host_maintenance_support());
+    assert!(!status.card_maintenance_support());
+    assert_eq!(status.command_queue_depth(), 32);
+
+    let perf = PerformanceEnhancement::new(2, 0x200);
+    let enable = perf.set_cache(true);
+    assert_eq!(enable.cmd().cmd, 49);
+    assert_eq!(enable.cmd().arg, 2 << 27 | (0x200 + 260) << 9);
+    assert_eq!(enable.block()[0], 1);
+    assert_eq!(perf.set_cache(false).block()[0], 0);
+    assert_eq!(perf.flush_status_cmd().arg, 2 << 27 | (0x200 + 261) << 9);
+
+    let maintenance = perf.set_maintenance(false, true);
+    assert_eq!(maintenance.cmd().arg, 2 << 27 | (0x200 + 257) << 9);
+    assert_eq!(maintenance.block()[0], 0b10);
+    assert_eq!(perf.set_maintenance(true, false).block()[0], 0b01);
+    let start = perf.start_maintenance();
+    assert_eq!(start.cmd().arg, 2 << 27 | (0x200 + 258) << 9);
+    assert_eq!(start.block()[0], 1);
+    let stop = perf.stop_maintenance();
+    assert_eq!(stop.cmd().arg, 2 << 27 | (0x200 + 259) << 9);
+    assert_eq!(stop.block()[0], 1);
+    let status = perf.maintenance_status_cmd();
+    assert_eq!((status.cmd, status.arg), (48, 2 << 27 | (0x200 + 258) << 9));
+
+    // Lengths are encoded minus one and limited to 1 ..= 512
+    assert_eq!(
+        read_extr_single(1, 0x1_2345, 512).arg,
+        1 << 27 | 0x1_2345 << 9 | 511
+    );
+    assert_eq!(write_extr_single(1, 0, 0).arg, 1 << 27);
+    assert_eq!(write_extr_single(1, 0, 600).arg, 1 << 27 | 511);
+}
+
+#[test]
+fn test_task_slots() {
+    use sdio_host::cq::{Task, TaskError, TaskSlotManager, TaskState};
+
+    let mut tasks = TaskSlotManager::new(2);
+    let task = Task {
+        read: false,
+        start: 100,
+        count: 4,
+    };
+    assert_eq!(tasks.queue(task), Ok(0));
+    assert_eq!(tasks.queue(task), Ok(1));
+    assert_eq!(tasks.queue(task), Err(TaskError::Full));
+    assert_eq!(tasks.update_qsr(0b100), Err(TaskError::InvalidQsr(0b100)));
+
+    tasks.update_qsr(0b10).unwrap();
+    assert_eq!(tasks.execute(0), Err(TaskError::InvalidTask(0)));
+    assert_eq!(tasks.execute(1).map(|t| t.start), Ok(100));
+    assert_eq!(tasks.state(1), TaskState::Executing);
+    // The executing task can't be ready again
+    assert!(tasks.update_qsr(0b11).is_err());
+    tasks.update_qsr(0b01).unwrap();
+    assert_eq!(tasks.execute(0), Err(TaskError::Busy));
+    tasks.complete(1).unwrap();
+    assert_eq!(tasks.state(1), TaskState::Free);
+    assert_eq!(tasks.in_use(), 1);
+
+    tasks.abort(0).unwrap();
+    assert_eq!(tasks.abort(0), Err(TaskError::InvalidTask(0)));
+    assert_eq!(tasks.in_use(), 0);
+    assert_eq!(tasks.state(40), TaskState::Free);
+}
+
+#[test]
+fn test_ext_csd_cmdq() {
+    use sdio_host::cq::TaskSlotManager;
+    use sdio_host::emmc_cmd::set_cmdq_mode;
+
+    let cmdq = ext_csd(&[(15, 1), (307, 31), (308, 1)]);
+    assert!(cmdq.cmdq_support());
+    assert!(cmdq.cmdq_enabled());
+    assert_eq!(cmdq.cmdq_depth(), 32);
+    assert_eq!(TaskSlotManager::new(cmdq.cmdq_depth()).depth(), 32);
+    // CMDQ_DEPTH is only valid with CMDQ_SUPPORT
+    assert_eq!(ext_csd(&[(307, 15)]).cmdq_depth(), 0);
+    assert_eq!(set_cmdq_mode(true).cmd().arg, 0x030F_0100);
+}
+
+#[test]
+fn test_crc() {
+    use sdio_host::crc::CrcBackend;
+    use sdio_host::spi::{CrcMode, DataCrcError};
+
+    #[derive(Default)]
+    struct Counting(u32);
+    impl CrcBackend for Counting {
+        fn crc16(&mut self, data: &[u8]) -> u16 {
+            self.0 += 1;
+            sdio_host::crc::crc16(data)
+        }
+    }
+
+    let mut backend = Counting::default();
+    let block = [0xFF; 512];
+    assert_eq!(CrcMode::On.block_crc_with(&mut backend, &block), 0x7FA1);
+    assert_eq!(
+        CrcMode::On.check_block_with(&mut backend, &block, 0),
+        Err(DataCrcError)
+    );
+    assert_eq!(
+        CrcMode::Off.check_block_with(&mut backend, &block, 0),
+        Ok(())
+    );
+    assert_eq!(backend.0, 2);
+    assert_eq!(backend.crc7(&[0x40, 0, 0, 0, 0]), 0x4A);
+
+    use sdio_host::crc::{crc16, BlockVerifier, VerifyError};
+    let data: Vec<u8> = (0..=255).chain(0..=255).collect();
+    let mut verifier = BlockVerifier::new(512);
+    assert_eq!(verifier.update(&data[..1]), 1);
+    assert_eq!(verifier.update(&data[1..300]), 299);
+    assert_eq!(verifier.verify(crc16(&data)), Err(VerifyError::Incomplete));
+    // Trailing bytes beyond the block are ignored
+    assert_eq!(verifier.update(&[&data[300..], &[0xAB][..]].concat()), 212);
+    assert!(verifier.is_complete());
+    assert_eq!(verifier.verify(crc16(&data)), Ok(()));
+}
+
+#[test]
+fn test_geometry() {
+    use sdio_host::sd::{FatType, Geometry, WriteAdvice};
+
+    let card = &SD_CARDS[2];
+    let csd: CSD<SD> = card.csd.into();
+    let status: SDStatus = card.sd_status.into();
+    let geometry = Geometry::sd(&csd, Some(&status));
+    assert_eq!(geometry.block_size, 512);
+    assert_eq!(geometry.block_count, 62333952);
+    assert_eq!(geometry.erase_sector_blocks, 1);
+    // 4MiB AU
+    assert_eq!(geometry.au_size, 8192);
+    assert_eq!(Geometry::sd(&csd, None).au_size, 0);
+
+    // 2GB and 4GB SDSC cards: C_SIZE 4095 and C_SIZE_MULT 7 with a
+    // READ_BL_LEN of 1024 and 2048 bytes
+    let sdsc = |read_bl_len: u128| CSD::<SD>::from(read_bl_len << 80 | 0xFFF << 62 | 7 << 47);
+    assert_eq!(Geometry::sd(&sdsc(10), None).block_count, 4 * 1024 * 1024);
+    assert_eq!(Geometry::sd(&sdsc(11), None).block_count, 8 * 1024 * 1024);
+
+    let params = geometry.format_parameters();
+    assert_eq!(params.fat_type, FatType::Fat32);
+    assert_eq!(params.sectors_per_cluster, 64);
+    assert_eq!(params.boundary_unit, 8192);
+    assert_eq!(params.partition_offset, 8192);
+
+    let sdxc = Geometry {
+        block_count: 64 * 1024 * 1024 * 2,
+        ..geometry
+    };
+    assert_eq!(sdxc.format_parameters().fat_type, FatType::ExFat);
+    assert_eq!(sdxc.format_parameters().boundary_unit, 32768);
+
+    // A C_SIZE 0xFFFF card is exactly 32GiB and already SDXC
+    let boundary = Geometry {
+        block_count: (0xFFFF + 1) * 1024,
+        ..geometry
+    };
+    assert_eq!(boundary.format_parameters().fat_type, FatType::ExFat);
+    let largest_sdhc = Geometry {
+        block_count: (0xFF5F + 1) * 1024,
+        ..geometry
+    };
+    assert_eq!(largest_sdhc.format_parameters().fat_type, FatType::Fat32);
+
+    assert_eq!(geometry.write_advice(100, 1), WriteAdvice::Single);
+    assert_eq!(
+        geometry.write_advice(100, 64),
+        WriteAdvice::PreErase { count: 64 }
+    );
+    assert_eq!(
+        geometry.write_advice(8000, 2 * 8192 + 300),
+        WriteAdvice::AuAligned {
+            head: 192,
+            aus: 2,
+            tail: 108
+        }
+    );
+
+    let ext_csd = ext_csd(&[(224, 2)]);
+    let geometry = sdio_host::emmc::Geometry::emmc(&ext_csd);
+    assert_eq!(geometry.erase_sector_blocks, 2048);
+    assert_eq!(geometry.au_size, 2048);
+}
+
+#[test]
+fn test_summary() {
+    let card = &SD_CARDS[1];
+    let cid: CID<SD> = card.cid.into();
+    let csd: CSD<SD> = card.csd.into();
+    let status: SDStatus = card.sd_status.into();
+    assert_eq!(csd.summary(&cid).to_string(), "SanDisk SU08G 7.4GiB SDHC");
+    assert_eq!(
+        csd.summary(&cid)
+            .status(&status)
+            .bus(BusWidth::Four, 25_000_000)
+            .to_string(),
+        "SanDisk SU08G 7.4GiB SDHC C4 4-bit @25MHz"
+    );
+
+    // C_SIZE 0xFF5F is the largest SDHC card, 0xFFFF is exactly 32GiB
+    let v2 = (1u128 << 126) | (9 << 80);
+    let sdhc = CSD::<SD>::from(v2 | (0xFF5F << 48));
+    assert!(sdhc.summary(&cid).to_string().ends_with("31.9GiB SDHC"));
+    let sdxc = CSD::<SD>::from(v2 | (0xFFFF << 48));
+    assert!(sdxc.summary(&cid).to_string().ends_with("32.0GiB SDXC"));
+}
+
+#[test]
+fn test_rca() {
+    use sdio_host::emmc::EMMC;
+    use sdio_host::sd::{CurrentState, RCA};
+
+    // Published RCA in the standby state, COM_CRC_ERROR set
+    let rca = RCA::<SD>::from(0xAAAA_8600);
+    assert_eq!(format!("{}", rca), "0xaaaa");
+    let status = rca.card_status();
+    assert_eq!(status.state(), CurrentState::Standby);
+    assert!(status.com_crc_error());
+    assert!(!status.illegal_command());
+    assert!(format!("{:?}", rca).contains("Standby"));
+
+    assert_eq!(format!("{}", RCA::<EMMC>::from(2u16)), "0x0002");
+    assert!(format!("{:?}", RCA::<EMMC>::from(2u16)).contains("0x0002"));
+
+    // Debug does not depend on the marker type
+    struct Marker;
+    let rca = RCA::<Marker>::from(0x0001_0900);
+    assert!(format!("{:?}", rca).contains("Transfer"));
+}
+
+#[test]
+fn test_emmc_vectors() {
+    use sdio_host::emmc::{ExtCSD, EMMC};
+    use sdio_host::test_vectors::EMMC_PARTS;
+
+    for part in EMMC_PARTS {
+        let expected = &part.expected;
+        let ext_csd: ExtCSD = part.ext_csd.into();
+        assert_eq!(ext_csd.version(), expected.version);
+        assert_eq!(ext_csd.sector_count(), expected.sector_count);
+        assert_eq!(ext_csd.device_timings().fastest(), expected.fastest_timing);
+        assert_eq!(
+            ext_csd.boot_partition_bytes(),
+            expected.boot_partition_bytes
+        );
+        assert_eq!(
+            ext_csd.rpmb_partition_bytes(),
+            expected.rpmb_partition_bytes
+        );
+        assert_eq!(ext_csd.erase_group_blocks(), expected.erase_group_blocks);
+        assert_eq!(ext_csd.cache_size_kib(), expected.cache_size_kib);
+        assert_eq!(ext_csd.cmdq_depth(), expected.cmdq_depth);
+
+        let cid: CID<EMMC> = part.cid.into();
+        assert_eq!(cid.manufacturer_id(), expected.manufacturer_id);
+        assert_eq!(cid.device_type(), expected.device_type);
+        assert_eq!(cid.product_name(), expected.product_name);
+        assert_eq!(cid.product_revision(), expected.product_revision);
+        assert_eq!(cid.serial(), expected.serial);
+        assert_eq!(cid.manufacturing_date(), expected.manufacturing_date);
+        assert_eq!(
+            cid.manufacturing_year(ext_csd.extended_csd_revision()),
+            expected.manufacturing_year
+        );
+        assert!(cid.is_plausible());
+
+        let csd: CSD<EMMC> = part.csd.into();
+        assert_eq!(csd.version(), expected.csd_version);
+        assert!(csd.is_plausible());
+
+        let ocr: OCR<EMMC> = part.ocr.into();
+        assert_eq!(ocr.access_mode() == 0b10, expected.sector_mode);
+        assert!(!ocr.is_busy());
+    }
+}
+
+#[cfg(feature = "recorder")]
+#[test]
+fn test_tracer() {
+    use sdio_host::common_cmd::{select_card, Stats};
+    use sdio_host::recorder::{Recorded, Recorder};
+    use sdio_host::trace::Tracer;
+
+    fn send<T: Tracer>(mut tracer: T) {
+        tracer.on_cmd(&select_card(0x1234));
+        tracer.on_resp(&[0x700]);
+    }
+    let mut buf = [Recorded::default(); 2];
+    let mut recorder = Recorder::new(&mut buf);
+    send(&mut recorder);
+    assert_eq!(recorder.commands(), &[Recorded::new(7, 0x1234_0000)]);
+
+    let mut stats = Stats::default();
+    send(&mut stats);
+    send(&mut stats);
+    assert_eq!(stats.commands, 2);
+}
+
+#[cfg(feature = "recorder")]
+#[test]
+fn test_recorder() {
+    use sdio_host::common_cmd::{LockUnlock, LockUnlockData, LockUnlockStep};
+    use sdio_host::recorder::{Recorded, Recorder};
+    use sdio_host::sd::CardStatus;
+
+    let mut buf = [Recorded::default(); 3];
+    let mut recorder = Recorder::new(&mut buf);
+    let data = LockUnlockData::unlock(b"pw").unwrap();
+    let mut lock = LockUnlock::new(0x1234, data);
+    let ok = CardStatus::<SD>::from(0x900);
+    let mut step = lock.step();
+    while let LockUnlockStep::Send(cmd) = step {
+        recorder.record(&cmd);
+        step = lock.update(&ok);
     }
+    assert_eq!(
+        recorder.commands(),
+        &[
+            Recorded::new(16, 4),
+            Recorded::new(42, 0),
+            Recorded::new(13, 0x1234_0000)
+        ]
+    );
+    assert!(recorder.overflowed());
+    recorder.clear();
+    assert!(recorder.commands().is_empty());
 }